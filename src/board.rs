@@ -0,0 +1,2913 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::cache::{get_conn, RedisPool};
+use crate::config::CONFIG;
+use crate::errors::{ApiError, CacheError};
+use crate::helpers::to_tokens;
+use crate::sync::{stats, sync, sync::EraIndex};
+use actix_web::web::Data;
+use chrono::Utc;
+use log::{error, warn};
+use redis::aio::Connection;
+use serde::{
+    de::{self, Deserializer},
+    Deserialize, Serialize,
+};
+use std::{collections::BTreeMap, str::FromStr};
+use subxt::sp_runtime::AccountId32;
+
+pub type ValidatorCache = BTreeMap<String, String>;
+
+pub type ValidatorEraCache = BTreeMap<String, String>;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Validator {
+    pub stash: String,
+    pub controller: String,
+    pub name: String,
+    pub email: String,
+    pub twitter: String,
+    pub web: String,
+    pub riot: String,
+    pub own_stake: u128,
+    pub own_stake_tokens: f64,
+    pub nominators: u32,
+    pub nominators_stake: u128,
+    pub nominators_stake_tokens: f64,
+    pub inclusion_rate: f32,
+    pub active_eras_count: u32,
+    pub total_eras_window: u32,
+    pub avg_reward_points: f64,
+    pub reward_points_stddev: f64,
+    pub commission: u32,
+    pub blocked: bool,
+    pub active: bool,
+    pub payee: String,
+    pub reward_staked: bool,
+    pub judgements: u32,
+    pub sub_accounts: u32,
+    pub est_apr: f64,
+    pub est_apy: f64,
+    pub slashes: u32,
+    pub last_slash_era: u32,
+    pub commission_volatility: f64,
+    pub oversubscribed: bool,
+    pub self_stake_ratio: f64,
+    /// Share of the validator total stake in the total stake of the active set
+    pub stake_share: f64,
+    pub first_active_era: u32,
+    pub eras_validating: u32,
+    pub first_active_era_lower_bound: bool,
+    pub recommended: bool,
+}
+
+impl From<ValidatorCache> for Validator {
+    fn from(data: ValidatorCache) -> Self {
+        let zero = "0".to_string();
+        Validator {
+            stash: data.get("stash").unwrap_or(&"".to_string()).to_string(),
+            controller: data
+                .get("controller")
+                .unwrap_or(&"".to_string())
+                .to_string(),
+            name: data.get("name").unwrap_or(&"".to_string()).to_string(),
+            email: data.get("email").unwrap_or(&"".to_string()).to_string(),
+            twitter: data.get("twitter").unwrap_or(&"".to_string()).to_string(),
+            web: data.get("web").unwrap_or(&"".to_string()).to_string(),
+            riot: data.get("riot").unwrap_or(&"".to_string()).to_string(),
+            own_stake: data
+                .get("own_stake")
+                .unwrap_or(&zero)
+                .parse::<u128>()
+                .unwrap_or_default(),
+            // Note: token-denominated values depend on the chain and are set by the handlers
+            own_stake_tokens: 0.0,
+            nominators: data
+                .get("nominators")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            nominators_stake: data
+                .get("nominators_stake")
+                .unwrap_or(&zero)
+                .parse::<u128>()
+                .unwrap_or_default(),
+            nominators_stake_tokens: 0.0,
+            inclusion_rate: data
+                .get("inclusion_rate")
+                .unwrap_or(&zero)
+                .parse::<f32>()
+                .unwrap_or_default(),
+            active_eras_count: data
+                .get("active_eras_count")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            total_eras_window: data
+                .get("total_eras_window")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            avg_reward_points: data
+                .get("avg_reward_points")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            reward_points_stddev: data
+                .get("reward_points_stddev")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            commission: data
+                .get("commission")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            blocked: data
+                .get("blocked")
+                .unwrap_or(&zero)
+                .parse::<bool>()
+                .unwrap_or_default(),
+            active: data
+                .get("active")
+                .unwrap_or(&zero)
+                .parse::<bool>()
+                .unwrap_or_default(),
+            payee: data.get("payee").unwrap_or(&"".to_string()).to_string(),
+            // Note: reward is considered staked when it compounds, either added to the bonded
+            // stake (Staked) or paid to the stash from where it can be bonded again (Stash),
+            // validators cached before the payee was synced keep the flag previously stored
+            reward_staked: match data.get("payee") {
+                Some(payee) => is_compounding_payee(payee),
+                None => data
+                    .get("reward_staked")
+                    .unwrap_or(&zero)
+                    .parse::<bool>()
+                    .unwrap_or_default(),
+            },
+            judgements: data
+                .get("judgements")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            sub_accounts: data
+                .get("sub_accounts")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            est_apr: data
+                .get("est_apr")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            est_apy: data
+                .get("est_apy")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            slashes: data
+                .get("slashes")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            last_slash_era: data
+                .get("last_slash_era")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            commission_volatility: data
+                .get("commission_volatility")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            oversubscribed: data
+                .get("oversubscribed")
+                .unwrap_or(&zero)
+                .parse::<bool>()
+                .unwrap_or_default(),
+            self_stake_ratio: data
+                .get("self_stake_ratio")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            stake_share: data
+                .get("stake_share")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            first_active_era: data
+                .get("first_active_era")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            eras_validating: data
+                .get("eras_validating")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            first_active_era_lower_bound: data
+                .get("first_active_era_lower_bound")
+                .unwrap_or(&zero)
+                .parse::<bool>()
+                .unwrap_or_default(),
+            recommended: data
+                .get("recommended")
+                .unwrap_or(&zero)
+                .parse::<bool>()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Validator {
+    /// Set the token-denominated stakes, for display only
+    pub fn with_tokens(mut self, token_decimals: u8) -> Self {
+        self.own_stake_tokens = to_tokens(self.own_stake, token_decimals);
+        self.nominators_stake_tokens = to_tokens(self.nominators_stake, token_decimals);
+        self
+    }
+}
+
+/// Reward destinations in which the reward compounds, rewards paid to the controller
+/// or to any other account are not restaked by the stash
+fn is_compounding_payee(payee: &str) -> bool {
+    payee == "Staked" || payee == "Stash"
+}
+
+/// Number of validators at the top of a board with the given total, rounded up
+fn recommended_count(total: usize, top_percent: u32) -> usize {
+    let top_percent = top_percent.min(100) as usize;
+    (total * top_percent + 99) / 100
+}
+
+/// Generate the board defined by the recommended weights for the active era and cache
+/// the validators at the top of it, returning the number of validators recommended.
+/// Note: the board is regenerated on every call, so that a sync in the same era does
+/// not keep the board scored with the data synced before
+pub async fn generate_recommended_board(cache: Data<RedisPool>) -> Result<usize, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let (weights, _) = parse_preset(&CONFIG.recommended_weights, "")?;
+    let board_name = formula_board_name(
+        get_board_name(&weights, None, Norm::default()),
+        Formula::default(),
+    );
+    invalidate_board(era_index, &board_name, cache.clone()).await?;
+    generate_board_scores(
+        era_index,
+        &weights,
+        Norm::default(),
+        Formula::default(),
+        false,
+        false,
+        cache.clone(),
+    )
+    .await?;
+
+    let key = sync::Key::BoardAtEra(era_index, board_name);
+    let total: usize = redis::cmd("ZCARD")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let n = recommended_count(total, CONFIG.recommended_top_percent);
+    let stashes = get_validators_stashes(key, 0, n as Quantity, None, cache.clone()).await?;
+
+    // The set is replaced at once so that lookups never see a partial set
+    let key_recommended =
+        sync::Key::BoardAtEra(era_index, sync::BOARD_RECOMMENDED_VALIDATORS.to_string());
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .cmd("DEL")
+        .arg(key_recommended.clone())
+        .ignore();
+    if !stashes.is_empty() {
+        pipe.cmd("SADD")
+            .arg(key_recommended)
+            .arg(stashes.clone())
+            .ignore();
+    }
+    let _: () = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(stashes.len())
+}
+
+/// Generate the board defined by the default weights at the active era, so that the
+/// rank history of the default board covers every era synced and not only the eras
+/// it happened to be requested. Returns the number of validators in the board.
+/// Note: the board is regenerated on every call, as the recommended board
+pub async fn generate_default_board(cache: Data<RedisPool>) -> Result<usize, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let weights = default_board_weights();
+    let board_name = get_board_name(&weights, None, Norm::MinMax);
+    invalidate_board(era_index, &board_name, cache.clone()).await?;
+    generate_board_scores(
+        era_index,
+        &weights,
+        Norm::MinMax,
+        Formula::Sum,
+        false,
+        false,
+        cache.clone(),
+    )
+    .await?;
+
+    let total: usize = redis::cmd("ZCARD")
+        .arg(sync::Key::BoardAtEra(era_index, board_name))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(total)
+}
+
+type BoardLimitsCache = BTreeMap<String, String>;
+
+/// Value of a cached limit, or the default when missing
+fn cached_limit<T: std::str::FromStr>(data: &BoardLimitsCache, field: &str, default: T) -> T {
+    data.get(field)
+        .and_then(|value| value.parse::<T>().ok())
+        .unwrap_or(default)
+}
+
+#[derive(Debug, Serialize, PartialEq, Copy, Clone)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for Interval {
+    fn default() -> Interval {
+        Interval {
+            min: 0.0_f64,
+            max: 0.0_f64,
+        }
+    }
+}
+
+impl Interval {
+    /// Convert an interval defined in percent (0-100) into commission planck
+    pub fn percent_to_planck(&self) -> Interval {
+        let scale = COMMISSION_PLANCK as f64 / 100.0;
+        Interval {
+            min: self.min * scale,
+            max: self.max * scale,
+        }
+    }
+
+    /// Interval without bounds, resolved later to the discovered board limits
+    fn unbounded() -> Interval {
+        Interval {
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+        }
+    }
+
+    /// Replace any unbounded side by the respective side of the board interval
+    fn bounded_by(&self, board: &Interval) -> Interval {
+        Interval {
+            min: if self.min.is_infinite() {
+                board.min
+            } else {
+                self.min
+            },
+            max: if self.max.is_infinite() {
+                board.max
+            } else {
+                self.max
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.min, self.max)
+    }
+}
+
+/// Interval of stakes in planck, kept exact since stakes don't fit in a double
+#[derive(Debug, Serialize, PartialEq, Copy, Clone, Default)]
+pub struct StakeInterval {
+    pub min: u128,
+    pub max: u128,
+}
+
+impl StakeInterval {
+    /// Planck interval of the requested interval, any unbounded side set by the board interval
+    fn from_interval(interval: &Interval, board: &StakeInterval) -> StakeInterval {
+        StakeInterval {
+            min: if interval.min.is_infinite() {
+                board.min
+            } else {
+                interval.min as u128
+            },
+            max: if interval.max.is_infinite() {
+                board.max
+            } else {
+                interval.max as u128
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for StakeInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.min, self.max)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BoardLimits {
+    pub inclusion_rate: Interval,
+    pub commission: Interval,
+    pub nominators: Interval,
+    pub avg_reward_points: Interval,
+    pub reward_staked: Interval,
+    pub active: Interval,
+    pub own_stake: StakeInterval,
+    pub total_stake: StakeInterval,
+    pub judgements: Interval,
+    pub sub_accounts: Interval,
+    pub slashes: Interval,
+    pub commission_volatility: Interval,
+    pub self_stake_ratio: Interval,
+}
+
+impl Default for BoardLimits {
+    fn default() -> BoardLimits {
+        BoardLimits {
+            inclusion_rate: Interval::default(),
+            commission: Interval::default(),
+            nominators: Interval::default(),
+            avg_reward_points: Interval::default(),
+            reward_staked: Interval::default(),
+            active: Interval::default(),
+            own_stake: StakeInterval::default(),
+            total_stake: StakeInterval::default(),
+            judgements: Interval::default(),
+            sub_accounts: Interval::default(),
+            slashes: Interval::default(),
+            commission_volatility: Interval::default(),
+            self_stake_ratio: Interval::default(),
+        }
+    }
+}
+
+impl std::fmt::Display for BoardLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Note: the position of the traits is important, it should be the same as the position in weights
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.inclusion_rate.to_string(),
+            self.commission.to_string(),
+            self.nominators.to_string(),
+            self.avg_reward_points.to_string(),
+            self.reward_staked.to_string(),
+            self.active.to_string(),
+            self.own_stake.to_string(),
+            self.total_stake.to_string(),
+            self.judgements.to_string(),
+            self.sub_accounts.to_string(),
+            self.slashes.to_string(),
+            self.commission_volatility.to_string(),
+            self.self_stake_ratio.to_string()
+        )
+    }
+}
+
+impl BoardLimits {
+    /// Limits of the requested intervals, any unbounded side set by the respective board limit
+    fn from_intervals(data: &Intervals, board: &BoardLimits) -> BoardLimits {
+        let interval = |i: usize| *data.get(i).unwrap_or(&Interval::unbounded());
+        BoardLimits {
+            inclusion_rate: interval(0).bounded_by(&board.inclusion_rate),
+            commission: interval(1).bounded_by(&board.commission),
+            nominators: interval(2).bounded_by(&board.nominators),
+            avg_reward_points: interval(3).bounded_by(&board.avg_reward_points),
+            reward_staked: interval(4).bounded_by(&board.reward_staked),
+            active: interval(5).bounded_by(&board.active),
+            own_stake: StakeInterval::from_interval(&interval(6), &board.own_stake),
+            total_stake: StakeInterval::from_interval(&interval(7), &board.total_stake),
+            judgements: interval(8).bounded_by(&board.judgements),
+            sub_accounts: interval(9).bounded_by(&board.sub_accounts),
+            slashes: interval(10).bounded_by(&board.slashes),
+            commission_volatility: interval(11).bounded_by(&board.commission_volatility),
+            self_stake_ratio: interval(12).bounded_by(&board.self_stake_ratio),
+        }
+    }
+}
+
+impl From<BoardLimitsCache> for BoardLimits {
+    fn from(data: BoardLimitsCache) -> Self {
+        let default_min = 0.0_f64;
+        let default_max = 100.0_f64;
+        BoardLimits {
+            inclusion_rate: Interval {
+                min: 0.0_f64,
+                max: 1.0_f64,
+            },
+            commission: Interval {
+                min: 0.0_f64,
+                max: COMMISSION_PLANCK as f64,
+            },
+            nominators: Interval {
+                min: 0.0_f64,
+                max: cached_limit(
+                    &data,
+                    "max_nominators",
+                    sync::DEFAULT_MAX_NOMINATOR_REWARDED_PER_VALIDATOR as f64,
+                ),
+            },
+            avg_reward_points: Interval {
+                min: cached_limit(&data, "min_avg_reward_points", default_min),
+                max: cached_limit(&data, "max_avg_reward_points", default_max),
+            },
+            reward_staked: Interval {
+                min: 0.0_f64,
+                max: 1.0_f64,
+            },
+            active: Interval {
+                min: 0.0_f64,
+                max: 1.0_f64,
+            },
+            own_stake: StakeInterval {
+                min: cached_limit(&data, "min_own_stake", 0),
+                max: cached_limit(&data, "max_own_stake", 100),
+            },
+            total_stake: StakeInterval {
+                min: cached_limit(&data, "min_total_stake", 0),
+                max: cached_limit(&data, "max_total_stake", 100),
+            },
+            judgements: Interval {
+                min: cached_limit(&data, "min_judgements", default_min),
+                max: cached_limit(&data, "max_judgements", default_max),
+            },
+            sub_accounts: Interval {
+                min: cached_limit(&data, "min_sub_accounts", default_min),
+                max: cached_limit(&data, "max_sub_accounts", default_max),
+            },
+            slashes: Interval {
+                min: cached_limit(&data, "min_slashes", default_min),
+                max: cached_limit(&data, "max_slashes", default_max),
+            },
+            commission_volatility: Interval {
+                min: cached_limit(&data, "min_commission_volatility", default_min),
+                max: cached_limit(&data, "max_commission_volatility", default_max),
+            },
+            self_stake_ratio: Interval {
+                min: cached_limit(&data, "min_self_stake_ratio", default_min),
+                max: cached_limit(&data, "max_self_stake_ratio", default_max),
+            },
+        }
+    }
+}
+
+/// Strategy used to calculate the board limits that normalize every criterion
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Norm {
+    /// Minimum and maximum values found
+    MinMax,
+    /// 95% confidence interval, so that outliers don't squash everyone else's score
+    Ci95,
+}
+
+impl Default for Norm {
+    fn default() -> Norm {
+        Norm::MinMax
+    }
+}
+
+impl std::fmt::Display for Norm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MinMax => write!(f, "minmax"),
+            Self::Ci95 => write!(f, "ci95"),
+        }
+    }
+}
+
+/// Formula used to combine the partial scores into the total score of a validator
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Formula {
+    /// Weighted sum, a high score in one criterion can make up for a low one in another
+    Sum,
+    /// Weighted geometric mean, a low score in any weighted criterion pulls the total down
+    GeoMean,
+}
+
+impl Default for Formula {
+    fn default() -> Formula {
+        Formula::Sum
+    }
+}
+
+impl std::fmt::Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sum => write!(f, "sum"),
+            Self::GeoMean => write!(f, "geomean"),
+        }
+    }
+}
+
+// TODO: get this constants from chain
+pub const COMMISSION_PLANCK: u32 = 1000000000;
+
+/// Weight can be any value in a 10-point scale. Higher the weight more important
+/// is the criteria to the user
+pub type Weight = u32;
+
+/// Highest weight, greater weights are capped to it
+pub const MAX_WEIGHT: Weight = 9;
+
+/// Weights represent an array of points, where the points in each position represents
+/// the weight for the respective criteria
+/// Position 0 - Higher Inclusion rate is preferrable
+/// Position 1 - Lower Commission is preferrable
+/// Position 2 - Lower Nominators is preferrable (limit to MaxNominatorRewardedPerValidator -> oversubscribed)
+/// Position 3 - Higher Reward Points is preferrable
+/// Position 4 - If reward is staked is preferrable
+/// Position 5 - If in active set is preferrable
+/// Position 6 - Higher own stake is preferrable
+/// Position 7 - Lower total stake is preferrable
+/// Position 8 - Higher number of Reasonable or KnownGood judgements is preferrable
+/// Position 9 - Lower number of sub-accounts is preferrable
+/// Position 10 - Lower number of slashes is preferrable
+/// Position 11 - Lower commission volatility is preferrable
+/// Position 12 - Higher self-stake ratio is preferrable
+pub type Weights = Vec<Weight>;
+
+pub type Intervals = Vec<Interval>;
+
+/// Current weighs capacity
+pub const WEIGHTS_CAPACITY: usize = 13;
+
+/// Default weights for the positions not defined by the user.
+/// Note: positions added after the first 10 criteria default to 0, so that boards
+/// requested with the previous capacity keep the same scores
+pub const DEFAULT_WEIGHTS: [&str; WEIGHTS_CAPACITY] = [
+    "5", "5", "5", "5", "5", "5", "5", "5", "5", "5", "0", "0", "0",
+];
+
+/// Current limits capacity
+pub const INTERVALS_CAPACITY: usize = 13;
+
+/// Positions of the intervals given in percent (0-100), e.g. commission 5:10 means 5-10%
+pub const PERCENT_INTERVALS: [usize; 1] = [1];
+
+// Number of elements to return
+pub type Quantity = u32;
+
+/// Weights and intervals of a stored preset, parsed as the board params
+#[derive(Debug, Deserialize)]
+struct PresetParams {
+    #[serde(default = "default_weights")]
+    #[serde(deserialize_with = "parse_weights")]
+    w: Weights,
+    #[serde(default = "default_intervals")]
+    #[serde(deserialize_with = "parse_intervals")]
+    i: Intervals,
+}
+
+/// Parse the weights and intervals of a preset, given as in the w and i params
+pub fn parse_preset(weights: &str, intervals: &str) -> Result<(Weights, Intervals), ApiError> {
+    let params: PresetParams =
+        serde_json::from_value(serde_json::json!({ "w": weights, "i": intervals }))
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok((params.w, params.i))
+}
+
+pub fn default_weights() -> Weights {
+    vec![0; WEIGHTS_CAPACITY]
+}
+
+pub fn default_intervals() -> Intervals {
+    vec![]
+}
+
+pub fn parse_weights<'de, D>(d: D) -> Result<Weights, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let x: Option<String> = Deserialize::deserialize(d)?;
+    let weights_as_csv = x.unwrap_or("".to_string());
+
+    let mut weights_as_strvec: Vec<&str> = weights_as_csv.split(",").collect();
+    weights_as_strvec.truncate(WEIGHTS_CAPACITY);
+    weights_as_strvec.extend_from_slice(&DEFAULT_WEIGHTS[weights_as_strvec.len()..]);
+
+    let mut weights: Weights = Vec::with_capacity(WEIGHTS_CAPACITY);
+    for i in 0..WEIGHTS_CAPACITY {
+        let weight: u32 = match weights_as_strvec[i].trim().parse() {
+            Ok(weight) => weight,
+            Err(_) => {
+                let msg = format!(
+                    "Parameter w has an invalid weight '{}' at position {}. Weights must be a comma-separated list of values between 0-9, e.g. w=5,3,2,1,0,9,8,7,6,5",
+                    weights_as_strvec[i], i
+                );
+                return Err(de::Error::custom(msg));
+            }
+        };
+        let weight = if weight > MAX_WEIGHT {
+            MAX_WEIGHT
+        } else {
+            weight
+        };
+        weights.push(weight);
+    }
+    Ok(weights)
+}
+
+pub fn parse_intervals<'de, D>(d: D) -> Result<Intervals, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let x: Option<String> = Deserialize::deserialize(d)?;
+    let intervals_as_csv = x.unwrap_or("".to_string());
+    if intervals_as_csv.trim().is_empty() {
+        return Ok(default_intervals());
+    }
+    let mut intervals_as_strvec: Vec<&str> = intervals_as_csv.split(",").collect();
+    intervals_as_strvec.resize(INTERVALS_CAPACITY, "");
+    let mut intervals: Intervals = Vec::with_capacity(INTERVALS_CAPACITY);
+    for i in 0..INTERVALS_CAPACITY {
+        match parse_interval(intervals_as_strvec[i]) {
+            Some(interval) if PERCENT_INTERVALS.contains(&i) => {
+                intervals.push(interval.percent_to_planck())
+            }
+            Some(interval) => intervals.push(interval),
+            None => {
+                let msg = format!(
+                    "Parameter i has an invalid interval '{}' at position {}. Intervals must be a comma-separated list of min:max values, e.g. i=0.5:1,0:10,... with the commission in percent. Either side can be left empty (or set to -1) to use the board limit.",
+                    intervals_as_strvec[i], i
+                );
+                return Err(de::Error::custom(msg));
+            }
+        }
+    }
+    Ok(intervals)
+}
+
+/// Interval side value that stands for unbounded, no criterion has negative values
+const UNBOUNDED_SIDE: f64 = -1.0;
+
+/// Parse an interval defined as min:max. A missing side, a side set to -1 (or a missing
+/// interval) is unbounded and it is resolved to the discovered board limit when generating
+/// the board, e.g. `10000:` or `10000:-1` only filter by the lower bound
+fn parse_interval(interval: &str) -> Option<Interval> {
+    let interval_as_strvec: Vec<&str> = interval.trim().split(":").collect();
+    if interval_as_strvec.len() > 2 {
+        return None;
+    }
+    let parse_side = |side: Option<&&str>, unbounded: f64| -> Option<f64> {
+        match side.map(|x| x.trim()) {
+            None | Some("") => Some(unbounded),
+            Some(x) => match x.parse::<f64>().ok().filter(|v| !v.is_nan())? {
+                v if v == UNBOUNDED_SIDE => Some(unbounded),
+                v => Some(v),
+            },
+        }
+    };
+    Some(Interval {
+        min: parse_side(interval_as_strvec.get(0), f64::NEG_INFINITY)?,
+        max: parse_side(interval_as_strvec.get(1), f64::INFINITY)?,
+    })
+}
+
+/// Board name defined by weights, intervals and normalization strategy.
+/// Note: minmax boards are not prefixed so that they keep the names used before
+/// the normalization strategy was selectable, e.g. `5,5,5|0:10` or `ci95:5,5,5|0:10`
+pub fn get_board_name(weights: &Weights, intervals: Option<&Intervals>, norm: Norm) -> String {
+    let prefix = match norm {
+        Norm::MinMax => String::new(),
+        _ => format!("{}:", norm),
+    };
+    match intervals {
+        Some(i) => {
+            if i.is_empty() {
+                return format!("{}{}", prefix, weights_to_string(weights));
+            }
+            format!(
+                "{}{}|{}",
+                prefix,
+                weights_to_string(weights),
+                intervals_to_string(i),
+            )
+        }
+        None => format!("{}{}", prefix, weights_to_string(weights)),
+    }
+}
+
+/// Name of a board scored by the formula.
+/// Note: boards scored by the sum are not prefixed so that they keep the names used before
+/// the formula was selectable, e.g. `5,5,5` or `geomean:5,5,5`
+pub fn formula_board_name(board_name: String, formula: Formula) -> String {
+    match formula {
+        Formula::Sum => board_name,
+        _ => format!("{}:{}", formula, board_name),
+    }
+}
+
+/// Split the formula from a board name, e.g. `geomean:ci95:5,5,5` -> (GeoMean, `ci95:5,5,5`)
+pub fn split_board_formula(board_name: &str) -> (Formula, &str) {
+    match board_name.strip_prefix(&format!("{}:", Formula::GeoMean)) {
+        Some(name) => (Formula::GeoMean, name),
+        None => (Formula::Sum, board_name),
+    }
+}
+
+/// Name of a board with the scores smoothed over eras, e.g. `smooth:5,5,5`
+pub fn smoothed_board_name(board_name: String, smooth: bool) -> String {
+    if smooth {
+        return format!("smooth:{}", board_name);
+    }
+    board_name
+}
+
+/// Split the smoothing from a board name, e.g. `smooth:geomean:5,5,5` -> (true, `geomean:5,5,5`)
+pub fn split_board_smooth(board_name: &str) -> (bool, &str) {
+    match board_name.strip_prefix("smooth:") {
+        Some(name) => (true, name),
+        None => (false, board_name),
+    }
+}
+
+/// Name of a board generated with the data of a past era, so that it is never mistaken
+/// for the board generated at that era with the data available at the time
+pub fn historical_board_name(board_name: String, historical: bool) -> String {
+    if historical {
+        return format!("hist:{}", board_name);
+    }
+    board_name
+}
+
+/// Name of the board whose limits are shared by every board defined by the same weights,
+/// i.e. without intervals nor smoothing, e.g. `hist:geomean:5,5,5`
+pub fn limits_board_name(
+    weights: &Weights,
+    norm: Norm,
+    formula: Formula,
+    historical: bool,
+) -> String {
+    historical_board_name(
+        formula_board_name(get_board_name(weights, None, norm), formula),
+        historical,
+    )
+}
+
+/// Split the normalization strategy from a board name, e.g. `ci95:5,5,5` -> (Ci95, `5,5,5`)
+pub fn split_board_norm(board_name: &str) -> (Norm, &str) {
+    match board_name.strip_prefix(&format!("{}:", Norm::Ci95)) {
+        Some(name) => (Norm::Ci95, name),
+        None => (Norm::MinMax, board_name),
+    }
+}
+
+pub fn weights_to_string(weights: &Weights) -> String {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            if i == 0 {
+                return x.to_string();
+            }
+            format!(",{}", x)
+        })
+        .collect()
+}
+
+pub fn intervals_to_string(intervals: &Intervals) -> String {
+    intervals
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            if i == 0 {
+                return format!("{}", x);
+            }
+            format!(",{}", x)
+        })
+        .collect()
+}
+
+/// Normalize value between min and max, values out of the interval are clamped to it.
+/// If min and max are the same there is nothing to tell values apart, so 0 is given
+fn normalize_value(value: f64, min: f64, max: f64) -> f64 {
+    if max <= min {
+        return 0.0;
+    }
+    (value.max(min).min(max) - min) / (max - min)
+}
+
+/// Reverse normalization
+fn reverse_normalize_value(value: f64, min: f64, max: f64) -> f64 {
+    1.0 - normalize_value(value, min, max)
+}
+
+/// Normalize commission between 0 - 1
+fn normalize_commission(commission: u32) -> f64 {
+    (commission as f64 / COMMISSION_PLANCK as f64) as f64
+}
+
+/// Reverse Normalize commission between 0 - 1
+/// lower commission the better
+fn reverse_normalize_commission(commission: u32, min: f64, max: f64) -> f64 {
+    reverse_normalize_value(
+        normalize_commission(commission),
+        (min / COMMISSION_PLANCK as f64) as f64,
+        (max / COMMISSION_PLANCK as f64) as f64,
+    )
+}
+
+/// Check if the commission (in planck) is within the interval, bounds included
+pub fn is_commission_within(commission: u32, interval: &Interval) -> bool {
+    (commission as f64) >= interval.min && (commission as f64) <= interval.max
+}
+
+/// Check if the stake (in planck) is within the interval, bounds included
+fn is_stake_within(stake: u128, interval: &StakeInterval) -> bool {
+    stake >= interval.min && stake <= interval.max
+}
+
+/// Normalize stake (in planck) between 0 - 1. The distance to the min is calculated
+/// in planck, so that nearly equal stakes keep distinct scores
+fn normalize_stake(stake: u128, interval: &StakeInterval) -> f64 {
+    if interval.max <= interval.min {
+        return 0.0;
+    }
+    (stake.max(interval.min).min(interval.max) - interval.min) as f64
+        / (interval.max - interval.min) as f64
+}
+
+/// Reverse stake normalization
+fn reverse_normalize_stake(stake: u128, interval: &StakeInterval) -> f64 {
+    1.0 - normalize_stake(stake, interval)
+}
+
+/// Normalize boolean flag between 0 - 1
+fn normalize_flag(flag: bool) -> f64 {
+    (flag as u32) as f64
+}
+
+async fn calculate_avg_points(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let v: Vec<(EraIndex, u32)> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(0, name.to_string()))
+        .arg("-inf")
+        .arg("+inf")
+        .arg("BYSCORE")
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    // Convert Vec<(EraIndex, u32)> to Vec<u32> to easily calculate average
+    let scores: Vec<u32> = v.into_iter().map(|(_, score)| score).collect();
+    let avg = stats::mean(&scores);
+    Ok(avg)
+}
+
+async fn calculate_confidence_interval_95(
+    cache: Data<RedisPool>,
+    name: &str,
+) -> Result<(f64, f64), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(0, name.to_string()))
+        .arg("-inf")
+        .arg("+inf")
+        .arg("BYSCORE")
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    // Convert Vec<(EraIndex, u32)> to Vec<u32> to easily make the calculation
+    let scores: Vec<f64> = v.into_iter().map(|(_, score)| score).collect();
+    let min_max = stats::confidence_interval_95(&scores);
+    Ok(min_max)
+}
+
+async fn calculate_min_max_interval(
+    cache: Data<RedisPool>,
+    name: &str,
+) -> Result<(f64, f64), ApiError> {
+    let max = calculate_max_limit(cache.clone(), name).await?;
+    let min = calculate_min_limit(cache.clone(), name).await?;
+    Ok((min, max))
+}
+
+async fn calculate_min_limit(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(0, name.to_string()))
+        .arg("-inf")
+        .arg("+inf")
+        .arg("BYSCORE")
+        .arg("LIMIT")
+        .arg("0")
+        .arg("1")
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    if v.len() == 0 {
+        return Ok(0.0);
+    }
+    Ok(v[0].1)
+}
+
+async fn calculate_max_limit(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(0, name.to_string()))
+        .arg("+inf")
+        .arg("-inf")
+        .arg("BYSCORE")
+        .arg("REV")
+        .arg("LIMIT")
+        .arg("0")
+        .arg("1")
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    if v.len() == 0 {
+        return Ok(0.0);
+    }
+    Ok(v[0].1)
+}
+
+/// Calculate the interval used to normalize the values of a board
+async fn calculate_interval(
+    cache: Data<RedisPool>,
+    name: &str,
+    norm: Norm,
+) -> Result<(f64, f64), ApiError> {
+    match norm {
+        Norm::MinMax => calculate_min_max_interval(cache, name).await,
+        Norm::Ci95 => calculate_confidence_interval_95(cache, name).await,
+    }
+}
+
+/// Interval used to normalize a list of values
+fn values_interval(values: &Vec<f64>, norm: Norm) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    match norm {
+        Norm::MinMax => (
+            values.iter().cloned().fold(f64::INFINITY, f64::min),
+            values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        ),
+        Norm::Ci95 => stats::confidence_interval_95(values),
+    }
+}
+
+/// Interval used to normalize a trait of the validators
+fn validators_interval(
+    validators: &[&Validator],
+    norm: Norm,
+    value: fn(&Validator) -> f64,
+) -> (f64, f64) {
+    let values: Vec<f64> = validators.iter().map(|v| value(v)).collect();
+    values_interval(&values, norm)
+}
+
+/// Interval used to normalize a list of stakes, kept in planck
+fn stakes_interval(stakes: &Vec<u128>, norm: Norm) -> StakeInterval {
+    match norm {
+        Norm::MinMax => StakeInterval {
+            min: stats::min_u128(stakes),
+            max: stats::max_u128(stakes),
+        },
+        Norm::Ci95 => {
+            let values: Vec<f64> = stakes.iter().map(|stake| *stake as f64).collect();
+            let (min, max) = stats::confidence_interval_95(&values);
+            StakeInterval {
+                min: min as u128,
+                max: max as u128,
+            }
+        }
+    }
+}
+
+/// Calculate the interval used to normalize the values of a stake board, from the
+/// exact planck stakes of its members since sorted set scores are doubles
+async fn calculate_stake_interval(
+    cache: Data<RedisPool>,
+    name: &str,
+    norm: Norm,
+) -> Result<StakeInterval, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let members: Vec<String> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(0, name.to_string()))
+        .arg(0)
+        .arg(-1)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let stakes: Vec<u128> = members
+        .iter()
+        .filter_map(|member| Some(sync::parse_stake_board_member(member)?.0))
+        .collect();
+    Ok(stakes_interval(&stakes, norm))
+}
+
+/// Fields of the era limits normalized by the strategy, e.g. `ci95:max_own_stake`
+fn era_limits_fields(norm: Norm, limits: &BoardLimitsCache) -> Vec<(String, String)> {
+    limits
+        .iter()
+        .map(|(field, value)| (format!("{}:{}", norm, field), value.clone()))
+        .collect()
+}
+
+/// Limits normalized by the strategy from the fields of the era limits
+fn parse_era_limits(norm: Norm, data: BoardLimitsCache) -> BoardLimitsCache {
+    let prefix = format!("{}:", norm);
+    data.into_iter()
+        .filter_map(|(field, value)| Some((field.strip_prefix(&prefix)?.to_string(), value)))
+        .collect()
+}
+
+/// Limits shared by every board of the era normalized by the strategy, since none of them
+/// depend on the weights. They are calculated for the first board requested in the era
+/// and cached in the era limits, which are cleared every time a sync finishes
+pub async fn get_era_limits(
+    era_index: EraIndex,
+    norm: Norm,
+    cache: Data<RedisPool>,
+) -> Result<BoardLimitsCache, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let key = sync::Key::LimitsAtEra(era_index);
+    let data: BoardLimitsCache = redis::cmd("HGETALL")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let limits = parse_era_limits(norm, data);
+    if limits.len() > 0 {
+        return Ok(limits);
+    }
+
+    let limits = calculate_limits(norm, cache.clone()).await?;
+    let _: () = redis::cmd("HSET")
+        .arg(key)
+        .arg(era_limits_fields(norm, &limits))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(limits)
+}
+
+/// Calculate the limits of every criterion from the boards of all validators
+async fn calculate_limits(
+    norm: Norm,
+    cache: Data<RedisPool>,
+) -> Result<BoardLimitsCache, ApiError> {
+    let mut limits: BoardLimitsCache = BTreeMap::new();
+
+    // let max_avg_reward_points =
+    //     calculate_avg_points(cache.clone(), sync::BOARD_MAX_POINTS_ERAS).await?;
+    // limits.insert("max_avg_reward_points".to_string(), max_avg_reward_points);
+    // let min_avg_reward_points =
+    //     calculate_avg_points(cache.clone(), sync::BOARD_MIN_POINTS_ERAS).await?;
+    // limits.insert("min_avg_reward_points".to_string(), min_avg_reward_points);
+
+    let avg_reward_points_interval =
+        calculate_interval(cache.clone(), sync::BOARD_AVG_POINTS_ERAS, norm).await?;
+    limits.insert(
+        "min_avg_reward_points".to_string(),
+        avg_reward_points_interval.0.to_string(),
+    );
+    limits.insert(
+        "max_avg_reward_points".to_string(),
+        avg_reward_points_interval.1.to_string(),
+    );
+
+    let own_stake_interval =
+        calculate_stake_interval(cache.clone(), sync::BOARD_OWN_STAKE_VALIDATORS, norm).await?;
+    limits.insert(
+        "min_own_stake".to_string(),
+        own_stake_interval.min.to_string(),
+    );
+    limits.insert(
+        "max_own_stake".to_string(),
+        own_stake_interval.max.to_string(),
+    );
+
+    let total_stake_interval =
+        calculate_stake_interval(cache.clone(), sync::BOARD_TOTAL_STAKE_VALIDATORS, norm).await?;
+    limits.insert(
+        "min_total_stake".to_string(),
+        total_stake_interval.min.to_string(),
+    );
+    limits.insert(
+        "max_total_stake".to_string(),
+        total_stake_interval.max.to_string(),
+    );
+
+    let judgements_interval =
+        calculate_interval(cache.clone(), sync::BOARD_JUDGEMENTS_VALIDATORS, norm).await?;
+    limits.insert(
+        "min_judgements".to_string(),
+        judgements_interval.0.to_string(),
+    );
+    limits.insert(
+        "max_judgements".to_string(),
+        judgements_interval.1.to_string(),
+    );
+
+    let sub_accounts_interval =
+        calculate_interval(cache.clone(), sync::BOARD_SUB_ACCOUNTS_VALIDATORS, norm).await?;
+    limits.insert(
+        "min_sub_accounts".to_string(),
+        sub_accounts_interval.0.to_string(),
+    );
+    limits.insert(
+        "max_sub_accounts".to_string(),
+        sub_accounts_interval.1.to_string(),
+    );
+
+    let slashes_interval =
+        calculate_interval(cache.clone(), sync::BOARD_SLASHED_VALIDATORS, norm).await?;
+    limits.insert("min_slashes".to_string(), slashes_interval.0.to_string());
+    limits.insert("max_slashes".to_string(), slashes_interval.1.to_string());
+
+    // Validators are oversubscribed above the chain limit of nominators rewarded
+    let max_nominator_rewarded = get_max_nominator_rewarded(cache.clone()).await?;
+    limits.insert(
+        "max_nominators".to_string(),
+        max_nominator_rewarded.to_string(),
+    );
+
+    let commission_volatility_interval = calculate_interval(
+        cache.clone(),
+        sync::BOARD_COMMISSION_VOLATILITY_VALIDATORS,
+        norm,
+    )
+    .await?;
+    limits.insert(
+        "min_commission_volatility".to_string(),
+        commission_volatility_interval.0.to_string(),
+    );
+    limits.insert(
+        "max_commission_volatility".to_string(),
+        commission_volatility_interval.1.to_string(),
+    );
+
+    let self_stake_ratio_interval =
+        calculate_interval(cache.clone(), sync::BOARD_SELF_STAKE_RATIO_VALIDATORS, norm).await?;
+    limits.insert(
+        "min_self_stake_ratio".to_string(),
+        self_stake_ratio_interval.0.to_string(),
+    );
+    limits.insert(
+        "max_self_stake_ratio".to_string(),
+        self_stake_ratio_interval.1.to_string(),
+    );
+
+    Ok(limits)
+}
+
+/// Insert the min and max limits of a criterion, e.g. `min_own_stake` and `max_own_stake`
+fn insert_limits<T: ToString>(limits: &mut BoardLimitsCache, name: &str, min: T, max: T) {
+    limits.insert(format!("min_{}", name), min.to_string());
+    limits.insert(format!("max_{}", name), max.to_string());
+}
+
+/// Calculate the limits of every criterion from the validators scored by a board at a
+/// past era, so that the board is normalized by the data of that era rather than by
+/// the current boards of all validators
+fn calculate_validators_limits(
+    validators: &[&Validator],
+    norm: Norm,
+    max_nominator_rewarded: u32,
+) -> BoardLimitsCache {
+    let mut limits: BoardLimitsCache = BTreeMap::new();
+
+    let (min, max) = validators_interval(validators, norm, |v| v.avg_reward_points);
+    insert_limits(&mut limits, "avg_reward_points", min, max);
+
+    let own_stakes: Vec<u128> = validators.iter().map(|v| v.own_stake).collect();
+    let interval = stakes_interval(&own_stakes, norm);
+    insert_limits(&mut limits, "own_stake", interval.min, interval.max);
+
+    let total_stakes: Vec<u128> = validators
+        .iter()
+        .map(|v| v.own_stake.saturating_add(v.nominators_stake))
+        .collect();
+    let interval = stakes_interval(&total_stakes, norm);
+    insert_limits(&mut limits, "total_stake", interval.min, interval.max);
+
+    let (min, max) = validators_interval(validators, norm, |v| v.judgements as f64);
+    insert_limits(&mut limits, "judgements", min, max);
+
+    let (min, max) = validators_interval(validators, norm, |v| v.sub_accounts as f64);
+    insert_limits(&mut limits, "sub_accounts", min, max);
+
+    let (min, max) = validators_interval(validators, norm, |v| v.slashes as f64);
+    insert_limits(&mut limits, "slashes", min, max);
+
+    limits.insert(
+        "max_nominators".to_string(),
+        max_nominator_rewarded.to_string(),
+    );
+
+    let (min, max) = validators_interval(validators, norm, |v| v.commission_volatility);
+    insert_limits(&mut limits, "commission_volatility", min, max);
+
+    let (min, max) = validators_interval(validators, norm, |v| v.self_stake_ratio);
+    insert_limits(&mut limits, "self_stake_ratio", min, max);
+
+    limits
+}
+
+/// Key of the limits used to normalize a board, shared by every board defined by the
+/// same weights at the era, e.g. `hist:geomean:5,5,5:limits`
+pub fn board_limits_key(era_index: EraIndex, board_name: &str) -> sync::Key {
+    sync::Key::BoardAtEra(era_index, format!("{}:limits", board_name))
+}
+
+pub async fn cache_board_limits(
+    era_index: EraIndex,
+    board_name: &str,
+    limits: BoardLimitsCache,
+    cache: Data<RedisPool>,
+) -> Result<BoardLimits, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    // Cache board limits
+    let _: () = redis::cmd("HSET")
+        .arg(board_limits_key(era_index, board_name))
+        .arg(limits.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(limits.into())
+}
+
+/// Get the maximum number of nominators rewarded per validator
+pub async fn get_max_nominator_rewarded(cache: Data<RedisPool>) -> Result<u32, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let max_nominator_rewarded: Option<u32> = redis::cmd("HGET")
+        .arg(sync::Key::Network)
+        .arg("max_nominator_rewarded_per_validator")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(max_nominator_rewarded.unwrap_or(sync::DEFAULT_MAX_NOMINATOR_REWARDED_PER_VALIDATOR))
+}
+
+pub async fn is_syncing(cache: Data<RedisPool>) -> Result<bool, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    let res: Option<String> = redis::cmd("HGET")
+        .arg(sync::Key::Info)
+        .arg("syncing")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let syncing = match res {
+        Some(v) => v.parse::<bool>().unwrap_or_default(),
+        None => false,
+    };
+
+    Ok(syncing)
+}
+
+/// Factor applied to the spread of the partial scores to break ties between equal scores.
+/// Note: partial scores are capped by the maximum weight (9), so the spread is at most 4.5
+/// and the tie-break never exceeds 4.5e-6, well below any meaningful score difference
+const TIE_BREAK_EPSILON: f64 = 0.000001;
+
+/// Total score with a tiny penalty for the spread of the partial scores, so that between
+/// validators with the same total score the more balanced one ranks higher
+fn composite_score(scores: &Vec<f64>) -> f64 {
+    let score = scores.iter().fold(0.0, |acc, x| acc + x);
+    if scores.is_empty() {
+        return score;
+    }
+    score - stats::standard_deviation(scores) * TIE_BREAK_EPSILON
+}
+
+/// Normalized value given to a criterion with a zero score in the geometric mean, so that
+/// a single zero penalizes the total score instead of zeroing it, e.g. a zero in one of
+/// twelve equally weighted criteria still scales the total score by 0.01^(1/12) ~ 0.68
+const GEOMEAN_EPSILON: f64 = 0.01;
+
+/// Weighted geometric mean of the normalized value of every weighted criterion, scaled by
+/// the sum of the weights so that it ranges between the same limits as the weighted sum
+fn geometric_score(scores: &Vec<f64>, weights: &Weights) -> f64 {
+    let max_score = weights.iter().fold(0.0, |acc, x| acc + *x as f64);
+    if max_score == 0.0 {
+        return 0.0;
+    }
+    let log_sum = scores
+        .iter()
+        .zip(weights.iter())
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0.0, |acc, (score, weight)| {
+            let value = (score / *weight as f64).max(GEOMEAN_EPSILON);
+            acc + *weight as f64 * value.ln()
+        });
+    (log_sum / max_score).exp() * max_score
+}
+
+/// Total score used to rank a validator, with the tie-break of the partial scores spread
+fn board_score(scores: &Vec<f64>, weights: &Weights, formula: Formula) -> f64 {
+    match formula {
+        Formula::Sum => composite_score(scores),
+        Formula::GeoMean => {
+            let score = geometric_score(scores, weights);
+            if scores.is_empty() {
+                return score;
+            }
+            score - stats::standard_deviation(scores) * TIE_BREAK_EPSILON
+        }
+    }
+}
+
+/// Total score as a percentage of the maximum score the weights can give, so that scores
+/// are comparable between boards defined by different weights, e.g. 87 out of 100
+pub fn normalized_score_of(scores: &Vec<f64>, weights: &Weights, formula: Formula) -> f64 {
+    let max_score = weights.iter().fold(0.0, |acc, x| acc + *x as f64);
+    if max_score == 0.0 {
+        return 0.0;
+    }
+    let score = match formula {
+        Formula::Sum => scores.iter().fold(0.0, |acc, x| acc + x),
+        Formula::GeoMean => geometric_score(scores, weights),
+    };
+    score / max_score * 100.0
+}
+
+fn calculate_scores(
+    validator: &Validator,
+    limits: &BoardLimits,
+    weights: &Weights,
+) -> Result<Vec<f64>, ApiError> {
+    let mut scores: Vec<f64> = Vec::with_capacity(WEIGHTS_CAPACITY);
+
+    scores.push(
+        normalize_value(
+            validator.inclusion_rate as f64,
+            limits.inclusion_rate.min,
+            limits.inclusion_rate.max,
+        ) * weights[0] as f64,
+    );
+    scores.push(
+        reverse_normalize_commission(
+            validator.commission,
+            limits.commission.min,
+            limits.commission.max,
+        ) * weights[1] as f64,
+    );
+    scores.push(
+        reverse_normalize_value(
+            validator.nominators as f64,
+            limits.nominators.min,
+            limits.nominators.max,
+        ) * weights[2] as f64,
+    );
+    scores.push(
+        normalize_value(
+            validator.avg_reward_points,
+            limits.avg_reward_points.min,
+            limits.avg_reward_points.max,
+        ) * weights[3] as f64,
+    );
+    scores.push(normalize_flag(validator.reward_staked) * weights[4] as f64);
+    scores.push(normalize_flag(validator.active) * weights[5] as f64);
+    scores.push(normalize_stake(validator.own_stake, &limits.own_stake) * weights[6] as f64);
+    scores.push(
+        reverse_normalize_stake(
+            validator.own_stake + validator.nominators_stake,
+            &limits.total_stake,
+        ) * weights[7] as f64,
+    );
+    scores.push(
+        normalize_value(
+            validator.judgements as f64,
+            limits.judgements.min,
+            limits.judgements.max,
+        ) * weights[8] as f64,
+    );
+    scores.push(
+        reverse_normalize_value(
+            validator.sub_accounts as f64,
+            limits.sub_accounts.min,
+            limits.sub_accounts.max,
+        ) * weights[9] as f64,
+    );
+    scores.push(
+        reverse_normalize_value(
+            validator.slashes as f64,
+            limits.slashes.min,
+            limits.slashes.max,
+        ) * weights[10] as f64,
+    );
+    scores.push(
+        reverse_normalize_value(
+            validator.commission_volatility,
+            limits.commission_volatility.min,
+            limits.commission_volatility.max,
+        ) * weights[11] as f64,
+    );
+
+    scores.push(
+        normalize_value(
+            validator.self_stake_ratio,
+            limits.self_stake_ratio.min,
+            limits.self_stake_ratio.max,
+        ) * weights[12] as f64,
+    );
+
+    Ok(scores)
+}
+
+/// Seconds a board generation lock is held at most, in case the generation is interrupted
+const BOARD_LOCK_SECONDS: usize = 300;
+
+/// Number of board members cached per pipeline
+const BOARD_PIPELINE_CAPACITY: usize = 100;
+
+/// Score of a validator in a board, with the partial and normalized scores cached with it
+#[derive(Debug, PartialEq)]
+struct BoardMember {
+    stash: String,
+    score: f64,
+    scores: Vec<f64>,
+    normalized: f64,
+}
+
+/// Score the candidates of a board. Validators that do not accept nominations or are
+/// excluded are not given a score
+fn score_board_candidates(
+    candidates: &[(AccountId32, Validator)],
+    limits: &BoardLimits,
+    weights: &Weights,
+    formula: Formula,
+    previous_scores: &BTreeMap<String, f64>,
+    alpha: f64,
+    is_excluded: impl Fn(&str) -> bool,
+) -> Result<Vec<BoardMember>, ApiError> {
+    let mut members: Vec<BoardMember> = Vec::with_capacity(candidates.len());
+    for (stash, validator) in candidates {
+        let stash = stash.to_string();
+        if validator.blocked || is_excluded(&stash) {
+            continue;
+        }
+
+        let scores = calculate_scores(validator, limits, weights)?;
+        let score = smoothed_score(
+            board_score(&scores, weights, formula),
+            previous_scores.get(&stash).copied(),
+            alpha,
+        );
+        let normalized = normalized_score_of(&scores, weights, formula);
+        members.push(BoardMember {
+            stash,
+            score,
+            scores,
+            normalized,
+        });
+    }
+    Ok(members)
+}
+
+/// Claim the generation of a board, so that only one request generates it at a time.
+/// The lock expires after BOARD_LOCK_SECONDS in case the generation is interrupted
+async fn lock_board(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<bool, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let res: Option<String> = redis::cmd("SET")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:lock", board_name),
+        ))
+        .arg(Utc::now().timestamp())
+        .arg("NX")
+        .arg("EX")
+        .arg(BOARD_LOCK_SECONDS)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(res.is_some())
+}
+
+async fn unlock_board(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let _: () = redis::cmd("DEL")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:lock", board_name),
+        ))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(())
+}
+
+/// Number of members expected by the board completion marker, none if the board was
+/// generated with a different list of excluded stashes than the current one
+fn expected_members(
+    expected: Option<usize>,
+    excluded: Option<&str>,
+    excluded_hash: Option<&str>,
+) -> Option<usize> {
+    if excluded != excluded_hash {
+        return None;
+    }
+    expected
+}
+
+/// Number of members expected by the board completion marker and number of members cached.
+/// Note: a board generated with a different list of excluded stashes is never complete, so
+/// that it is regenerated without the stashes excluded since
+pub async fn get_board_members_count(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<(Option<usize>, usize), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let key_meta = sync::Key::BoardAtEra(era_index, format!("{}:meta", board_name));
+    let (expected, excluded, members): (Option<usize>, Option<String>, usize) = redis::pipe()
+        .cmd("HGET")
+        .arg(key_meta.clone())
+        .arg("members")
+        .cmd("HGET")
+        .arg(key_meta)
+        .arg("excluded")
+        .cmd("ZCARD")
+        .arg(sync::Key::BoardAtEra(era_index, board_name.to_string()))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let excluded_hash = CONFIG.excluded_stashes_hash();
+    Ok((
+        expected_members(expected, excluded.as_deref(), excluded_hash.as_deref()),
+        members,
+    ))
+}
+
+pub async fn generate_board_scores(
+    era_index: EraIndex,
+    weights: &Weights,
+    norm: Norm,
+    formula: Formula,
+    smooth: bool,
+    historical: bool,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let board_name = historical_board_name(
+        smoothed_board_name(
+            formula_board_name(get_board_name(weights, None, norm), formula),
+            smooth,
+        ),
+        historical,
+    );
+
+    // If board is already cached and complete do nothing.
+    // Note: boards defined by the default weights are kept for every era so that
+    // the rank history of a validator can be queried later, boards defined by
+    // custom weights expire after BOARD_TTL_SECONDS
+    let (expected, members) =
+        get_board_members_count(era_index, &board_name, cache.clone()).await?;
+    if is_board_complete(expected, members) {
+        return Ok(());
+    }
+
+    // Only generate board if cache is not syncing
+    if is_syncing(cache.clone()).await? {
+        let msg = format!(
+            "The system is currently syncing. Usually doesn't take long 5 - 10min. Please just wait a few minutes before you try again. Thank you.");
+        warn!("{}", msg);
+        return Err(ApiError::ServiceUnavailable(msg));
+    }
+
+    // A board being generated by another request is incomplete as well, it must not be
+    // mistaken for a board left behind by an interrupted generation and cleared
+    if !lock_board(era_index, &board_name, cache.clone()).await? {
+        let msg = format!(
+            "Leaderboard {} is being generated. Wait a second and try again.",
+            board_name
+        );
+        warn!("{}", msg);
+        return Err(ApiError::ServiceUnavailable(msg));
+    }
+
+    let res = write_board_scores(
+        era_index,
+        weights,
+        norm,
+        formula,
+        smooth,
+        historical,
+        cache.clone(),
+    )
+    .await;
+    unlock_board(era_index, &board_name, cache.clone()).await?;
+
+    res
+}
+
+/// Write the scores of the board and its completion marker, only called by the request
+/// holding the board generation lock
+async fn write_board_scores(
+    era_index: EraIndex,
+    weights: &Weights,
+    norm: Norm,
+    formula: Formula,
+    smooth: bool,
+    historical: bool,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    let unsmoothed_board_name = limits_board_name(weights, norm, formula, historical);
+    let board_name = historical_board_name(
+        smoothed_board_name(
+            formula_board_name(get_board_name(weights, None, norm), formula),
+            smooth,
+        ),
+        historical,
+    );
+    let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+    let key_meta = sync::Key::BoardAtEra(era_index, format!("{}:meta", board_name));
+
+    // The board may have been completed while the lock was being claimed
+    let (expected, members) =
+        get_board_members_count(era_index, &board_name, cache.clone()).await?;
+    if is_board_complete(expected, members) {
+        return Ok(());
+    }
+
+    // A board left behind by an interrupted generation is discarded, so that
+    // stashes no longer eligible are not kept in the regenerated board
+    if members > 0 {
+        warn!(
+            "Board {} at era {} is incomplete ({} members), regenerating",
+            board_name, era_index, members
+        );
+        clear_board(era_index, &board_name, cache.clone()).await?;
+    }
+
+    let candidates = get_board_candidates(era_index, historical, cache.clone()).await?;
+
+    // Cache board limits based on all validators, or on the validators scored at the era
+    // for a board at a past era.
+    // Note: limits do not depend on the smoothing, so they are shared with the board
+    // without smoothing
+    let era_limits = if historical {
+        let validators: Vec<&Validator> = candidates.iter().map(|(_, v)| v).collect();
+        let max_nominator_rewarded = get_max_nominator_rewarded(cache.clone()).await?;
+        calculate_validators_limits(&validators, norm, max_nominator_rewarded)
+    } else {
+        get_era_limits(era_index, norm, cache.clone()).await?
+    };
+    let limits: BoardLimits =
+        cache_board_limits(era_index, &unsmoothed_board_name, era_limits, cache.clone()).await?;
+
+    let previous_scores = if smooth {
+        get_previous_board_scores(
+            era_index,
+            &board_name,
+            &unsmoothed_board_name,
+            cache.clone(),
+        )
+        .await?
+    } else {
+        BTreeMap::new()
+    };
+
+    let members = score_board_candidates(
+        &candidates,
+        &limits,
+        weights,
+        formula,
+        &previous_scores,
+        CONFIG.board_smoothing_alpha,
+        |stash| CONFIG.is_stash_excluded(stash),
+    )?;
+    let total = members.len();
+
+    // Cache total, partial and normalized scores
+    let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name));
+    let key_normalized = sync::Key::BoardAtEra(era_index, format!("{}:normalized", board_name));
+    for chunk in members.chunks(BOARD_PIPELINE_CAPACITY) {
+        let mut pipe = redis::pipe();
+        for member in chunk {
+            let scores_str: String = member
+                .scores
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            pipe.cmd("ZADD")
+                .arg(key.to_string())
+                .arg(member.score)
+                .arg(member.stash.to_string())
+                .ignore()
+                .cmd("HSET")
+                .arg(key_scores.to_string())
+                .arg(member.stash.to_string())
+                .arg(scores_str)
+                .ignore()
+                .cmd("HSET")
+                .arg(key_normalized.to_string())
+                .arg(member.stash.to_string())
+                .arg(member.normalized)
+                .ignore();
+        }
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+    }
+
+    // Mark board as complete only after all members have been cached, together with
+    // the list of excluded stashes the board was generated with
+    let mut marker = redis::cmd("HSET");
+    marker
+        .arg(key_meta.clone())
+        .arg("members")
+        .arg(total)
+        .arg("generated_at")
+        .arg(Utc::now().timestamp());
+    if let Some(excluded_hash) = CONFIG.excluded_stashes_hash() {
+        marker.arg("excluded").arg(excluded_hash);
+    }
+    let _: () = marker
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    expire_board(era_index, &board_name, cache.clone()).await?;
+
+    Ok(())
+}
+
+/// Exponentially weighted moving average of the board score, the current score is given
+/// the alpha weight and the score at the era before the rest. Validators without a
+/// score at the era before keep the current score
+fn smoothed_score(score: f64, previous_score: Option<f64>, alpha: f64) -> f64 {
+    match previous_score {
+        Some(previous_score) => {
+            let alpha = alpha.max(0.0).min(1.0);
+            alpha * score + (1.0 - alpha) * previous_score
+        }
+        None => score,
+    }
+}
+
+/// Scores of the board at the era before, used to smooth the scores of the board.
+/// The smoothed board is preferred so that older eras keep decaying, the board without
+/// smoothing is used otherwise, e.g. the first era the smoothed board is requested.
+/// Note: nothing is generated, no scores are returned if neither board is cached
+async fn get_previous_board_scores(
+    era_index: EraIndex,
+    board_name: &str,
+    unsmoothed_board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<BTreeMap<String, f64>, ApiError> {
+    let previous_era_index = match era_index.checked_sub(1) {
+        Some(i) => i,
+        None => return Ok(BTreeMap::new()),
+    };
+    let mut conn = get_conn(&cache).await?;
+    for name in [board_name, unsmoothed_board_name].iter() {
+        let scores: Vec<(String, f64)> = redis::cmd("ZRANGE")
+            .arg(sync::Key::BoardAtEra(previous_era_index, name.to_string()))
+            .arg(0)
+            .arg(-1)
+            .arg("WITHSCORES")
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        if scores.len() > 0 {
+            return Ok(scores.into_iter().collect());
+        }
+    }
+
+    Ok(BTreeMap::new())
+}
+
+/// A board is complete when its completion marker is set and the number of
+/// members cached matches the number expected by the marker
+pub fn is_board_complete(expected: Option<usize>, members: usize) -> bool {
+    expected == Some(members)
+}
+
+/// Remove the completion marker of a board, so that the board is regenerated the next
+/// time it is generated, with every member cached before cleared
+async fn invalidate_board(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let _: () = redis::cmd("DEL")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:meta", board_name),
+        ))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(())
+}
+
+/// Remove every key of a board, including its completion marker
+async fn clear_board(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let _: () = redis::cmd("DEL")
+        .arg(sync::Key::BoardAtEra(era_index, board_name.to_string()))
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:scores", board_name),
+        ))
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:normalized", board_name),
+        ))
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            format!("{}:meta", board_name),
+        ))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(())
+}
+
+pub async fn generate_board_filtered_by_intervals(
+    era_index: EraIndex,
+    weights: &Weights,
+    intervals: &Intervals,
+    norm: Norm,
+    formula: Formula,
+    smooth: bool,
+    historical: bool,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    let unsmoothed_board_name = historical_board_name(
+        formula_board_name(get_board_name(weights, Some(intervals), norm), formula),
+        historical,
+    );
+    let board_name = historical_board_name(
+        smoothed_board_name(
+            formula_board_name(get_board_name(weights, Some(intervals), norm), formula),
+            smooth,
+        ),
+        historical,
+    );
+    let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+
+    let exists: bool = redis::cmd("EXISTS")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    // If board is already cached do nothing
+    // if exists {
+    //     return Ok(());
+    // }
+
+    // Without intervals the board is the same as the one generated by the weights only
+    if intervals.is_empty() {
+        return Ok(());
+    }
+
+    // Only generate board if cache is not syncing
+    if !exists && is_syncing(cache.clone()).await? {
+        let msg = format!(
+            "The system is currently syncing. Usually doesn't take long 5 - 10min. Please just wait a few minutes before you try again. Thank you.");
+        warn!("{}", msg);
+        return Err(ApiError::ServiceUnavailable(msg));
+    }
+
+    // Unbounded interval sides are set by the limits discovered for the weights board
+    let board_limits: BoardLimits = get_board_limits(
+        era_index,
+        &limits_board_name(weights, norm, formula, historical),
+        cache.clone(),
+    )
+    .await?;
+    let limits: BoardLimits = BoardLimits::from_intervals(intervals, &board_limits);
+    let max_nominator_rewarded = get_max_nominator_rewarded(cache.clone()).await?;
+
+    let previous_scores = if smooth {
+        get_previous_board_scores(
+            era_index,
+            &board_name,
+            &unsmoothed_board_name,
+            cache.clone(),
+        )
+        .await?
+    } else {
+        BTreeMap::new()
+    };
+
+    let candidates = get_board_candidates(era_index, historical, cache.clone()).await?;
+    for (stash, validator) in candidates {
+        // If the validator does not accept nominations or is excluded
+        // score is not given
+        if validator.blocked || CONFIG.is_stash_excluded(&stash.to_string()) {
+            continue;
+        }
+
+        // Verify if validator traits are within the respective interval defined by user
+        // Position 0 - Higher Inclusion rate is preferrable
+        // Position 1 - Lower Commission is preferrable
+        // Position 2 - Lower Nominators is preferrable (limit to MaxNominatorRewardedPerValidator -> oversubscribed)
+        // Position 3 - Higher Reward Points is preferrable
+        // Position 4 - If reward is staked is preferrable
+        // Position 5 - If in active set is preferrable
+        // Position 6 - Higher own stake is preferrable
+        // Position 7 - Lower total stake is preferrable
+        // Position 8 - Higher number of Reasonable or KnownGood judgements is preferrable
+        // Position 9 - Lower number of sub-accounts is preferrable
+        // Position 10 - Lower number of slashes is preferrable
+        // Position 11 - Lower commission volatility is preferrable
+        // Position 12 - Higher self-stake ratio is preferrable
+
+        if (validator.inclusion_rate as f64) < limits.inclusion_rate.min
+            || (validator.inclusion_rate as f64) > limits.inclusion_rate.max
+        {
+            continue;
+        }
+        if !is_commission_within(validator.commission, &limits.commission) {
+            continue;
+        }
+        if (validator.nominators as f64) < limits.nominators.min
+            || ((validator.nominators as f64) > limits.nominators.max
+                && limits.nominators.max < max_nominator_rewarded as f64)
+        {
+            continue;
+        }
+        if validator.avg_reward_points < limits.avg_reward_points.min
+            || validator.avg_reward_points > limits.avg_reward_points.max
+        {
+            continue;
+        }
+        if normalize_flag(validator.reward_staked) != limits.reward_staked.min
+            && limits.reward_staked.min == limits.reward_staked.max
+        {
+            continue;
+        }
+        if normalize_flag(validator.active) != limits.active.min
+            && limits.active.min == limits.active.max
+        {
+            continue;
+        }
+        if !is_stake_within(validator.own_stake, &limits.own_stake) {
+            continue;
+        }
+        if !is_stake_within(
+            validator.own_stake + validator.nominators_stake,
+            &limits.total_stake,
+        ) {
+            continue;
+        }
+        if (validator.judgements as f64) < limits.judgements.min
+            || (validator.judgements as f64) > limits.judgements.max
+        {
+            continue;
+        }
+        if (validator.sub_accounts as f64) < limits.sub_accounts.min
+            || (validator.sub_accounts as f64) > limits.sub_accounts.max
+        {
+            continue;
+        }
+        if (validator.slashes as f64) < limits.slashes.min
+            || (validator.slashes as f64) > limits.slashes.max
+        {
+            continue;
+        }
+        if (validator.commission_volatility) < limits.commission_volatility.min
+            || (validator.commission_volatility) > limits.commission_volatility.max
+        {
+            continue;
+        }
+        if (validator.self_stake_ratio) < limits.self_stake_ratio.min
+            || (validator.self_stake_ratio) > limits.self_stake_ratio.max
+        {
+            continue;
+        }
+
+        // Calculate scores
+        let scores = calculate_scores(&validator, &limits, weights)?;
+        let score = smoothed_score(
+            board_score(&scores, weights, formula),
+            previous_scores.get(&stash.to_string()).copied(),
+            CONFIG.board_smoothing_alpha,
+        );
+
+        // Cache total score
+        let _: () = redis::cmd("ZADD")
+            .arg(key.to_string())
+            .arg(score) // score
+            .arg(stash.to_string()) // member
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        let scores_str: String = scores
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                if i == 0 {
+                    return x.to_string();
+                }
+                format!(",{}", x)
+            })
+            .collect();
+
+        // Cache partial scores
+        let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name.clone()));
+        let _: () = redis::cmd("HSET")
+            .arg(key_scores.to_string())
+            .arg(stash.to_string())
+            .arg(scores_str.to_string())
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        // Cache normalized score
+        let key_normalized =
+            sync::Key::BoardAtEra(era_index, format!("{}:normalized", board_name.clone()));
+        let _: () = redis::cmd("HSET")
+            .arg(key_normalized.to_string())
+            .arg(stash.to_string())
+            .arg(normalized_score_of(&scores, weights, formula))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+    }
+
+    expire_board(era_index, &board_name, cache.clone()).await?;
+
+    Ok(())
+}
+
+/// Set a time to live on the keys of a generated board, so that every weights and
+/// intervals combination requested does not grow the cache indefinitely.
+/// Note: boards generated with the default weights never expire
+pub async fn expire_board(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<(), ApiError> {
+    if CONFIG.board_ttl_seconds == 0 || is_default_board(board_name) {
+        return Ok(());
+    }
+
+    let mut conn = get_conn(&cache).await?;
+    let mut pipe = redis::pipe();
+    for name in [
+        board_name.to_string(),
+        format!("{}:scores", board_name),
+        format!("{}:normalized", board_name),
+        format!("{}:limits", board_name),
+        format!("{}:meta", board_name),
+    ]
+    .iter()
+    {
+        pipe.cmd("EXPIRE")
+            .arg(sync::Key::BoardAtEra(era_index, name.to_string()))
+            .arg(CONFIG.board_ttl_seconds)
+            .ignore();
+    }
+    let _: () = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(())
+}
+
+pub fn is_default_board(board_name: &str) -> bool {
+    board_name == DEFAULT_WEIGHTS.join(",")
+}
+
+/// Weights of the default board
+pub fn default_board_weights() -> Weights {
+    DEFAULT_WEIGHTS
+        .iter()
+        .map(|x| x.parse::<Weight>().unwrap_or_default())
+        .collect()
+}
+
+/// Get the limits cached for the board, named without intervals nor smoothing
+pub async fn get_board_limits(
+    era_index: EraIndex,
+    board_name: &str,
+    cache: Data<RedisPool>,
+) -> Result<BoardLimits, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    // Check if limits key is already available
+    let key = board_limits_key(era_index, board_name);
+    if let redis::Value::Int(0) = redis::cmd("EXISTS")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?
+    {
+        let msg = format!(
+            "Limits not yet available for Leaderboard {}. Wait a second and try again.",
+            board_name
+        );
+        warn!("{}", msg);
+        return Err(ApiError::ServiceUnavailable(msg));
+    }
+    // Get limits
+    let limits: BoardLimitsCache = redis::cmd("HGETALL")
+        .arg(key)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    if limits.len() == 0 {
+        let msg = format!("Limits not available for Leaderboard {}", board_name);
+        error!("{}", msg);
+        return Err(ApiError::InternalServerError(msg));
+    }
+
+    Ok(limits.into())
+}
+
+/// Lowest score of the members returned from a board, 0 by default
+pub fn min_score_bound(min_score: Option<f64>) -> Result<String, ApiError> {
+    match min_score {
+        Some(score) if !score.is_finite() => {
+            let msg = "Parameter min_score must be a number".to_string();
+            warn!("{}", msg);
+            Err(ApiError::BadRequest(msg))
+        }
+        Some(score) => Ok(score.to_string()),
+        None => Ok("0".to_string()),
+    }
+}
+
+pub async fn get_validators_stashes(
+    key: sync::Key,
+    offset: Quantity,
+    n: Quantity,
+    min_score: Option<f64>,
+    cache: Data<RedisPool>,
+) -> Result<Vec<String>, ApiError> {
+    let min = min_score_bound(min_score)?;
+    let mut conn = get_conn(&cache).await?;
+
+    // Note: an offset beyond the board size just returns an empty list
+    let stashes: Vec<String> = redis::cmd("ZRANGE")
+        .arg(key)
+        .arg("+inf")
+        .arg(min)
+        .arg("BYSCORE")
+        .arg("REV")
+        .arg("LIMIT")
+        .arg(offset)
+        .arg(n)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(stashes)
+}
+
+/// Validators scored by a board, with the data used to score them.
+/// Note: boards at a past era only score the validators active in that era, with the
+/// era commission, blocked flag, stakes, nominators and reward points. Any other trait
+/// (e.g. inclusion rate, identity or slashes) is only synced for the current state
+async fn get_board_candidates(
+    era_index: EraIndex,
+    historical: bool,
+    cache: Data<RedisPool>,
+) -> Result<Vec<(AccountId32, Validator)>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    let stashes: Vec<String> = if historical {
+        redis::cmd("ZRANGE")
+            .arg(sync::Key::BoardAtEra(
+                era_index,
+                sync::BOARD_POINTS_VALIDATORS.to_string(),
+            ))
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?
+    } else {
+        redis::cmd("ZRANGE")
+            .arg(sync::Key::BoardAtEra(
+                era_index,
+                sync::BOARD_ALL_VALIDATORS.to_string(),
+            ))
+            .arg("-inf")
+            .arg("+inf")
+            .arg("BYSCORE")
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?
+    };
+
+    let mut candidates: Vec<(AccountId32, Validator)> = Vec::with_capacity(stashes.len());
+    for stash in stashes {
+        let stash = AccountId32::from_str(&*stash.to_string())?;
+        let mut data: ValidatorCache = redis::cmd("HGETALL")
+            .arg(sync::Key::Validator(stash.clone()))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        if historical {
+            let era_data: ValidatorEraCache = redis::cmd("HGETALL")
+                .arg(sync::Key::ValidatorAtEra(era_index, stash.clone()))
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+            overlay_era_data(&mut data, &era_data);
+        }
+
+        candidates.push((stash, data.into()));
+    }
+
+    Ok(candidates)
+}
+
+/// Replace the validator traits by the ones recorded at the era
+fn overlay_era_data(data: &mut ValidatorCache, era_data: &ValidatorEraCache) {
+    for (field, era_field) in [
+        ("commission", "commission"),
+        ("blocked", "blocked"),
+        ("active", "active"),
+        ("own_stake", "own_stake"),
+        ("nominators_stake", "others_stake"),
+        ("nominators", "stakers"),
+        ("avg_reward_points", "reward_points"),
+    ]
+    .iter()
+    {
+        if let Some(value) = era_data.get(*era_field) {
+            data.insert(field.to_string(), value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_an_interval() {
+        assert_eq!(
+            parse_interval("1:2"),
+            Some(Interval { min: 1.0, max: 2.0 })
+        );
+        assert_eq!(
+            parse_interval("0.5"),
+            Some(Interval {
+                min: 0.5,
+                max: f64::INFINITY
+            })
+        );
+        assert_eq!(
+            parse_interval("0.5:"),
+            Some(Interval {
+                min: 0.5,
+                max: f64::INFINITY
+            })
+        );
+        assert_eq!(
+            parse_interval(":10"),
+            Some(Interval {
+                min: f64::NEG_INFINITY,
+                max: 10.0
+            })
+        );
+        assert_eq!(parse_interval(""), Some(Interval::unbounded()));
+        assert_eq!(parse_interval("abc:def"), None);
+        assert_eq!(parse_interval("1:2:3"), None);
+        assert_eq!(parse_interval("NaN:1"), None);
+    }
+
+    #[test]
+    fn it_bounds_an_unbounded_interval() {
+        let board = Interval { min: 3.0, max: 7.0 };
+        let interval = Interval {
+            min: 5.0,
+            max: f64::INFINITY,
+        };
+        assert_eq!(
+            interval.bounded_by(&board),
+            Interval { min: 5.0, max: 7.0 }
+        );
+        assert_eq!(Interval::unbounded().bounded_by(&board), board);
+    }
+
+    #[test]
+    fn it_parses_unbounded_sides() {
+        assert_eq!(
+            parse_interval("10:-1"),
+            Some(Interval {
+                min: 10.0,
+                max: f64::INFINITY
+            })
+        );
+        assert_eq!(
+            parse_interval("-1:10"),
+            Some(Interval {
+                min: f64::NEG_INFINITY,
+                max: 10.0
+            })
+        );
+        assert_eq!(parse_interval("-1:-1"), Some(Interval::unbounded()));
+        // Zero is a valid bound, e.g. no slashes
+        assert_eq!(parse_interval("0:0"), Some(Interval { min: 0.0, max: 0.0 }));
+    }
+
+    #[test]
+    fn it_filters_own_stake_by_lower_bound_only() {
+        let board = StakeInterval {
+            min: 1_000,
+            max: 5_000_000_000_000,
+        };
+        for interval in &["2000000000000:", "2000000000000:-1"] {
+            let own_stake =
+                StakeInterval::from_interval(&parse_interval(interval).unwrap(), &board);
+            assert_eq!(own_stake.max, board.max);
+            assert!(is_stake_within(2_000_000_000_000, &own_stake));
+            assert!(is_stake_within(5_000_000_000_000, &own_stake));
+            assert!(!is_stake_within(1_999_999_999_999, &own_stake));
+        }
+    }
+
+    #[test]
+    fn it_filters_total_stake_by_upper_bound_only() {
+        let board = StakeInterval {
+            min: 1_000,
+            max: 9_000_000_000_000_000,
+        };
+        for interval in &[":3000000000000000", "-1:3000000000000000"] {
+            let total_stake =
+                StakeInterval::from_interval(&parse_interval(interval).unwrap(), &board);
+            assert_eq!(total_stake.min, board.min);
+            assert!(is_stake_within(1_000, &total_stake));
+            assert!(is_stake_within(3_000_000_000_000_000, &total_stake));
+            assert!(!is_stake_within(3_000_001_000_000_000, &total_stake));
+        }
+    }
+
+    #[test]
+    fn it_keeps_nearly_equal_stakes_scored_apart() {
+        // Stakes above 2^53 planck collapse to the same double
+        let a: u128 = (1 << 100) + (1 << 20);
+        let b: u128 = (1 << 100) + (1 << 30);
+        assert_eq!(a as f64, b as f64);
+        let limits = StakeInterval {
+            min: 1 << 100,
+            max: (1 << 100) + (1 << 31),
+        };
+        assert!(normalize_stake(b, &limits) > normalize_stake(a, &limits));
+        assert_eq!(normalize_stake(b, &limits), 0.5);
+        assert!(reverse_normalize_stake(a, &limits) > reverse_normalize_stake(b, &limits));
+        assert!(is_stake_within(a, &limits));
+        assert!(!is_stake_within(limits.max + 1, &limits));
+        assert_eq!(normalize_stake(a, &StakeInterval::default()), 0.0);
+    }
+
+    #[test]
+    fn it_derives_reward_staked_from_payee() {
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("payee".to_string(), "Staked".to_string());
+        let validator: Validator = data.into();
+        assert_eq!(validator.payee, "Staked");
+        assert_eq!(validator.reward_staked, true);
+
+        // Rewards paid to the stash can be bonded again, so they are treated as compounding
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("payee".to_string(), "Stash".to_string());
+        let validator: Validator = data.into();
+        assert_eq!(validator.reward_staked, true);
+
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("payee".to_string(), "Controller".to_string());
+        data.insert("reward_staked".to_string(), "true".to_string());
+        let validator: Validator = data.into();
+        assert_eq!(validator.reward_staked, false);
+
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("payee".to_string(), "Account(5GrwvaEF)".to_string());
+        let validator: Validator = data.into();
+        assert_eq!(validator.reward_staked, false);
+
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("reward_staked".to_string(), "true".to_string());
+        let validator: Validator = data.into();
+        assert_eq!(validator.payee, "");
+        assert_eq!(validator.reward_staked, true);
+    }
+
+    #[test]
+    fn it_overlays_era_data() {
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("name".to_string(), "Alice".to_string());
+        data.insert("commission".to_string(), "50000000".to_string());
+        data.insert("nominators".to_string(), "64".to_string());
+        data.insert("active".to_string(), "false".to_string());
+        let mut era_data: ValidatorEraCache = BTreeMap::new();
+        era_data.insert("commission".to_string(), "10000000".to_string());
+        era_data.insert("stakers".to_string(), "12".to_string());
+        era_data.insert("others_stake".to_string(), "1000".to_string());
+        era_data.insert("reward_points".to_string(), "3200".to_string());
+        era_data.insert("active".to_string(), "true".to_string());
+        overlay_era_data(&mut data, &era_data);
+        let validator: Validator = data.into();
+        assert_eq!(validator.name, "Alice");
+        assert_eq!(validator.commission, 10000000);
+        assert_eq!(validator.nominators, 12);
+        assert_eq!(validator.nominators_stake, 1000);
+        assert_eq!(validator.avg_reward_points, 3200.0);
+        assert_eq!(validator.active, true);
+    }
+
+    #[test]
+    fn it_limits_historical_boards_by_era_data() {
+        let validators: Vec<Validator> = [("1000", "3200"), ("3000", "0")]
+            .iter()
+            .map(|(others_stake, reward_points)| {
+                let mut data: ValidatorCache = BTreeMap::new();
+                data.insert("own_stake".to_string(), "500".to_string());
+                data.insert("avg_reward_points".to_string(), "60000".to_string());
+                let mut era_data: ValidatorEraCache = BTreeMap::new();
+                era_data.insert("others_stake".to_string(), others_stake.to_string());
+                era_data.insert("reward_points".to_string(), reward_points.to_string());
+                overlay_era_data(&mut data, &era_data);
+                data.into()
+            })
+            .collect();
+        let validators: Vec<&Validator> = validators.iter().collect();
+        let limits: BoardLimits =
+            calculate_validators_limits(&validators, Norm::MinMax, 256).into();
+        assert_eq!(limits.avg_reward_points.min, 0.0);
+        assert_eq!(limits.avg_reward_points.max, 3200.0);
+        assert_eq!(limits.own_stake.min, 500);
+        assert_eq!(limits.total_stake.min, 1500);
+        assert_eq!(limits.total_stake.max, 3500);
+        assert_eq!(limits.nominators.max, 256.0);
+        // Writers and readers share the key of the limits of a historical board
+        assert_eq!(
+            board_limits_key(42, &historical_board_name("5,5,5".to_string(), true)).to_string(),
+            "42:era:hist:5,5,5:limits:board"
+        );
+    }
+
+    #[test]
+    fn it_normalizes_value_within_min_max() {
+        assert_eq!(normalize_value(10.0, 10.0, 20.0), 0.0);
+        assert_eq!(normalize_value(20.0, 10.0, 20.0), 1.0);
+        assert_eq!(normalize_value(15.0, 10.0, 20.0), 0.5);
+        // Out of the interval values are clamped
+        assert_eq!(normalize_value(5.0, 10.0, 20.0), 0.0);
+        assert_eq!(normalize_value(25.0, 10.0, 20.0), 1.0);
+        // A zero value scales when min is negative
+        assert_eq!(normalize_value(0.0, -10.0, 10.0), 0.5);
+        // No divide-by-zero when max equals min
+        assert_eq!(normalize_value(10.0, 10.0, 10.0), 0.0);
+        assert_eq!(normalize_value(0.0, 0.0, 0.0), 0.0);
+        assert_eq!(reverse_normalize_value(0.0, 0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn it_normalizes_score_by_weights() {
+        assert_eq!(
+            normalized_score_of(&vec![4.5, 2.0, 0.0], &vec![5, 5, 0], Formula::Sum),
+            65.0
+        );
+        assert_eq!(
+            normalized_score_of(&vec![9.0, 1.0], &vec![9, 1], Formula::Sum),
+            100.0
+        );
+        assert_eq!(
+            normalized_score_of(&vec![0.0, 0.0], &vec![0, 0], Formula::Sum),
+            0.0
+        );
+    }
+
+    #[test]
+    fn it_scores_by_geometric_mean() {
+        let weights: Weights = vec![5, 5, 0];
+        // Maximum scores give the same total as the sum
+        assert!((geometric_score(&vec![5.0, 5.0, 0.0], &weights) - 10.0).abs() < 1e-9);
+        // A zero in a weighted criterion is penalized rather than made up for
+        let balanced = geometric_score(&vec![2.5, 2.5, 0.0], &weights);
+        let unbalanced = geometric_score(&vec![5.0, 0.0, 0.0], &weights);
+        assert!((balanced - 5.0).abs() < 1e-9);
+        assert!((unbalanced - 1.0).abs() < 1e-9);
+        assert!(balanced > unbalanced);
+        assert_eq!(geometric_score(&vec![0.0, 0.0], &vec![0, 0]), 0.0);
+        assert!(
+            (normalized_score_of(&vec![2.5, 2.5, 0.0], &weights, Formula::GeoMean) - 50.0).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn it_splits_board_formula() {
+        let weights: Weights = vec![5, 5, 5];
+        let board_name =
+            formula_board_name(get_board_name(&weights, None, Norm::Ci95), Formula::GeoMean);
+        assert_eq!(board_name, "geomean:ci95:5,5,5");
+        assert_eq!(
+            split_board_formula(&board_name),
+            (Formula::GeoMean, "ci95:5,5,5")
+        );
+        assert_eq!(
+            formula_board_name("5,5,5".to_string(), Formula::Sum),
+            "5,5,5"
+        );
+        assert_eq!(split_board_formula("5,5,5"), (Formula::Sum, "5,5,5"));
+        // Limits of boards scored by each formula are cached separately
+        assert_eq!(
+            limits_board_name(&weights, Norm::MinMax, Formula::GeoMean, true),
+            "hist:geomean:5,5,5"
+        );
+        assert_eq!(
+            limits_board_name(&weights, Norm::MinMax, Formula::Sum, false),
+            "5,5,5"
+        );
+    }
+
+    #[test]
+    fn it_splits_board_smooth() {
+        let board_name = smoothed_board_name(
+            formula_board_name("5,5,5".to_string(), Formula::GeoMean),
+            true,
+        );
+        assert_eq!(board_name, "smooth:geomean:5,5,5");
+        assert_eq!(split_board_smooth(&board_name), (true, "geomean:5,5,5"));
+        assert_eq!(smoothed_board_name("5,5,5".to_string(), false), "5,5,5");
+        assert_eq!(split_board_smooth("5,5,5"), (false, "5,5,5"));
+    }
+
+    #[test]
+    fn it_smooths_board_scores() {
+        assert_eq!(smoothed_score(40.0, None, 0.5), 40.0);
+        assert_eq!(smoothed_score(40.0, Some(20.0), 0.5), 30.0);
+        assert_eq!(smoothed_score(40.0, Some(20.0), 0.25), 25.0);
+        assert_eq!(smoothed_score(40.0, Some(20.0), 1.0), 40.0);
+        // Alpha is bounded between 0 and 1
+        assert_eq!(smoothed_score(40.0, Some(20.0), 2.0), 40.0);
+        assert_eq!(smoothed_score(40.0, Some(20.0), -1.0), 20.0);
+    }
+
+    #[test]
+    fn it_bounds_board_by_min_score() {
+        assert_eq!(min_score_bound(None).unwrap(), "0");
+        assert_eq!(min_score_bound(Some(12.5)).unwrap(), "12.5");
+        assert!(min_score_bound(Some(f64::NAN)).is_err());
+        assert!(min_score_bound(Some(f64::INFINITY)).is_err());
+    }
+
+    #[test]
+    fn it_counts_recommended_validators() {
+        assert_eq!(recommended_count(1000, 10), 100);
+        // Rounded up so that small boards still have recommended validators
+        assert_eq!(recommended_count(15, 10), 2);
+        assert_eq!(recommended_count(0, 10), 0);
+        assert_eq!(recommended_count(1000, 0), 0);
+        assert_eq!(recommended_count(1000, 150), 1000);
+    }
+
+    #[test]
+    fn it_parses_presets_as_board_params() {
+        let (weights, intervals) = parse_preset("9,0,5", "").unwrap();
+        assert_eq!(weights.len(), WEIGHTS_CAPACITY);
+        assert_eq!(&weights[..3], &[9, 0, 5]);
+        // Positions not given take the default weights
+        assert_eq!(weights[3], 5);
+        assert!(intervals.is_empty());
+
+        let (_, intervals) = parse_preset("", ",5:10").unwrap();
+        assert_eq!(intervals.len(), INTERVALS_CAPACITY);
+        assert_eq!(intervals[1].min, 50000000.0);
+
+        assert!(parse_preset("9,x", "").is_err());
+    }
+
+    #[test]
+    fn it_regenerates_boards_when_excluded_stashes_change() {
+        // Boards generated with the same list of excluded stashes are kept
+        assert_eq!(expected_members(Some(300), None, None), Some(300));
+        assert_eq!(
+            expected_members(Some(300), Some("a1"), Some("a1")),
+            Some(300)
+        );
+        // Stashes excluded or no longer excluded since the board was generated
+        assert_eq!(expected_members(Some(300), None, Some("a1")), None);
+        assert_eq!(expected_members(Some(300), Some("a1"), None), None);
+        assert_eq!(expected_members(Some(300), Some("a1"), Some("b2")), None);
+        assert!(!is_board_complete(
+            expected_members(Some(300), Some("a1"), Some("b2")),
+            300
+        ));
+    }
+
+    #[test]
+    fn it_regenerates_partially_written_boards() {
+        // Generation interrupted before the completion marker was written
+        assert!(!is_board_complete(None, 42));
+        // Members removed or not yet cached
+        assert!(!is_board_complete(Some(300), 42));
+        // Regeneration fills in the board and writes the marker
+        assert!(is_board_complete(Some(300), 300));
+        // A board without eligible candidates is complete once marked
+        assert!(is_board_complete(Some(0), 0));
+        assert!(!is_board_complete(None, 0));
+
+        let candidates: Vec<(AccountId32, Validator)> = (1..=4u8)
+            .map(|i| {
+                let mut data: ValidatorCache = BTreeMap::new();
+                data.insert("own_stake".to_string(), (i as u32 * 100).to_string());
+                data.insert("blocked".to_string(), (i == 4).to_string());
+                (AccountId32::new([i; 32]), data.into())
+            })
+            .collect();
+        let excluded = candidates[2].0.to_string();
+        let weights: Weights = vec![5; WEIGHTS_CAPACITY];
+
+        // Generation interrupted after the first member was cached
+        let mut board: BTreeMap<String, f64> = BTreeMap::new();
+        board.insert(candidates[0].0.to_string(), 1.0);
+        let expected: Option<usize> = None;
+        assert!(!is_board_complete(expected, board.len()));
+
+        // Regeneration clears the board, fills it in with every eligible candidate
+        // and writes the marker
+        board.clear();
+        let members = score_board_candidates(
+            &candidates,
+            &BoardLimits::default(),
+            &weights,
+            Formula::Sum,
+            &BTreeMap::new(),
+            0.5,
+            |stash| stash == excluded,
+        )
+        .unwrap();
+        for member in members.iter() {
+            board.insert(member.stash.clone(), member.score);
+        }
+        let expected = Some(members.len());
+        assert!(is_board_complete(expected, board.len()));
+        assert_eq!(board.len(), 2);
+        assert!(!board.contains_key(&excluded));
+        assert!(!board.contains_key(&candidates[3].0.to_string()));
+    }
+
+    #[test]
+    fn it_keeps_default_boards_from_expiring() {
+        let weights: Weights = DEFAULT_WEIGHTS
+            .iter()
+            .map(|x| x.parse::<u32>().unwrap())
+            .collect();
+        assert!(is_default_board(&get_board_name(
+            &weights,
+            None,
+            Norm::MinMax
+        )));
+        assert!(is_default_board(&get_board_name(
+            &weights,
+            Some(&vec![]),
+            Norm::MinMax
+        )));
+        let mut weights = weights.clone();
+        weights[0] = 9;
+        assert!(!is_default_board(&get_board_name(
+            &weights,
+            None,
+            Norm::MinMax
+        )));
+        assert!(!is_default_board(&get_board_name(
+            &weights,
+            None,
+            Norm::Ci95
+        )));
+    }
+
+    #[test]
+    fn it_names_boards_by_norm() {
+        let weights: Weights = vec![5, 5, 5];
+        let intervals: Intervals = vec![Interval {
+            min: 0.0,
+            max: 10.0,
+        }];
+        assert_eq!(
+            get_board_name(&weights, Some(&intervals), Norm::MinMax),
+            "5,5,5|0:10"
+        );
+        assert_eq!(
+            get_board_name(&weights, Some(&intervals), Norm::Ci95),
+            "ci95:5,5,5|0:10"
+        );
+        assert_eq!(
+            split_board_norm("ci95:5,5,5|0:10"),
+            (Norm::Ci95, "5,5,5|0:10")
+        );
+        assert_eq!(split_board_norm("5,5,5"), (Norm::MinMax, "5,5,5"));
+    }
+
+    #[test]
+    fn it_breaks_ties_by_scores_spread() {
+        let balanced = composite_score(&vec![5.0, 5.0, 5.0]);
+        let unbalanced = composite_score(&vec![9.0, 6.0, 0.0]);
+        assert_eq!(balanced, 15.0);
+        assert!(balanced > unbalanced);
+        // Genuinely different scores keep their order
+        assert!(unbalanced > composite_score(&vec![5.0, 5.0, 4.99999]));
+        assert_eq!(composite_score(&vec![]), 0.0);
+    }
+
+    #[test]
+    fn it_keeps_era_limits_by_norm() {
+        let mut limits: BoardLimitsCache = BTreeMap::new();
+        limits.insert("min_own_stake".to_string(), "1".to_string());
+        limits.insert(
+            "max_own_stake".to_string(),
+            "1267650600228229401496703205377".to_string(),
+        );
+        let fields = era_limits_fields(Norm::Ci95, &limits);
+        assert!(fields.contains(&(
+            "ci95:max_own_stake".to_string(),
+            "1267650600228229401496703205377".to_string()
+        )));
+
+        let mut data: BoardLimitsCache = fields.into_iter().collect();
+        data.insert("minmax:max_own_stake".to_string(), "20".to_string());
+        assert_eq!(parse_era_limits(Norm::Ci95, data.clone()), limits);
+        assert_eq!(parse_era_limits(Norm::MinMax, data).len(), 1);
+        assert!(parse_era_limits(Norm::Ci95, BTreeMap::new()).is_empty());
+        // Stake limits keep every planck
+        let board_limits = BoardLimits::from(limits);
+        assert_eq!(board_limits.own_stake.max, (1 << 100) + 1);
+        assert_eq!(board_limits.total_stake.max, 100);
+    }
+}
@@ -29,34 +29,52 @@ use mobc_redis::RedisConnectionManager;
 use std::time::Duration;
 use std::{thread, time};
 
-const CACHE_POOL_MAX_OPEN: u64 = 20;
-const CACHE_POOL_MAX_IDLE: u64 = 8;
-const CACHE_POOL_TIMEOUT_SECONDS: u64 = 30;
-const CACHE_POOL_EXPIRE_SECONDS: u64 = 60;
-
 pub type RedisPool = Pool<RedisConnectionManager>;
 pub type RedisConn = Connection<RedisConnectionManager>;
 
+/// Build the Redis connection url, `rediss://` connections require the `tls` feature.
+/// Note: server certificates are verified against the system trust store,
+/// SSL_CERT_FILE can be used to point to a custom CA bundle. The TLS connector is built
+/// by the redis client from the url, so there is no dedicated CA or server name setting:
+/// the host of the url is the server name
 fn get_redis_url(config: Config) -> String {
+    let scheme = if config.redis_tls { "rediss" } else { "redis" };
+    let fragment = if config.redis_tls && config.redis_tls_insecure {
+        "#insecure"
+    } else {
+        ""
+    };
     format!(
-        "redis://:{}@{}/{}",
-        config.redis_password, config.redis_hostname, config.redis_database
+        "{}://:{}@{}/{}{}",
+        scheme, config.redis_password, config.redis_hostname, config.redis_database, fragment
     )
     .to_string()
 }
 
 pub fn create_pool(config: Config) -> Result<RedisPool, CacheError> {
-    let redis_url = get_redis_url(config);
+    let redis_url = get_redis_url(config.clone());
     let client = redis::Client::open(redis_url).map_err(CacheError::RedisClientError)?;
     let manager = RedisConnectionManager::new(client);
     Ok(Pool::builder()
-        .get_timeout(Some(Duration::from_secs(CACHE_POOL_TIMEOUT_SECONDS)))
-        .max_open(CACHE_POOL_MAX_OPEN)
-        .max_idle(CACHE_POOL_MAX_IDLE)
-        .max_lifetime(Some(Duration::from_secs(CACHE_POOL_EXPIRE_SECONDS)))
+        .get_timeout(Some(Duration::from_secs(config.cache_pool_timeout_seconds)))
+        .max_open(config.cache_pool_max_open)
+        .max_idle(config.cache_pool_max_idle)
+        .max_lifetime(Some(Duration::from_secs(config.cache_pool_expire_seconds)))
         .build(manager))
 }
 
+/// Create a dedicated connection for pub/sub, since a subscribed connection
+/// can't be returned to the pool
+pub async fn create_pubsub(config: Config) -> Result<redis::aio::PubSub, CacheError> {
+    let redis_url = get_redis_url(config);
+    let client = redis::Client::open(redis_url).map_err(CacheError::RedisClientError)?;
+    let conn = client
+        .get_async_connection()
+        .await
+        .map_err(CacheError::RedisClientError)?;
+    Ok(conn.into_pubsub())
+}
+
 pub fn create_or_await_pool(config: Config) -> RedisPool {
     loop {
         match create_pool(config.clone()) {
@@ -78,3 +96,33 @@ pub fn add_pool(cfg: &mut web::ServiceConfig) {
 pub async fn get_conn(pool: &RedisPool) -> Result<RedisConn, CacheError> {
     pool.get().await.map_err(CacheError::RedisPoolError)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_gets_a_redis_url() {
+        let mut config = CONFIG.clone();
+        config.redis_password = "secret".to_string();
+        config.redis_hostname = "127.0.0.1:6379".to_string();
+        config.redis_database = 2;
+        config.redis_tls = false;
+        config.redis_tls_insecure = true;
+        assert_eq!(
+            get_redis_url(config.clone()),
+            "redis://:secret@127.0.0.1:6379/2"
+        );
+        config.redis_tls = true;
+        config.redis_tls_insecure = false;
+        assert_eq!(
+            get_redis_url(config.clone()),
+            "rediss://:secret@127.0.0.1:6379/2"
+        );
+        config.redis_tls_insecure = true;
+        assert_eq!(
+            get_redis_url(config),
+            "rediss://:secret@127.0.0.1:6379/2#insecure"
+        );
+    }
+}
@@ -30,23 +30,240 @@
 //
 // Set Config struct into a CONFIG lazy_static to avoid multiple processing.
 //
+use chrono::Utc;
 use dotenv;
 use lazy_static::lazy_static;
 use log::info;
 use serde::Deserialize;
-use std::env;
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    hash::{Hash, Hasher},
+    io::Write,
+};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Config {
     pub turboflakes_host: String,
     pub turboflakes_port: u16,
     pub turboflakes_featured_stashes: Vec<String>,
+    // Comma-separated list of origins allowed by CORS, * allows any origin and an entry
+    // starting with a dot allows the subdomains of a domain, e.g. .turboflakes.io
+    #[serde(default = "default_turboflakes_cors_allow_origin")]
+    pub turboflakes_cors_allow_origin: Vec<String>,
+    #[serde(default = "default_turboflakes_cors_allow_methods")]
+    pub turboflakes_cors_allow_methods: Vec<String>,
+    #[serde(default = "default_turboflakes_cors_allow_headers")]
+    pub turboflakes_cors_allow_headers: Vec<String>,
     pub rust_backtrace: u8,
     pub rust_log: String,
-    pub substrate_ws_url: String,
+    // Log records as plain text or as json objects
+    #[serde(default)]
+    pub log_format: LogFormat,
+    // Comma-separated list of endpoints, the next one is tried if the connection fails
+    pub substrate_ws_url: Vec<String>,
+    // Time to wait for a substrate node connection before trying the next endpoint
+    #[serde(default = "default_substrate_ws_connect_timeout_seconds")]
+    pub substrate_ws_connect_timeout_seconds: u64,
     pub redis_hostname: String,
     pub redis_password: String,
     pub redis_database: u8,
+    // Prefix of every key, so that instances of different chains can share a database
+    #[serde(default)]
+    pub redis_key_prefix: String,
+    // Connect to Redis over TLS, requires the tls feature
+    #[serde(default)]
+    pub redis_tls: bool,
+    // Skip the verification of the Redis server certificate
+    #[serde(default)]
+    pub redis_tls_insecure: bool,
+    // Number of eras synced concurrently while backfilling history
+    #[serde(default = "default_sync_era_concurrency")]
+    pub sync_era_concurrency: usize,
+    // Time to live of the boards generated by custom weights or intervals, 0 to keep them
+    #[serde(default = "default_board_ttl_seconds")]
+    pub board_ttl_seconds: usize,
+    // Number of past eras searched for a board to serve while syncing, 0 to always wait
+    #[serde(default = "default_board_stale_fallback_eras")]
+    pub board_stale_fallback_eras: u32,
+    // Weight (0-1) of the current era score in the smoothed boards, the rest is given
+    // to the score of the era before
+    #[serde(default = "default_board_smoothing_alpha")]
+    pub board_smoothing_alpha: f64,
+    // Maximum number of eras synced, the chain history depth is used if not set
+    #[serde(default)]
+    pub sync_history_depth: Option<u32>,
+    // Shared secret required by admin routes, admin routes are disabled if empty
+    #[serde(default)]
+    pub admin_token: String,
+    // Comma-separated list of keys accepted in the Authorization header of write and
+    // admin routes, in addition to the admin token
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    // Require a key for every API route, read-only routes included
+    #[serde(default)]
+    pub require_auth_all: bool,
+    // Number of retries of a failed chain storage read during sync
+    #[serde(default = "default_sync_retries")]
+    pub sync_retries: u32,
+    // Maximum number of websocket clients connected at the same time
+    #[serde(default = "default_ws_max_connections")]
+    pub ws_max_connections: usize,
+    // Time to live of validators not found, 0 to always look them up
+    #[serde(default = "default_not_found_ttl_seconds")]
+    pub not_found_ttl_seconds: usize,
+    // Redis connection pool sizing
+    #[serde(default = "default_cache_pool_max_open")]
+    pub cache_pool_max_open: u64,
+    #[serde(default = "default_cache_pool_max_idle")]
+    pub cache_pool_max_idle: u64,
+    #[serde(default = "default_cache_pool_timeout_seconds")]
+    pub cache_pool_timeout_seconds: u64,
+    #[serde(default = "default_cache_pool_expire_seconds")]
+    pub cache_pool_expire_seconds: u64,
+    // Weighting of the eras in the average reward points, none weights every era equally
+    #[serde(default)]
+    pub avg_reward_points_weighting: Weighting,
+    // Active only calculates the inclusion rate and average reward points of the active set
+    #[serde(default)]
+    pub sync_mode: SyncMode,
+    // Subscribe to new sessions to record the reward points of every validator per session
+    #[serde(default)]
+    pub sync_session_points: bool,
+    // Interval between checks of the cached active era against the chain, a sync is
+    // triggered if they diverge, e.g. an era payout event missed, 0 to disable
+    #[serde(default = "default_sync_fallback_interval_seconds")]
+    pub sync_fallback_interval_seconds: u64,
+    // Weights of the board generated on every sync to flag the recommended validators
+    #[serde(default = "default_recommended_weights")]
+    pub recommended_weights: String,
+    // Share (0-100) of the top validators in the recommended board flagged as recommended
+    #[serde(default = "default_recommended_top_percent")]
+    pub recommended_top_percent: u32,
+    // Comma-separated list of stashes never listed nor scored by any board
+    #[serde(default)]
+    pub excluded_stashes: Vec<String>,
+}
+
+/// How much of the validators history is calculated on every sync
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMode {
+    Active,
+    Full,
+}
+
+impl Default for SyncMode {
+    fn default() -> SyncMode {
+        SyncMode::Full
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> LogFormat {
+        LogFormat::Text
+    }
+}
+
+/// How eras are weighted in an average, recent eras are weighted higher by
+/// linear or exponential decay
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Weighting {
+    None,
+    Linear,
+    Exponential,
+}
+
+impl Default for Weighting {
+    fn default() -> Weighting {
+        Weighting::None
+    }
+}
+
+/// Host of an origin, e.g. `https://app.turboflakes.io:8443` -> `app.turboflakes.io`
+fn origin_host(origin: &str) -> &str {
+    let host = origin.splitn(2, "://").nth(1).unwrap_or(origin);
+    host.split(':').next().unwrap_or(host)
+}
+
+fn default_turboflakes_cors_allow_origin() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_turboflakes_cors_allow_methods() -> Vec<String> {
+    vec!["GET".to_string(), "POST".to_string(), "OPTIONS".to_string()]
+}
+
+fn default_turboflakes_cors_allow_headers() -> Vec<String> {
+    vec!["Content-Type".to_string(), "Authorization".to_string()]
+}
+
+fn default_substrate_ws_connect_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_sync_era_concurrency() -> usize {
+    4
+}
+
+fn default_board_ttl_seconds() -> usize {
+    86400
+}
+
+fn default_board_stale_fallback_eras() -> u32 {
+    1
+}
+
+fn default_recommended_weights() -> String {
+    "5,5,5,5,5,5,5,5,5,5,0,0,0".to_string()
+}
+
+fn default_recommended_top_percent() -> u32 {
+    10
+}
+
+fn default_board_smoothing_alpha() -> f64 {
+    0.5
+}
+
+fn default_sync_fallback_interval_seconds() -> u64 {
+    600
+}
+
+fn default_sync_retries() -> u32 {
+    3
+}
+
+fn default_ws_max_connections() -> usize {
+    1024
+}
+
+fn default_not_found_ttl_seconds() -> usize {
+    60
+}
+
+fn default_cache_pool_max_open() -> u64 {
+    20
+}
+
+fn default_cache_pool_max_idle() -> u64 {
+    8
+}
+
+fn default_cache_pool_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_cache_pool_expire_seconds() -> u64 {
+    60
 }
 
 // Set Config struct into a CONFIG lazy_static to avoid multiple processing
@@ -59,14 +276,105 @@ fn get_config() -> Config {
     let config_filename = env::var("TURBOFLAKES_CONFIG_FILENAME").unwrap_or(".env".to_string());
     dotenv::from_filename(&config_filename).ok();
 
-    env_logger::try_init().unwrap_or_default();
+    let config = match envy::from_env::<Config>() {
+        Ok(config) => config,
+        Err(error) => panic!("Configuration error: {:#?}", error),
+    };
+
+    init_logger(config.log_format);
 
     info!("Loading configuration from {} file", &config_filename);
 
-    match envy::from_env::<Config>() {
-        Ok(config) => config,
-        Err(error) => panic!("Configuration error: {:#?}", error),
+    config
+}
+
+impl Config {
+    /// Check if the request origin matches any of the origins allowed by CORS.
+    /// Note: origins must match exactly, only an entry starting with a dot matches the
+    /// origins with a host ending with it, e.g. `.turboflakes.io` its subdomains
+    pub fn is_cors_origin_allowed(&self, origin: &str) -> bool {
+        self.turboflakes_cors_allow_origin
+            .iter()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .any(|x| {
+                x == "*" || x == origin || (x.starts_with('.') && origin_host(origin).ends_with(x))
+            })
     }
+
+    /// Substrate node endpoints in the order they are tried
+    pub fn substrate_ws_urls(&self) -> Vec<String> {
+        self.substrate_ws_url
+            .iter()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    /// Check if the stash is in the list of excluded stashes
+    pub fn is_stash_excluded(&self, stash: &str) -> bool {
+        self.excluded_stashes.iter().any(|x| x.trim() == stash)
+    }
+
+    /// Hash of the list of excluded stashes, none if no stash is excluded.
+    /// Note: boards record the hash they were generated with, so that they are
+    /// regenerated once the list changes
+    pub fn excluded_stashes_hash(&self) -> Option<String> {
+        let mut stashes: Vec<&str> = self
+            .excluded_stashes
+            .iter()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect();
+        if stashes.is_empty() {
+            return None;
+        }
+        stashes.sort_unstable();
+        stashes.dedup();
+        let mut hasher = DefaultHasher::new();
+        stashes.hash(&mut hasher);
+        Some(format!("{:x}", hasher.finish()))
+    }
+
+    /// Keys accepted by the guarded routes, the admin token included
+    pub fn api_keys(&self) -> Vec<&str> {
+        std::iter::once(&self.admin_token)
+            .chain(self.api_keys.iter())
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+}
+
+/// Initialize the logger, records are filtered by RUST_LOG in both formats
+fn init_logger(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "{}",
+                json_log_line(
+                    &Utc::now().to_rfc3339(),
+                    &record.level().to_string(),
+                    record.target(),
+                    &record.args().to_string(),
+                )
+            )
+        });
+    }
+    builder.try_init().unwrap_or_default();
+}
+
+/// Log record as a single line json object
+fn json_log_line(timestamp: &str, level: &str, target: &str, message: &str) -> String {
+    serde_json::json!({
+        "timestamp": timestamp,
+        "level": level,
+        "target": target,
+        "message": message,
+    })
+    .to_string()
 }
 
 #[cfg(test)]
@@ -79,6 +387,118 @@ mod tests {
         assert_ne!(config.rust_log, "".to_string());
     }
 
+    #[test]
+    fn it_defaults_sync_era_concurrency() {
+        let config = get_config();
+        if env::var("SYNC_ERA_CONCURRENCY").is_err() {
+            assert_eq!(config.sync_era_concurrency, 4);
+        }
+    }
+
+    #[test]
+    fn it_allows_cors_origins() {
+        let mut config = get_config();
+        config.turboflakes_cors_allow_origin = default_turboflakes_cors_allow_origin();
+        assert!(config.is_cors_origin_allowed("https://example.com"));
+
+        config.turboflakes_cors_allow_origin = vec![
+            "http://localhost:3000".to_string(),
+            "https://turboflakes.io".to_string(),
+        ];
+        assert!(config.is_cors_origin_allowed("http://localhost:3000"));
+        assert!(config.is_cors_origin_allowed("https://turboflakes.io"));
+        assert!(!config.is_cors_origin_allowed("http://localhost:3001"));
+        assert!(!config.is_cors_origin_allowed("https://example.com"));
+        // Origins only ending with an allowed origin are not allowed
+        assert!(!config.is_cors_origin_allowed("https://app.turboflakes.io"));
+        assert!(!config.is_cors_origin_allowed("https://eviturboflakes.io"));
+    }
+
+    #[test]
+    fn it_allows_cors_subdomains_by_explicit_rule() {
+        let mut config = get_config();
+        config.turboflakes_cors_allow_origin = vec![".turboflakes.io".to_string()];
+        assert!(config.is_cors_origin_allowed("https://app.turboflakes.io"));
+        assert!(config.is_cors_origin_allowed("http://beta.app.turboflakes.io:8080"));
+        assert!(!config.is_cors_origin_allowed("https://turboflakes.io"));
+        assert!(!config.is_cors_origin_allowed("https://eviturboflakes.io"));
+        assert!(!config.is_cors_origin_allowed("https://turboflakes.io.example.com"));
+    }
+
+    #[test]
+    fn it_lists_substrate_ws_urls() {
+        let mut config = get_config();
+        config.substrate_ws_url = vec![
+            "wss://rpc.example.com".to_string(),
+            " wss://backup.example.com ".to_string(),
+            "".to_string(),
+        ];
+        assert_eq!(
+            config.substrate_ws_urls(),
+            vec!["wss://rpc.example.com", "wss://backup.example.com"]
+        );
+    }
+
+    #[test]
+    fn it_checks_excluded_stashes() {
+        let mut config = get_config();
+        config.excluded_stashes = vec![
+            "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY".to_string(),
+            " 5HEvZ3LYZCN3qt7B4ZwmiTyqgRhbDTaZATavCT1Mq86dN1h7 ".to_string(),
+            "".to_string(),
+        ];
+        assert!(config.is_stash_excluded("5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"));
+        assert!(config.is_stash_excluded("5HEvZ3LYZCN3qt7B4ZwmiTyqgRhbDTaZATavCT1Mq86dN1h7"));
+        assert!(!config.is_stash_excluded("5CVFESwfkk7NmhQ6FwHCM9roBvr9BGa4vJHFYU8DnGQxrXvz"));
+    }
+
+    #[test]
+    fn it_hashes_excluded_stashes() {
+        let mut config = get_config();
+        config.excluded_stashes = vec![" ".to_string()];
+        assert_eq!(config.excluded_stashes_hash(), None);
+        config.excluded_stashes = vec![
+            "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY".to_string(),
+            "5HEvZ3LYZCN3qt7B4ZwmiTyqgRhbDTaZATavCT1Mq86dN1h7".to_string(),
+        ];
+        let hash = config.excluded_stashes_hash();
+        assert!(hash.is_some());
+        // Order, whitespace and duplicates do not change the hash
+        config.excluded_stashes = vec![
+            " 5HEvZ3LYZCN3qt7B4ZwmiTyqgRhbDTaZATavCT1Mq86dN1h7".to_string(),
+            "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY".to_string(),
+            "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY".to_string(),
+        ];
+        assert_eq!(config.excluded_stashes_hash(), hash);
+        config.excluded_stashes =
+            vec!["5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY".to_string()];
+        assert_ne!(config.excluded_stashes_hash(), hash);
+    }
+
+    #[test]
+    fn it_lists_api_keys() {
+        let mut config = get_config();
+        config.admin_token = "".to_string();
+        config.api_keys = vec![];
+        assert!(config.api_keys().is_empty());
+        config.admin_token = "s3cr3t".to_string();
+        config.api_keys = vec![" k3y ".to_string(), "".to_string()];
+        assert_eq!(config.api_keys(), vec!["s3cr3t", "k3y"]);
+    }
+
+    #[test]
+    fn it_formats_a_json_log_line() {
+        assert_eq!(
+            json_log_line(
+                "2021-10-17T00:00:00+00:00",
+                "INFO",
+                "turboflakes_backend::sync",
+                "Era \"2470\" synced"
+            ),
+            r#"{"level":"INFO","message":"Era \"2470\" synced","target":"turboflakes_backend::sync","timestamp":"2021-10-17T00:00:00+00:00"}"#
+        );
+    }
+
     #[test]
     fn it_gets_a_config_from_the_lazy_static() {
         let config = &CONFIG;
@@ -19,7 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use actix_web::{error::ResponseError, HttpResponse};
+use crate::sync::sync::NODE_RECONNECT_DELAY_SECS;
+use actix_web::{
+    error::{QueryPayloadError, ResponseError},
+    http::header,
+    HttpRequest, HttpResponse,
+};
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -28,43 +33,99 @@ use thiserror::Error;
 #[allow(dead_code)]
 pub enum ApiError {
     BadRequest(String),
+    Unauthorized(String),
     NotFound(String),
+    Conflict(String),
     InternalServerError(String),
+    ServiceUnavailable(String),
+    NodeUnavailable(String),
+}
+
+/// Seconds a client is asked to wait before retrying a temporarily unavailable resource
+pub const RETRY_AFTER_SECONDS: u32 = 60;
+
+impl ApiError {
+    /// Machine-readable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::BadRequest(_) => ErrorCode::BadRequest,
+            ApiError::Unauthorized(_) => ErrorCode::Unauthorized,
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::InternalServerError(_) => ErrorCode::InternalServerError,
+            ApiError::ServiceUnavailable(_) => ErrorCode::Syncing,
+            ApiError::NodeUnavailable(_) => ErrorCode::NodeUnavailable,
+        }
+    }
 }
 
 /// Automatically convert ApiErrors to external Response Errors
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse {
+        let code = self.code();
         match self {
             ApiError::BadRequest(error) => {
-                HttpResponse::BadRequest().json::<ErrorResponse>(error.into())
+                HttpResponse::BadRequest().json(ErrorResponse::new(code, error))
+            }
+            ApiError::Unauthorized(message) => {
+                HttpResponse::Unauthorized().json(ErrorResponse::new(code, message))
             }
             ApiError::NotFound(message) => {
-                HttpResponse::NotFound().json::<ErrorResponse>(message.into())
+                HttpResponse::NotFound().json(ErrorResponse::new(code, message))
+            }
+            ApiError::Conflict(message) => {
+                HttpResponse::Conflict().json(ErrorResponse::new(code, message))
             }
             ApiError::InternalServerError(error) => {
-                HttpResponse::InternalServerError().json::<ErrorResponse>(error.into())
+                HttpResponse::InternalServerError().json(ErrorResponse::new(code, error))
             }
+            ApiError::ServiceUnavailable(message) => HttpResponse::ServiceUnavailable()
+                .header(header::RETRY_AFTER, RETRY_AFTER_SECONDS.to_string())
+                .json(ErrorResponse::new(code, message)),
+            // Note: the node connection is attempted again every few seconds
+            ApiError::NodeUnavailable(message) => HttpResponse::ServiceUnavailable()
+                .header(header::RETRY_AFTER, NODE_RECONNECT_DELAY_SECS.to_string())
+                .json(ErrorResponse::new(code, message)),
         }
     }
 }
 
+/// Convert query string deserialization errors into a BadRequest ApiError
+pub fn query_error_handler(error: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(error.to_string()).into()
+}
+
 impl From<&str> for ApiError {
     fn from(error: &str) -> Self {
         ApiError::InternalServerError(error.into())
     }
 }
 
+/// Stable error codes, so that clients don't need to match the error messages
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    NotFound,
+    Conflict,
+    InternalServerError,
+    Syncing,
+    RateLimited,
+    NodeUnavailable,
+}
+
 /// User-friendly error messages
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ErrorResponse {
+    code: ErrorCode,
     errors: Vec<String>,
 }
 
-/// Utility to make transforming a string reference into an ErrorResponse
-impl From<&String> for ErrorResponse {
-    fn from(error: &String) -> Self {
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, error: &String) -> Self {
         ErrorResponse {
+            code,
             errors: vec![error.into()],
         }
     }
@@ -109,8 +170,72 @@ pub enum SyncError {
     CacheError(#[from] CacheError),
     #[error("Substrate_subxt error: {0}")]
     SubxtError(#[from] subxt::Error),
+    #[error("Metadata error: {0}")]
+    MetadataError(#[from] subxt::MetadataError),
+    #[error("Codec error: {0}")]
+    CodecError(#[from] codec::Error),
+    #[error("Serde json error: {0}")]
+    JsonError(#[from] serde_json::Error),
     #[error("Subscription finished")]
     SubscriptionFinished,
     #[error("Other error: {0}")]
     Other(String),
 }
+
+impl SyncError {
+    /// Errors that can be recovered from without a new connection to the substrate node
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SyncError::SubxtError(_) | SyncError::SubscriptionFinished => false,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    #[test]
+    fn it_serializes_error_codes() {
+        let error = ApiError::NotFound("not found".to_string());
+        assert_eq!(
+            serde_json::to_string(&ErrorResponse::new(error.code(), &error.to_string())).unwrap(),
+            r#"{"code":"NOT_FOUND","errors":["not found"]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ApiError::ServiceUnavailable("".to_string()).code()).unwrap(),
+            r#""SYNCING""#
+        );
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::InternalServerError).unwrap(),
+            r#""INTERNAL_SERVER_ERROR""#
+        );
+    }
+
+    #[test]
+    fn it_responds_node_unavailable_with_retry_after() {
+        let error = ApiError::NodeUnavailable("node unreachable".to_string());
+        assert_eq!(
+            serde_json::to_string(&error.code()).unwrap(),
+            r#""NODE_UNAVAILABLE""#
+        );
+        let resp = error.error_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            resp.headers().get(header::RETRY_AFTER).unwrap(),
+            &NODE_RECONNECT_DELAY_SECS.to_string()
+        );
+    }
+
+    #[test]
+    fn it_responds_service_unavailable_with_retry_after() {
+        let resp = ApiError::ServiceUnavailable("syncing".to_string()).error_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            resp.headers().get(header::RETRY_AFTER).unwrap(),
+            &RETRY_AFTER_SECONDS.to_string()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::board::is_syncing;
+use crate::cache::{get_conn, RedisPool};
+use crate::config::CONFIG;
+use crate::errors::{ApiError, CacheError};
+use crate::helpers::node_connected_or_unavailable;
+use crate::sync::sync;
+use actix_web::{dev::Payload, http::header, web::Data, FromRequest, HttpRequest, HttpResponse};
+use chrono::Utc;
+use futures::future::{ready, Ready};
+use log::{info, warn};
+use redis::aio::Connection;
+
+/// Header holding the shared secret for admin routes, still accepted along the Authorization header
+const ADMIN_TOKEN_HEADER: &'static str = "X-Admin-Token";
+
+/// Scheme of the key given in the Authorization header
+const BEARER_SCHEME: &'static str = "Bearer ";
+
+/// Key given in the Authorization header, with or without the bearer scheme
+fn bearer_key(value: &str) -> &str {
+    value.strip_prefix(BEARER_SCHEME).unwrap_or(value).trim()
+}
+
+/// Verify the key of a request to a guarded route.
+/// Note: the key is never logged, only that it is missing or invalid
+pub fn authorize(req: &HttpRequest) -> Result<(), ApiError> {
+    let headers = req.headers();
+    let key = match headers.get(header::AUTHORIZATION) {
+        Some(value) => value.to_str().ok().map(bearer_key),
+        None => headers
+            .get(ADMIN_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    };
+    if !is_authorized(key, &CONFIG.api_keys()) {
+        let msg = format!("Missing or invalid Authorization header");
+        warn!("{}", msg);
+        return Err(ApiError::Unauthorized(msg));
+    }
+    Ok(())
+}
+
+/// Path left open even if REQUIRE_AUTH_ALL is set, so that health checks need no key
+const HEALTH_PATH: &'static str = "/health";
+
+/// Check if the route is open even if REQUIRE_AUTH_ALL is set
+fn is_public_path(path: &str) -> bool {
+    path == HEALTH_PATH
+}
+
+/// Verify the key of a request to any route but the health check if REQUIRE_AUTH_ALL
+/// is set, otherwise only write and admin routes are guarded
+pub fn authorize_all(req: &HttpRequest) -> Result<(), ApiError> {
+    if !CONFIG.require_auth_all || is_public_path(req.path()) {
+        return Ok(());
+    }
+    authorize(req)
+}
+
+/// Extractor of a request carrying a valid key, handlers taking it respond 401 otherwise
+pub struct Authorized;
+
+impl FromRequest for Authorized {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authorize(req).map(|_| Authorized))
+    }
+}
+
+/// Trigger a full resync of the cache in the background
+pub async fn post_resync(
+    _auth: Authorized,
+    cache: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    // A resync reads everything from the node
+    node_connected_or_unavailable()?;
+
+    if is_syncing(cache.clone()).await? {
+        let msg = format!("The system is already syncing.");
+        warn!("{}", msg);
+        return Err(ApiError::Conflict(msg));
+    }
+
+    // Flag the cache as syncing straight away so that a second request is refused
+    let mut conn = get_conn(&cache).await?;
+    let _: () = redis::cmd("HSET")
+        .arg(sync::Key::Info)
+        .arg(&[
+            ("syncing", "true".to_string()),
+            ("syncing_started_at", Utc::now().timestamp().to_string()),
+        ])
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    info!("Full resync requested");
+    sync::spawn_and_restart_history_on_error(Some(true));
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Guarded routes are disabled if no key is configured
+fn is_authorized(key: Option<&str>, api_keys: &[&str]) -> bool {
+    match key {
+        Some(key) => api_keys
+            .iter()
+            .filter(|x| !x.is_empty())
+            .fold(false, |acc, x| constant_time_eq(key, x) | acc),
+        None => false,
+    }
+}
+
+/// Compare tokens without leaking the position of the first mismatch
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_only_the_health_check_public() {
+        assert!(is_public_path("/health"));
+        assert!(!is_public_path("/"));
+        assert!(!is_public_path("/ws"));
+        assert!(!is_public_path("/metrics"));
+        assert!(!is_public_path("/api/v1/validator/batch"));
+        assert!(!is_public_path("/health/x"));
+    }
+
+    #[test]
+    fn it_authorizes_api_keys() {
+        assert!(is_authorized(Some("s3cr3t"), &["s3cr3t"]));
+        assert!(is_authorized(Some("k3y"), &["s3cr3t", "k3y"]));
+        assert!(!is_authorized(Some("s3cr3x"), &["s3cr3t"]));
+        assert!(!is_authorized(Some("s3cr3"), &["s3cr3t"]));
+        assert!(!is_authorized(None, &["s3cr3t"]));
+        // No keys configured disables guarded routes
+        assert!(!is_authorized(Some(""), &[""]));
+        assert!(!is_authorized(Some("s3cr3t"), &[]));
+    }
+
+    #[test]
+    fn it_parses_bearer_keys() {
+        assert_eq!(bearer_key("Bearer s3cr3t"), "s3cr3t");
+        assert_eq!(bearer_key("s3cr3t"), "s3cr3t");
+        assert_eq!(bearer_key("Bearer  s3cr3t "), "s3cr3t");
+    }
+}
@@ -0,0 +1,191 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::board::{split_board_formula, split_board_norm, split_board_smooth, Formula, Norm};
+use crate::cache::{get_conn, RedisPool};
+use crate::errors::{ApiError, CacheError};
+use crate::helpers::respond_json;
+use crate::sync::sync;
+use actix_web::web::{Data, Json, Query};
+use redis::aio::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default number of boards returned
+const BOARD_STATS_DEFAULT_LIMIT: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct BoardStatsParams {
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    BOARD_STATS_DEFAULT_LIMIT
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BoardStats {
+    pub board_name: String,
+    pub weights: Vec<u32>,
+    pub intervals: Vec<String>,
+    pub norm: Norm,
+    pub formula: Formula,
+    pub smooth: bool,
+    pub count: u64,
+}
+
+impl BoardStats {
+    fn new(board_name: String, count: u64) -> Self {
+        let (smooth, name) = split_board_smooth(&board_name);
+        let (formula, name) = split_board_formula(name);
+        let (norm, name) = split_board_norm(name);
+        let mut parts = name.splitn(2, '|');
+        let weights = parts
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|x| x.parse::<u32>().ok())
+            .collect();
+        let intervals = match parts.next() {
+            Some(i) => i.split(',').map(|x| x.to_string()).collect(),
+            None => Vec::new(),
+        };
+        BoardStats {
+            board_name,
+            weights,
+            intervals,
+            norm,
+            formula,
+            smooth,
+            count,
+        }
+    }
+}
+
+type BoardStatsResponse = Vec<BoardStats>;
+
+/// Board name from the board key, e.g. `2470:era:5,5,5|0:10:board` -> `5,5,5|0:10`
+fn parse_board_name(key: &str) -> Option<&str> {
+    let start = key.find(":era:")? + ":era:".len();
+    key.get(start..)?.strip_suffix(":board")
+}
+
+/// Sum the requests of every board, for all eras, and sort them by most requested
+fn board_stats(stats: BTreeMap<String, u64>, limit: usize) -> BoardStatsResponse {
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    for (key, count) in stats {
+        if let Some(board_name) = parse_board_name(&key) {
+            *counts.entry(board_name.to_string()).or_default() += count;
+        }
+    }
+    let mut boards: BoardStatsResponse = counts
+        .into_iter()
+        .map(|(board_name, count)| BoardStats::new(board_name, count))
+        .collect();
+    boards.sort_by(|a, b| b.count.cmp(&a.count));
+    boards.truncate(limit);
+    boards
+}
+
+/// Get the most requested boards
+pub async fn get_board_stats(
+    params: Query<BoardStatsParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<BoardStatsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let stats: BTreeMap<String, u64> = redis::cmd("HGETALL")
+        .arg(sync::Key::Stats)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(board_stats(stats, params.limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_board_name() {
+        assert_eq!(
+            parse_board_name("2470:era:5,5,5|0:10,-inf:inf:board"),
+            Some("5,5,5|0:10,-inf:inf")
+        );
+        assert_eq!(parse_board_name("2470:era:5,5,5:board"), Some("5,5,5"));
+        assert_eq!(parse_board_name("stats"), None);
+    }
+
+    #[test]
+    fn it_splits_smoothed_board_stats() {
+        let stats = BoardStats::new("smooth:geomean:ci95:1,1,1".to_string(), 2);
+        assert_eq!(stats.weights, vec![1, 1, 1]);
+        assert_eq!(stats.norm, Norm::Ci95);
+        assert_eq!(stats.formula, Formula::GeoMean);
+        assert!(stats.smooth);
+    }
+
+    #[test]
+    fn it_sorts_board_stats_by_count() {
+        let mut stats: BTreeMap<String, u64> = BTreeMap::new();
+        stats.insert("2470:era:5,5,5:board".to_string(), 3);
+        stats.insert("2471:era:5,5,5:board".to_string(), 4);
+        stats.insert("2471:era:9,0,1|0:10,-inf:inf:board".to_string(), 5);
+        stats.insert("2471:era:1,1,1:board".to_string(), 1);
+        stats.insert("2471:era:ci95:1,1,1:board".to_string(), 6);
+        stats.insert("2471:era:smooth:geomean:1,1,1:board".to_string(), 2);
+
+        let boards = board_stats(stats, 3);
+        assert_eq!(
+            boards,
+            vec![
+                BoardStats {
+                    board_name: "5,5,5".to_string(),
+                    weights: vec![5, 5, 5],
+                    intervals: vec![],
+                    norm: Norm::MinMax,
+                    formula: Formula::Sum,
+                    smooth: false,
+                    count: 7,
+                },
+                BoardStats {
+                    board_name: "ci95:1,1,1".to_string(),
+                    weights: vec![1, 1, 1],
+                    intervals: vec![],
+                    norm: Norm::Ci95,
+                    formula: Formula::Sum,
+                    smooth: false,
+                    count: 6,
+                },
+                BoardStats {
+                    board_name: "9,0,1|0:10,-inf:inf".to_string(),
+                    weights: vec![9, 0, 1],
+                    intervals: vec!["0:10".to_string(), "-inf:inf".to_string()],
+                    norm: Norm::MinMax,
+                    formula: Formula::Sum,
+                    smooth: false,
+                    count: 5,
+                },
+            ]
+        );
+    }
+}
@@ -21,11 +21,14 @@
 
 use crate::cache::{get_conn, RedisPool};
 use crate::errors::{ApiError, CacheError};
-use crate::helpers::respond_json;
-use actix_web::web::{Data, Json, Path};
+use crate::handlers::network::get_token_decimals;
+use crate::handlers::validator::get_etag_era;
+use crate::helpers::{respond_json, respond_json_with_etag, to_tokens};
+use crate::sync::sync::{self, EraIndex};
+use actix_web::web::{Data, HttpRequest, HttpResponse, Json, Path, Query};
 use redis::aio::Connection;
-use serde::Serialize;
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 
 type EraCache = BTreeMap<String, String>;
 
@@ -33,11 +36,23 @@ type EraCache = BTreeMap<String, String>;
 pub struct EraResponse {
     pub era_index: u32,
     pub total_reward: u128,
+    pub total_reward_tokens: f64,
     pub total_stake: u128,
+    pub total_stake_tokens: f64,
     pub total_reward_points: u32,
     pub min_reward_points: u32,
     pub max_reward_points: u32,
     pub avg_reward_points: u32,
+    pub stddev_reward_points: f64,
+    pub ci95_lower_reward_points: f64,
+    pub ci95_upper_reward_points: f64,
+    /// Headcounts at the time the era was active, 0 if not synced while active
+    pub num_validators: u32,
+    pub num_active: u32,
+    pub num_nominators: u32,
+    /// Minimum number of active validators holding more than 1/3 of the active stake,
+    /// 0 if not calculated while the era was active
+    pub nakamoto_coefficient: u32,
 }
 
 impl From<EraCache> for EraResponse {
@@ -54,11 +69,14 @@ impl From<EraCache> for EraResponse {
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            // Note: token-denominated values depend on the chain and are set by the handler
+            total_reward_tokens: 0.0,
             total_stake: data
                 .get("total_stake")
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            total_stake_tokens: 0.0,
             total_reward_points: data
                 .get("total_reward_points")
                 .unwrap_or(&zero)
@@ -79,19 +97,63 @@ impl From<EraCache> for EraResponse {
                 .unwrap_or(&zero)
                 .parse::<u32>()
                 .unwrap_or_default(),
+            stddev_reward_points: data
+                .get("stddev_reward_points")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            ci95_lower_reward_points: data
+                .get("ci95_lower_reward_points")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            ci95_upper_reward_points: data
+                .get("ci95_upper_reward_points")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
+            num_validators: data
+                .get("num_validators")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            num_active: data
+                .get("num_active")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            num_nominators: data
+                .get("num_nominators")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            nakamoto_coefficient: data
+                .get("nakamoto_coefficient")
+                .unwrap_or(&zero)
+                .parse::<u32>()
+                .unwrap_or_default(),
         }
     }
 }
 
+impl EraResponse {
+    /// Set the token-denominated totals, for display only
+    fn with_tokens(mut self, token_decimals: u8) -> Self {
+        self.total_reward_tokens = to_tokens(self.total_reward, token_decimals);
+        self.total_stake_tokens = to_tokens(self.total_stake, token_decimals);
+        self
+    }
+}
+
 /// Get a era
 pub async fn get_era(
+    req: HttpRequest,
     era_index: Path<u32>,
     cache: Data<RedisPool>,
-) -> Result<Json<EraResponse>, ApiError> {
+) -> Result<HttpResponse, ApiError> {
     let mut conn = get_conn(&cache).await?;
-    let key = format!("{}:era", era_index);
     let mut data: EraCache = redis::cmd("HGETALL")
-        .arg(key)
+        .arg(sync::Key::Era(*era_index))
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
@@ -101,5 +163,407 @@ pub async fn get_era(
     if data.len() == 0 {
         return Err(ApiError::NotFound(not_found));
     }
-    respond_json(data.into())
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    respond_json_with_etag(
+        &req,
+        EraResponse::from(data).with_tokens(token_decimals),
+        get_etag_era(cache.clone()).await?,
+    )
+}
+
+/// Default number of eras returned, ending at the active era
+const ERAS_DEFAULT_RANGE: u32 = 30;
+
+/// Maximum number of eras returned in one request
+const ERAS_MAX_RANGE: u32 = 336;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ErasRangeParams {
+    #[serde(default)]
+    from: Option<EraIndex>,
+    #[serde(default)]
+    to: Option<EraIndex>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ErasResponse {
+    pub eras: Vec<EraResponse>,
+    /// Eras in the range requested that are not synced yet
+    pub missing: Vec<EraIndex>,
+}
+
+/// Eras requested, by default the last ERAS_DEFAULT_RANGE eras ending at the active era.
+/// Ranges are capped at ERAS_MAX_RANGE eras counting from the first era requested
+fn eras_range(
+    params: &ErasRangeParams,
+    active_era_index: EraIndex,
+) -> Result<(EraIndex, EraIndex), ApiError> {
+    let to = params.to.unwrap_or(active_era_index);
+    let from = params
+        .from
+        .unwrap_or(to.saturating_sub(ERAS_DEFAULT_RANGE - 1));
+    if from > to {
+        return Err(ApiError::BadRequest(format!(
+            "Parameter from={} must be lower or equal than to={}",
+            from, to
+        )));
+    }
+    Ok((from, to.min(from.saturating_add(ERAS_MAX_RANGE - 1))))
+}
+
+/// Get a range of eras sorted by era index
+pub async fn get_eras(
+    params: Query<ErasRangeParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ErasResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let active_era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let (from, to) = eras_range(&params, active_era_index)?;
+
+    let mut pipe = redis::pipe();
+    for era_index in from..=to {
+        pipe.cmd("HGETALL").arg(sync::Key::Era(era_index));
+    }
+    let data: Vec<EraCache> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    let mut eras: Vec<EraResponse> = Vec::with_capacity(data.len());
+    let mut missing: Vec<EraIndex> = Vec::new();
+    for (era_index, mut data) in (from..=to).zip(data.into_iter()) {
+        if data.len() == 0 {
+            missing.push(era_index);
+            continue;
+        }
+        data.insert("era_index".to_string(), era_index.to_string());
+        eras.push(EraResponse::from(data).with_tokens(token_decimals));
+    }
+
+    respond_json(ErasResponse { eras, missing })
+}
+
+/// Default number of validators returned in the era points distribution
+const ERA_POINTS_DEFAULT_LIMIT: u32 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct EraPointsParams {
+    #[serde(default)]
+    offset: u32,
+    #[serde(default = "default_points_limit")]
+    n: u32,
+}
+
+fn default_points_limit() -> u32 {
+    ERA_POINTS_DEFAULT_LIMIT
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorPoints {
+    pub stash: String,
+    pub points: u32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EraPointsResponse {
+    pub era_index: EraIndex,
+    pub total: u32,
+    pub validators: Vec<ValidatorPoints>,
+}
+
+/// Get the reward points of every validator in a era, sorted by most points
+pub async fn get_era_points_distribution(
+    era_index: Path<EraIndex>,
+    params: Query<EraPointsParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<EraPointsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let key = sync::Key::BoardAtEra(*era_index, sync::BOARD_POINTS_VALIDATORS.to_string());
+
+    let total: u32 = redis::cmd("ZCARD")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    if total == 0 {
+        return Err(ApiError::NotFound(format!(
+            "era index {} points not available",
+            era_index
+        )));
+    }
+
+    let points: Vec<(String, u32)> = redis::cmd("ZRANGE")
+        .arg(key)
+        .arg("+inf")
+        .arg("-inf")
+        .arg("BYSCORE")
+        .arg("REV")
+        .arg("LIMIT")
+        .arg(params.offset)
+        .arg(params.n)
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(EraPointsResponse {
+        era_index: *era_index,
+        total,
+        validators: points
+            .into_iter()
+            .map(|(stash, points)| ValidatorPoints { stash, points })
+            .collect(),
+    })
+}
+
+/// Maximum number of reward points returned in one request
+const ERA_POINTS_RAW_MAX_LIMIT: u32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct EraPointsRawParams {
+    #[serde(default)]
+    offset: u32,
+    #[serde(default = "default_points_raw_limit")]
+    n: u32,
+}
+
+fn default_points_raw_limit() -> u32 {
+    ERA_POINTS_RAW_MAX_LIMIT
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EraPointsRawResponse {
+    pub era_index: EraIndex,
+    pub total: u32,
+    pub points: Vec<u32>,
+}
+
+/// Get the reward points of every validator in a era, without the stashes and sorted
+/// from the lowest, so that clients can calculate their own statistics
+pub async fn get_era_points_raw(
+    era_index: Path<EraIndex>,
+    params: Query<EraPointsRawParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<EraPointsRawResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let key = sync::Key::BoardAtEra(*era_index, sync::BOARD_POINTS_VALIDATORS.to_string());
+
+    let total: u32 = redis::cmd("ZCARD")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    if total == 0 {
+        return Err(ApiError::NotFound(format!(
+            "era index {} points not available",
+            era_index
+        )));
+    }
+
+    let n = params.n.min(ERA_POINTS_RAW_MAX_LIMIT);
+    let points: Vec<(String, u32)> = redis::cmd("ZRANGE")
+        .arg(key)
+        .arg("-inf")
+        .arg("+inf")
+        .arg("BYSCORE")
+        .arg("LIMIT")
+        .arg(params.offset)
+        .arg(n)
+        .arg("WITHSCORES")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(EraPointsRawResponse {
+        era_index: *era_index,
+        total,
+        points: points.into_iter().map(|(_, points)| points).collect(),
+    })
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ActiveSetDiffParams {
+    #[serde(default)]
+    from: Option<EraIndex>,
+    #[serde(default)]
+    to: Option<EraIndex>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ActiveSetDiffResponse {
+    pub from: EraIndex,
+    pub to: EraIndex,
+    pub entered: Vec<String>,
+    pub left: Vec<String>,
+}
+
+/// Eras compared, by default the last era transition ending at the active era
+fn active_set_diff_range(
+    params: &ActiveSetDiffParams,
+    active_era_index: EraIndex,
+) -> Result<(EraIndex, EraIndex), ApiError> {
+    let to = params.to.unwrap_or(active_era_index);
+    let from = params.from.unwrap_or(to.saturating_sub(1));
+    if from >= to {
+        return Err(ApiError::BadRequest(format!(
+            "Parameter from={} must be lower than to={}",
+            from, to
+        )));
+    }
+    Ok((from, to))
+}
+
+/// Stashes that entered and left the active set between two eras, sorted by stash
+fn active_set_diff(from: Vec<String>, to: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let from: BTreeSet<String> = from.into_iter().collect();
+    let to: BTreeSet<String> = to.into_iter().collect();
+    (
+        to.difference(&from).cloned().collect(),
+        from.difference(&to).cloned().collect(),
+    )
+}
+
+/// Get the validators that entered and left the active set between two eras
+pub async fn get_active_set_diff(
+    params: Query<ActiveSetDiffParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ActiveSetDiffResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let active_era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let (from, to) = active_set_diff_range(&params, active_era_index)?;
+
+    let mut pipe = redis::pipe();
+    for era_index in [from, to].iter() {
+        pipe.cmd("ZRANGE")
+            .arg(sync::Key::BoardAtEra(
+                *era_index,
+                sync::BOARD_ACTIVE_VALIDATORS.to_string(),
+            ))
+            .arg(0)
+            .arg(-1);
+    }
+    let (from_set, to_set): (Vec<String>, Vec<String>) = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    for (era_index, set) in [(from, &from_set), (to, &to_set)].iter() {
+        if set.is_empty() {
+            return Err(ApiError::NotFound(format!(
+                "era index {} active set not available",
+                era_index
+            )));
+        }
+    }
+
+    let (entered, left) = active_set_diff(from_set, to_set);
+    respond_json(ActiveSetDiffResponse {
+        from,
+        to,
+        entered,
+        left,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_defaults_eras_range_to_the_last_eras() {
+        let params = ErasRangeParams::default();
+        assert_eq!(eras_range(&params, 100).unwrap(), (71, 100));
+        assert_eq!(eras_range(&params, 10).unwrap(), (0, 10));
+
+        let params = ErasRangeParams {
+            from: Some(90),
+            to: None,
+        };
+        assert_eq!(eras_range(&params, 100).unwrap(), (90, 100));
+
+        let params = ErasRangeParams {
+            from: None,
+            to: Some(50),
+        };
+        assert_eq!(eras_range(&params, 100).unwrap(), (21, 50));
+    }
+
+    #[test]
+    fn it_defaults_missing_headcounts_to_zero() {
+        let mut data: EraCache = EraCache::new();
+        data.insert("era_index".to_string(), "2400".to_string());
+        data.insert("num_validators".to_string(), "1020".to_string());
+        let era = EraResponse::from(data);
+        assert_eq!(era.num_validators, 1020);
+        assert_eq!(era.num_active, 0);
+        assert_eq!(era.num_nominators, 0);
+        assert_eq!(era.nakamoto_coefficient, 0);
+    }
+
+    #[test]
+    fn it_caps_eras_range() {
+        let params = ErasRangeParams {
+            from: Some(0),
+            to: Some(1000),
+        };
+        assert_eq!(eras_range(&params, 1000).unwrap(), (0, ERAS_MAX_RANGE - 1));
+
+        let params = ErasRangeParams {
+            from: Some(20),
+            to: Some(10),
+        };
+        assert!(eras_range(&params, 100).is_err());
+    }
+
+    #[test]
+    fn it_defaults_active_set_diff_to_the_last_era_transition() {
+        let params = ActiveSetDiffParams::default();
+        assert_eq!(active_set_diff_range(&params, 100).unwrap(), (99, 100));
+
+        let params = ActiveSetDiffParams {
+            from: Some(90),
+            to: None,
+        };
+        assert_eq!(active_set_diff_range(&params, 100).unwrap(), (90, 100));
+
+        let params = ActiveSetDiffParams {
+            from: None,
+            to: Some(50),
+        };
+        assert_eq!(active_set_diff_range(&params, 100).unwrap(), (49, 50));
+
+        let params = ActiveSetDiffParams {
+            from: Some(50),
+            to: Some(50),
+        };
+        assert!(active_set_diff_range(&params, 100).is_err());
+        assert!(active_set_diff_range(&ActiveSetDiffParams::default(), 0).is_err());
+    }
+
+    #[test]
+    fn it_diffs_active_sets() {
+        let from = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let to = vec!["d".to_string(), "c".to_string(), "a".to_string()];
+        assert_eq!(
+            active_set_diff(from, to),
+            (vec!["d".to_string()], vec!["b".to_string()])
+        );
+        assert_eq!(
+            active_set_diff(vec!["a".to_string()], vec!["a".to_string()]),
+            (vec![], vec![])
+        );
+    }
 }
@@ -22,6 +22,7 @@
 use crate::cache::{get_conn, RedisPool};
 use crate::errors::{ApiError, CacheError};
 use crate::helpers::respond_json;
+use crate::sync::sync;
 use actix_web::web::{Data, Json};
 use redis::aio::Connection;
 use serde::{Deserialize, Serialize};
@@ -29,6 +30,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub struct HealthResponse {
     pub status: String,
+    // Liveness is not affected, cached data is still served while the node is unreachable
+    pub node_connected: bool,
 }
 
 /// Handler to get the liveness of the service
@@ -46,5 +49,6 @@ pub async fn get_health(cache: Data<RedisPool>) -> Result<Json<HealthResponse>,
 
     respond_json(HealthResponse {
         status: "ok".into(),
+        node_connected: sync::is_node_connected(),
     })
 }
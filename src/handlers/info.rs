@@ -23,6 +23,7 @@ use crate::cache::{get_conn, RedisPool};
 use crate::config::CONFIG;
 use crate::errors::{ApiError, CacheError};
 use crate::helpers::respond_json;
+use crate::routes::API_PATH;
 use crate::sync::sync;
 use actix_web::web::{Data, Json};
 use redis::aio::Connection;
@@ -47,6 +48,7 @@ pub struct ChainDetailsResponse {
     pub ss58_format: u8,
     pub substrate_node_url: String,
     pub genesis_hash: String,
+    pub eras_per_day: f64,
 }
 
 impl From<BTreeMap<String, String>> for ChainDetailsResponse {
@@ -76,6 +78,11 @@ impl From<BTreeMap<String, String>> for ChainDetailsResponse {
                 .get("genesis_hash")
                 .unwrap_or(&"".to_string())
                 .to_string(),
+            eras_per_day: data
+                .get("eras_per_day")
+                .unwrap_or(&zero)
+                .parse::<f64>()
+                .unwrap_or_default(),
         }
     }
 }
@@ -87,17 +94,25 @@ pub struct CacheInfoResponse {
     pub syncing_finished_at: u32,
     pub validators: u32,
     pub nominators: u32,
+    /// Progress (0-1) of the sync in progress, 1 if not syncing
+    pub progress: f32,
 }
 
 impl From<BTreeMap<String, String>> for CacheInfoResponse {
     fn from(data: BTreeMap<String, String>) -> Self {
         let zero = "0".to_string();
+        let parse = |field: &str| -> u32 {
+            data.get(field)
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or_default()
+        };
+        let syncing = data
+            .get("syncing")
+            .unwrap_or(&zero)
+            .parse::<bool>()
+            .unwrap_or_default();
         CacheInfoResponse {
-            syncing: data
-                .get("syncing")
-                .unwrap_or(&zero)
-                .parse::<bool>()
-                .unwrap_or_default(),
+            syncing,
             syncing_started_at: data
                 .get("syncing_started_at")
                 .unwrap_or(&zero)
@@ -118,10 +133,42 @@ impl From<BTreeMap<String, String>> for CacheInfoResponse {
                 .unwrap_or(&zero)
                 .parse::<u32>()
                 .unwrap_or_default(),
+            progress: sync::sync_progress(
+                syncing,
+                (parse("sync_eras_done"), parse("sync_eras_total")),
+                (
+                    parse("sync_validators_done"),
+                    parse("sync_validators_total"),
+                ),
+            ),
         }
     }
 }
 
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SyncErrorsResponse {
+    pub errors: Vec<sync::SyncErrorRecord>,
+}
+
+/// Handler to get the most recent sync errors, most recent first
+pub async fn get_sync_errors(cache: Data<RedisPool>) -> Result<Json<SyncErrorsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let records: Vec<String> = redis::cmd("LRANGE")
+        .arg(sync::Key::SyncErrors)
+        .arg(0)
+        .arg(sync::SYNC_ERRORS_CAPACITY - 1)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let errors = records
+        .iter()
+        .filter_map(|record| serde_json::from_str(record).ok())
+        .collect();
+
+    respond_json(SyncErrorsResponse { errors })
+}
+
 /// Handler to get information about the service
 pub async fn get_info(cache: Data<RedisPool>) -> Result<Json<InfoResponse>, ApiError> {
     let config = CONFIG.clone();
@@ -141,7 +188,7 @@ pub async fn get_info(cache: Data<RedisPool>) -> Result<Json<InfoResponse>, ApiE
     respond_json(InfoResponse {
         pkg_name: env!("CARGO_PKG_NAME").into(),
         pkg_version: env!("CARGO_PKG_VERSION").into(),
-        api_path: "/api/v1".into(),
+        api_path: API_PATH.into(),
         chain: chain_info.into(),
         cache: cache_info.into(),
         featured: config.turboflakes_featured_stashes,
@@ -0,0 +1,169 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::cache::{get_conn, RedisPool};
+use crate::errors::{ApiError, CacheError};
+use crate::sync::sync;
+use actix_web::{web::Data, HttpResponse};
+use redis::aio::Connection;
+use std::collections::BTreeMap;
+
+/// Lightweight registry to render metrics in the Prometheus text exposition format
+struct Registry {
+    buffer: String,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Registry {
+            buffer: String::new(),
+        }
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.metric(name, help, "gauge", value);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: f64) {
+        self.metric(name, help, "counter", value);
+    }
+
+    fn metric(&mut self, name: &str, help: &str, kind: &str, value: f64) {
+        self.buffer.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+            name, help, name, kind, name, value
+        ));
+    }
+
+    fn render(self) -> String {
+        self.buffer
+    }
+}
+
+/// Handler to expose sync and API metrics in the Prometheus text format.
+/// Values are read from the cache on every scrape
+pub async fn get_metrics(cache: Data<RedisPool>) -> Result<HttpResponse, ApiError> {
+    let pool_state = cache.state().await;
+    let mut conn = get_conn(&cache).await?;
+
+    let info: BTreeMap<String, String> = redis::cmd("HGETALL")
+        .arg(sync::Key::Info)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let board_requests: Vec<u64> = redis::cmd("HVALS")
+        .arg(sync::Key::Stats)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let field = |name: &str| -> f64 {
+        info.get(name)
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or_default()
+    };
+    let syncing = info
+        .get("syncing")
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or_default();
+    // Only a finished sync has a meaningful duration
+    let sync_duration = if syncing {
+        0.0
+    } else {
+        (field("syncing_finished_at") - field("syncing_started_at")).max(0.0)
+    };
+
+    let mut registry = Registry::new();
+    registry.gauge(
+        "turboflakes_last_era_synced",
+        "Last era successfully synced",
+        field("last_era_synced"),
+    );
+    registry.gauge(
+        "turboflakes_syncing",
+        "Whether the cache is currently syncing",
+        (syncing as u32) as f64,
+    );
+    registry.gauge(
+        "turboflakes_sync_duration_seconds",
+        "Duration of the last finished sync in seconds",
+        sync_duration,
+    );
+    registry.gauge(
+        "turboflakes_validators",
+        "Number of validators synced",
+        field("validators"),
+    );
+    registry.gauge(
+        "turboflakes_nominators",
+        "Number of nominators synced",
+        field("nominators"),
+    );
+    registry.counter(
+        "turboflakes_board_requests_total",
+        "Number of leaderboards served",
+        board_requests.iter().sum::<u64>() as f64,
+    );
+    registry.gauge(
+        "turboflakes_cache_pool_connections_in_use",
+        "Number of Redis pool connections currently in use",
+        pool_state.in_use as f64,
+    );
+    registry.gauge(
+        "turboflakes_cache_pool_connections",
+        "Number of Redis pool connections open",
+        pool_state.connections as f64,
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(registry.render()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_metrics_in_text_format() {
+        let mut registry = Registry::new();
+        registry.gauge(
+            "turboflakes_validators",
+            "Number of validators synced",
+            297.0,
+        );
+        registry.counter(
+            "turboflakes_board_requests_total",
+            "Number of leaderboards served",
+            3.0,
+        );
+        assert_eq!(
+            registry.render(),
+            "# HELP turboflakes_validators Number of validators synced\n\
+             # TYPE turboflakes_validators gauge\n\
+             turboflakes_validators 297\n\
+             # HELP turboflakes_board_requests_total Number of leaderboards served\n\
+             # TYPE turboflakes_board_requests_total counter\n\
+             turboflakes_board_requests_total 3\n"
+        );
+    }
+}
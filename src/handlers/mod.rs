@@ -19,7 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod admin;
+pub mod board;
 pub mod era;
 pub mod health;
 pub mod info;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod network;
+pub mod nominator;
+pub mod preset;
 pub mod validator;
+pub mod ws;
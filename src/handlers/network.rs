@@ -0,0 +1,262 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::board::is_syncing;
+use crate::cache::{get_conn, RedisPool};
+use crate::config::CONFIG;
+use crate::errors::{ApiError, CacheError};
+use crate::handlers::info::ChainDetailsResponse;
+use crate::helpers::{respond_json, to_tokens};
+use crate::sync::{
+    stats,
+    sync::{self, EraIndex},
+};
+use actix_web::web::{Data, Json};
+use redis::aio::Connection;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+use subxt::sp_runtime::AccountId32;
+
+/// Get the chain token decimals used to convert planck values into tokens
+pub async fn get_token_decimals(cache: Data<RedisPool>) -> Result<u8, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let token_decimals: Option<u8> = redis::cmd("HGET")
+        .arg(sync::Key::Network)
+        .arg("token_decimals")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(token_decimals.unwrap_or_default())
+}
+
+/// Get the chain ss58 format, none if the network details are not synced yet
+pub async fn get_ss58_format(cache: Data<RedisPool>) -> Result<Option<u16>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let ss58_format: Option<u16> = redis::cmd("HGET")
+        .arg(sync::Key::Network)
+        .arg("ss58_format")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(ss58_format)
+}
+
+/// Handler to get the chain details of the network the service is connected to
+pub async fn get_network(cache: Data<RedisPool>) -> Result<Json<ChainDetailsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let data: BTreeMap<String, String> = redis::cmd("HGETALL")
+        .arg(sync::Key::Network)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    if data.len() == 0 {
+        return Err(ApiError::NotFound(
+            "network details not available".to_string(),
+        ));
+    }
+
+    respond_json(data.into())
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct NetworkStatsResponse {
+    pub era: EraIndex,
+    pub total_stake: u128,
+    pub total_stake_tokens: f64,
+    pub active_validators: u32,
+    /// Average commission of the active set, in parts per billion like the validator commission
+    pub avg_commission: u32,
+    /// Gini coefficient of the total stake of the active set
+    pub stake_gini: f64,
+    /// Minimum number of active validators holding more than 1/3 of the active stake
+    pub nakamoto_coefficient: u32,
+    /// Rewards are only known once an era ends, so the total reward is the one of the
+    /// era before the active era
+    pub total_reward_era: EraIndex,
+    pub total_reward: u128,
+    pub total_reward_tokens: f64,
+}
+
+/// Average of the commissions in parts per billion, zero if there are none
+fn avg_commission(commissions: &Vec<u32>) -> u32 {
+    stats::mean_f64(&stats::to_f64(commissions)).round() as u32
+}
+
+/// Calculate the network stats of the active era from the era hash and the boards
+async fn calculate_network_stats(
+    era_index: EraIndex,
+    cache: Data<RedisPool>,
+) -> Result<NetworkStatsResponse, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let members: Vec<String> = redis::cmd("ZRANGE")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            sync::BOARD_ACTIVE_VALIDATORS.to_string(),
+        ))
+        .arg(0)
+        .arg(-1)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let stashes: Vec<AccountId32> = members
+        .iter()
+        .filter_map(|member| AccountId32::from_str(member).ok())
+        .collect();
+
+    let mut commissions: Vec<u32> = Vec::new();
+    let mut stakes: Vec<u128> = Vec::new();
+    if stashes.len() > 0 {
+        let mut pipe = redis::pipe();
+        for stash in stashes.iter() {
+            pipe.cmd("HGET")
+                .arg(sync::Key::ValidatorAtEra(era_index, stash.clone()))
+                .arg("commission");
+        }
+        let values: Vec<Option<u32>> = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        commissions = values.into_iter().flatten().collect();
+
+        // Note: the exact planck values of the stake board are used, since sorted set
+        // scores are doubles
+        let values: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(sync::Key::BoardAtEra(
+                0,
+                sync::planck_board_name(sync::BOARD_TOTAL_STAKE_VALIDATORS),
+            ))
+            .arg(
+                stashes
+                    .iter()
+                    .map(|stash| stash.to_string())
+                    .collect::<Vec<String>>(),
+            )
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        stakes = values
+            .into_iter()
+            .filter_map(|x| x?.parse::<u128>().ok())
+            .collect();
+    }
+
+    let total_reward_era = era_index.saturating_sub(1);
+    let (total_stake, total_reward): (Option<String>, Option<String>) = redis::pipe()
+        .cmd("HGET")
+        .arg(sync::Key::Era(era_index))
+        .arg("total_stake")
+        .cmd("HGET")
+        .arg(sync::Key::Era(total_reward_era))
+        .arg("total_reward")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let total_stake = total_stake
+        .and_then(|x| x.parse::<u128>().ok())
+        .unwrap_or_default();
+    let total_reward = total_reward
+        .and_then(|x| x.parse::<u128>().ok())
+        .unwrap_or_default();
+
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    Ok(NetworkStatsResponse {
+        era: era_index,
+        total_stake,
+        total_stake_tokens: to_tokens(total_stake, token_decimals),
+        active_validators: stashes.len() as u32,
+        avg_commission: avg_commission(&commissions),
+        stake_gini: stats::gini_u128(&stakes),
+        nakamoto_coefficient: stats::nakamoto_coefficient(&stakes, sync::NAKAMOTO_STAKE_SHARE),
+        total_reward_era,
+        total_reward,
+        total_reward_tokens: to_tokens(total_reward, token_decimals),
+    })
+}
+
+/// Handler to get the staking system stats of the active era.
+/// Note: stats are cached per era and only calculated again once expired,
+/// except while syncing since the boards are still being updated
+pub async fn get_network_stats(
+    cache: Data<RedisPool>,
+) -> Result<Json<NetworkStatsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: Option<EraIndex> = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let era_index = match era_index {
+        Some(era_index) => era_index,
+        None => {
+            return Err(ApiError::NotFound(
+                "network stats not available".to_string(),
+            ))
+        }
+    };
+
+    let key = sync::Key::NetworkStatsAtEra(era_index);
+    let cached: Option<String> = redis::cmd("GET")
+        .arg(key.clone())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    if let Some(serialized) = cached {
+        if let Ok(stats) = serde_json::from_str::<NetworkStatsResponse>(&serialized) {
+            return respond_json(stats);
+        }
+    }
+
+    let stats = calculate_network_stats(era_index, cache.clone()).await?;
+    if !is_syncing(cache.clone()).await? {
+        let serialized = serde_json::to_string(&stats).map_err(|e| {
+            ApiError::InternalServerError(format!("network stats not serialized: {}", e))
+        })?;
+        let _: () = redis::cmd("SET")
+            .arg(key)
+            .arg(serialized)
+            .arg("EX")
+            .arg(CONFIG.board_ttl_seconds)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+    }
+
+    respond_json(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_averages_commissions() {
+        assert_eq!(avg_commission(&vec![]), 0);
+        assert_eq!(
+            avg_commission(&vec![1_000_000_000, 50_000_000]),
+            525_000_000
+        );
+        // Perbill commissions of a large active set do not overflow
+        assert_eq!(avg_commission(&vec![1_000_000_000; 400]), 1_000_000_000);
+    }
+}
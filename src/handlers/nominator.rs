@@ -0,0 +1,190 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::cache::{get_conn, RedisPool};
+use crate::errors::{ApiError, CacheError};
+use crate::handlers::network::get_token_decimals;
+use crate::handlers::validator::{get_default_board_scores, parse_stash};
+use crate::helpers::{respond_json, to_tokens};
+use crate::sync::sync::{self, EraIndex};
+use actix_web::web::{Data, Json, Path};
+use log::warn;
+use redis::aio::Connection;
+use serde::Serialize;
+use std::{collections::BTreeMap, str::FromStr};
+use subxt::sp_runtime::AccountId32;
+
+type NominatorCache = BTreeMap<String, String>;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NominatorTarget {
+    pub stash: String,
+    pub name: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NominatorResponse {
+    pub stash: String,
+    pub bonded: u128,
+    pub bonded_tokens: f64,
+    pub era_index: EraIndex,
+    pub targets: Vec<NominatorTarget>,
+    pub score: f64,
+}
+
+/// Aggregate score of a set of targets, as the average of the target scores
+fn aggregate_score(scores: &Vec<f64>) -> f64 {
+    if scores.len() == 0 {
+        return 0.0;
+    }
+    scores.iter().sum::<f64>() / scores.len() as f64
+}
+
+/// Check if the nominator was not refreshed by the last nominators sync, and so is no
+/// longer nominating. Nominators synced before sync runs were tagged are stale too
+fn is_nominator_stale(sync_run: Option<i64>, last_sync_run: Option<i64>) -> bool {
+    match last_sync_run {
+        Some(last_sync_run) => sync_run.unwrap_or_default() < last_sync_run,
+        None => false,
+    }
+}
+
+/// Get the nominator targets with the score of the set in the default board
+pub async fn get_nominator(
+    stash: Path<String>,
+    cache: Data<RedisPool>,
+) -> Result<Json<NominatorResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
+    let msg = format!("Nominator account with address {} not found", stash);
+
+    let data: NominatorCache = redis::cmd("HGETALL")
+        .arg(sync::Key::Nominator(stash.clone()))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    if data.len() == 0 {
+        warn!("{}", msg);
+        return Err(ApiError::NotFound(msg));
+    }
+
+    let era_index = data
+        .get("era_index")
+        .unwrap_or(&"0".to_string())
+        .parse::<EraIndex>()
+        .unwrap_or_default();
+
+    // Nominators not refreshed by the last nominators sync are no longer nominating
+    let last_sync_run: Option<i64> = redis::cmd("HGET")
+        .arg(sync::Key::Info)
+        .arg("nominators_sync_run")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let sync_run = data.get("sync_run").and_then(|x| x.parse::<i64>().ok());
+    if is_nominator_stale(sync_run, last_sync_run) {
+        warn!("{}", msg);
+        return Err(ApiError::NotFound(msg));
+    }
+
+    let bonded = data
+        .get("bonded")
+        .unwrap_or(&"0".to_string())
+        .parse::<u128>()
+        .unwrap_or_default();
+
+    let mut stashes: Vec<AccountId32> = Vec::new();
+    for target in data.get("targets").unwrap_or(&String::new()).split(",") {
+        if target.is_empty() {
+            continue;
+        }
+        match AccountId32::from_str(target) {
+            Ok(target) => stashes.push(target),
+            Err(e) => warn!("Skipping target with stash {} -> {}", target, e),
+        }
+    }
+
+    // Resolve the targets names
+    let mut pipe = redis::pipe();
+    for target in stashes.iter() {
+        pipe.cmd("HGET")
+            .arg(sync::Key::Validator(target.clone()))
+            .arg("name");
+    }
+    let names: Vec<Option<String>> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let active_era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let scores = get_default_board_scores(active_era_index, &stashes, cache.clone()).await?;
+
+    let targets: Vec<NominatorTarget> = stashes
+        .iter()
+        .zip(names.into_iter())
+        .zip(scores.into_iter())
+        .map(|((stash, name), score)| NominatorTarget {
+            stash: stash.to_string(),
+            name: name.unwrap_or_default(),
+            score: score.unwrap_or_default(),
+        })
+        .collect();
+
+    let score = aggregate_score(&targets.iter().map(|x| x.score).collect());
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+
+    respond_json(NominatorResponse {
+        stash: stash.to_string(),
+        bonded,
+        bonded_tokens: to_tokens(bonded, token_decimals),
+        era_index,
+        targets,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_aggregates_score() {
+        assert_eq!(aggregate_score(&vec![]), 0.0);
+        assert_eq!(aggregate_score(&vec![10.0, 20.0, 30.0]), 20.0);
+    }
+
+    #[test]
+    fn it_tells_stale_nominators_apart() {
+        // Nominators already refreshed while a sync in the same era is still running
+        assert!(!is_nominator_stale(Some(2000), Some(1000)));
+        assert!(!is_nominator_stale(Some(1000), Some(1000)));
+        // Nominators no longer nominating are not refreshed by the last sync
+        assert!(is_nominator_stale(Some(1000), Some(2000)));
+        assert!(is_nominator_stale(None, Some(2000)));
+        assert!(!is_nominator_stale(None, None));
+    }
+}
@@ -0,0 +1,170 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::board::parse_preset;
+use crate::cache::{get_conn, RedisPool};
+use crate::errors::{ApiError, CacheError};
+use crate::handlers::admin::Authorized;
+use crate::helpers::respond_json;
+use crate::sync::sync;
+use actix_web::web::{Data, Json};
+use log::warn;
+use redis::aio::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Maximum number of presets stored
+const PRESETS_CAPACITY: usize = 32;
+
+/// Maximum length of a preset name
+const PRESET_NAME_MAX_LENGTH: usize = 32;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Preset {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub weights: String,
+    #[serde(default)]
+    pub intervals: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PresetsResponse {
+    pub presets: Vec<Preset>,
+}
+
+/// Preset id derived from its name, e.g. "Max Yield" is stored as "max-yield"
+fn preset_id(name: &str) -> String {
+    name.trim()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+/// Store a named preset of weights and intervals, so that boards can be queried
+/// with `preset=<id>`. Storing a preset with the name of an existing one replaces it
+pub async fn post_preset(
+    _auth: Authorized,
+    body: Json<Preset>,
+    cache: Data<RedisPool>,
+) -> Result<Json<Preset>, ApiError> {
+    let id = preset_id(&body.name);
+    if id.is_empty() || body.name.len() > PRESET_NAME_MAX_LENGTH {
+        let msg = format!(
+            "Preset name must have alphanumeric characters and be at most {} characters long",
+            PRESET_NAME_MAX_LENGTH
+        );
+        warn!("{}", msg);
+        return Err(ApiError::BadRequest(msg));
+    }
+    // Verify that weights and intervals are parsed as board params would be
+    parse_preset(&body.weights, &body.intervals)?;
+
+    let mut conn = get_conn(&cache).await?;
+    let (exists, total): (bool, usize) = redis::pipe()
+        .cmd("HEXISTS")
+        .arg(sync::Key::Presets)
+        .arg(id.clone())
+        .cmd("HLEN")
+        .arg(sync::Key::Presets)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    if !exists && total >= PRESETS_CAPACITY {
+        let msg = format!(
+            "The maximum number of presets ({}) has been reached.",
+            PRESETS_CAPACITY
+        );
+        warn!("{}", msg);
+        return Err(ApiError::Conflict(msg));
+    }
+
+    let preset = Preset {
+        id: id.clone(),
+        name: body.name.trim().to_string(),
+        weights: body.weights.clone(),
+        intervals: body.intervals.clone(),
+    };
+    let serialized =
+        serde_json::to_string(&preset).map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let _: () = redis::cmd("HSET")
+        .arg(sync::Key::Presets)
+        .arg(id)
+        .arg(serialized)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(preset)
+}
+
+/// Get all stored presets, ordered by id
+pub async fn get_presets(cache: Data<RedisPool>) -> Result<Json<PresetsResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let data: BTreeMap<String, String> = redis::cmd("HGETALL")
+        .arg(sync::Key::Presets)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(PresetsResponse {
+        presets: data
+            .values()
+            .filter_map(|x| serde_json::from_str(x).ok())
+            .collect(),
+    })
+}
+
+/// Get a stored preset by id
+pub async fn get_preset(id: &str, cache: Data<RedisPool>) -> Result<Preset, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let data: Option<String> = redis::cmd("HGET")
+        .arg(sync::Key::Presets)
+        .arg(id)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    match data.and_then(|x| serde_json::from_str(&x).ok()) {
+        Some(preset) => Ok(preset),
+        None => {
+            let msg = format!("Preset {} not found", id);
+            warn!("{}", msg);
+            Err(ApiError::NotFound(msg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_derives_preset_id_from_name() {
+        assert_eq!(preset_id("balanced"), "balanced");
+        assert_eq!(preset_id(" Max Yield "), "max-yield");
+        assert_eq!(preset_id("decentralization (v2)"), "decentralization-v2");
+        assert_eq!(preset_id("?!"), "");
+    }
+}
@@ -19,117 +19,125 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::board::{
+    board_limits_key, cache_board_limits, default_board_weights, default_intervals,
+    default_weights, expire_board, formula_board_name, generate_board_filtered_by_intervals,
+    generate_board_scores, get_board_limits, get_board_members_count, get_board_name,
+    get_era_limits, get_max_nominator_rewarded, get_validators_stashes, historical_board_name,
+    is_board_complete, is_default_board, is_syncing, limits_board_name, min_score_bound,
+    normalized_score_of, parse_intervals, parse_preset, parse_weights, smoothed_board_name,
+    BoardLimits, Formula, Intervals, Norm, Quantity, Validator, ValidatorCache, ValidatorEraCache,
+    Weight, Weights, COMMISSION_PLANCK, DEFAULT_WEIGHTS, INTERVALS_CAPACITY, MAX_WEIGHT,
+    PERCENT_INTERVALS, WEIGHTS_CAPACITY,
+};
 use crate::cache::{get_conn, RedisPool};
+use crate::config::CONFIG;
 use crate::errors::{ApiError, CacheError};
-use crate::helpers::respond_json;
-use crate::sync::{stats, sync, sync::EraIndex};
-use actix_web::web::{Data, Json, Path, Query};
+use crate::handlers::admin::Authorized;
+use crate::handlers::network::{get_ss58_format, get_token_decimals};
+use crate::handlers::preset::get_preset;
+use crate::helpers::{accepts_csv, csv_field, respond_json, respond_json_with_etag, to_tokens};
+use crate::routes::API_PATH;
+use crate::sync::{
+    sync,
+    sync::{EraIndex, SessionIndex},
+};
+use actix_web::web::{Data, HttpRequest, HttpResponse, Json, Path, Query};
 use log::{error, warn};
 use redis::aio::Connection;
-use serde::{de::Deserializer, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use std::{collections::BTreeMap, str::FromStr};
-use subxt::sp_runtime::AccountId32;
+use subxt::{sp_core::crypto::Ss58Codec, sp_runtime::AccountId32};
+
+/// Decode a stash address, only addresses of the chain ss58 format are accepted so that
+/// an address of another network is not looked up in vain. Any format is accepted while
+/// the chain ss58 format is not synced yet
+fn decode_stash(stash: &str, ss58_format: Option<u16>) -> Result<AccountId32, ApiError> {
+    let (account, format) = AccountId32::from_ss58check_with_version(stash)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid stash address {}", stash)))?;
+    match ss58_format {
+        Some(expected) if u16::from(format) != expected => Err(ApiError::BadRequest(format!(
+            "Stash address {} belongs to another network (ss58 format {}), addresses of this network are in ss58 format {}",
+            stash,
+            u16::from(format),
+            expected
+        ))),
+        _ => Ok(account),
+    }
+}
 
-type ValidatorCache = BTreeMap<String, String>;
-type ValidatorEraCache = BTreeMap<String, String>;
+/// Decode a stash address against the chain ss58 format
+pub async fn parse_stash(stash: &str, cache: Data<RedisPool>) -> Result<AccountId32, ApiError> {
+    let ss58_format = get_ss58_format(cache).await?;
+    decode_stash(stash, ss58_format)
+}
 
-#[derive(Debug, Serialize, PartialEq)]
-pub struct Validator {
-    pub stash: String,
-    pub controller: String,
-    pub name: String,
-    pub own_stake: u128,
-    pub nominators: u32,
-    pub nominators_stake: u128,
-    pub inclusion_rate: f32,
-    pub avg_reward_points: f64,
-    pub commission: u32,
-    pub blocked: bool,
-    pub active: bool,
-    pub reward_staked: bool,
-    pub judgements: u32,
-    pub sub_accounts: u32,
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ValidatorParams {
+    // Comma-separated list of the fields returned, all fields if not given
+    #[serde(default)]
+    fields: Option<String>,
 }
 
-impl From<ValidatorCache> for Validator {
-    fn from(data: ValidatorCache) -> Self {
-        let zero = "0".to_string();
-        Validator {
-            stash: data.get("stash").unwrap_or(&"".to_string()).to_string(),
-            controller: data
-                .get("controller")
-                .unwrap_or(&"".to_string())
-                .to_string(),
-            name: data.get("name").unwrap_or(&"".to_string()).to_string(),
-            own_stake: data
-                .get("own_stake")
-                .unwrap_or(&zero)
-                .parse::<u128>()
-                .unwrap_or_default(),
-            nominators: data
-                .get("nominators")
-                .unwrap_or(&zero)
-                .parse::<u32>()
-                .unwrap_or_default(),
-            nominators_stake: data
-                .get("nominators_stake")
-                .unwrap_or(&zero)
-                .parse::<u128>()
-                .unwrap_or_default(),
-            inclusion_rate: data
-                .get("inclusion_rate")
-                .unwrap_or(&zero)
-                .parse::<f32>()
-                .unwrap_or_default(),
-            avg_reward_points: data
-                .get("avg_reward_points")
-                .unwrap_or(&zero)
-                .parse::<f64>()
-                .unwrap_or_default(),
-            commission: data
-                .get("commission")
-                .unwrap_or(&zero)
-                .parse::<u32>()
-                .unwrap_or_default(),
-            blocked: data
-                .get("blocked")
-                .unwrap_or(&zero)
-                .parse::<bool>()
-                .unwrap_or_default(),
-            active: data
-                .get("active")
-                .unwrap_or(&zero)
-                .parse::<bool>()
-                .unwrap_or_default(),
-            reward_staked: data
-                .get("reward_staked")
-                .unwrap_or(&zero)
-                .parse::<bool>()
-                .unwrap_or_default(),
-            judgements: data
-                .get("judgements")
-                .unwrap_or(&zero)
-                .parse::<u32>()
-                .unwrap_or_default(),
-            sub_accounts: data
-                .get("sub_accounts")
-                .unwrap_or(&zero)
-                .parse::<u32>()
-                .unwrap_or_default(),
+/// Fields of a serialized validator, values are kept as serialized so that planck
+/// values above u64::MAX are not rejected or rounded
+type ValidatorFields = BTreeMap<String, Box<RawValue>>;
+
+/// Keep only the fields requested of a serialized validator, e.g. `name,commission,active`.
+/// Unknown fields are rejected so that a typo is not mistaken for a missing value
+fn project_fields(
+    mut object: ValidatorFields,
+    fields: Option<&str>,
+) -> Result<ValidatorFields, ApiError> {
+    let fields: Vec<&str> = fields
+        .unwrap_or_default()
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect();
+    if fields.is_empty() {
+        return Ok(object);
+    }
+    let mut projection = ValidatorFields::new();
+    for field in fields {
+        match object.remove(field) {
+            Some(v) => {
+                projection.insert(field.to_string(), v);
+            }
+            None if projection.contains_key(field) => continue,
+            None => {
+                return Err(ApiError::BadRequest(format!(
+                    "Parameter fields has an invalid field '{}'. Fields must be a comma-separated list of the validator fields, e.g. fields=name,commission,active",
+                    field
+                )))
+            }
         }
     }
+    Ok(projection)
 }
 
-type ValidatorResponse = Validator;
-
 /// Get a validator
 pub async fn get_validator(
+    req: HttpRequest,
     stash: Path<String>,
-    _params: Query<Params>,
+    params: Query<ValidatorParams>,
     cache: Data<RedisPool>,
-) -> Result<Json<ValidatorResponse>, ApiError> {
+) -> Result<HttpResponse, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
     let mut conn = get_conn(&cache).await?;
-    let stash = AccountId32::from_str(&*stash.to_string())?;
+    let msg = format!("Validator account with address {} not found", stash);
+
+    // Short-circuit stashes recently looked up and not found
+    let not_found: bool = redis::cmd("EXISTS")
+        .arg(sync::Key::ValidatorNotFound(stash.clone()))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    if not_found {
+        return Err(ApiError::NotFound(msg));
+    }
+
     let mut data: ValidatorCache = redis::cmd("HGETALL")
         .arg(sync::Key::Validator(stash.clone()))
         .query_async(&mut conn as &mut Connection)
@@ -137,153 +145,386 @@ pub async fn get_validator(
         .map_err(CacheError::RedisCMDError)?;
 
     if data.len() == 0 {
-        let msg = format!("Validator account with address {} not found", stash);
         warn!("{}", msg);
+        if CONFIG.not_found_ttl_seconds > 0 {
+            let _: () = redis::cmd("SET")
+                .arg(sync::Key::ValidatorNotFound(stash.clone()))
+                .arg(1)
+                .arg("EX")
+                .arg(CONFIG.not_found_ttl_seconds)
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+        }
         return Err(ApiError::NotFound(msg));
     }
     data.insert("stash".to_string(), stash.to_string());
+    data.insert(
+        "recommended".to_string(),
+        is_recommended(&stash, cache.clone()).await?.to_string(),
+    );
 
-    respond_json(data.into())
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    let validator = Validator::from(data).with_tokens(token_decimals);
+    let etag_era = get_etag_era(cache.clone()).await?;
+    if params.fields.is_none() {
+        return respond_json_with_etag(&req, validator, etag_era);
+    }
+    let validator = serde_json::to_string(&validator)
+        .and_then(|validator| serde_json::from_str::<ValidatorFields>(&validator))
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    respond_json_with_etag(
+        &req,
+        project_fields(validator, params.fields.as_deref())?,
+        etag_era,
+    )
 }
 
-type BoardLimitsCache = BTreeMap<String, f64>;
+/// Verify if the validator is in the top of the recommended board of the active era
+async fn is_recommended(stash: &AccountId32, cache: Data<RedisPool>) -> Result<bool, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let recommended: bool = redis::cmd("SISMEMBER")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            sync::BOARD_RECOMMENDED_VALIDATORS.to_string(),
+        ))
+        .arg(stash.to_string())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
 
-#[derive(Debug, Serialize, PartialEq, Copy, Clone)]
-pub struct Interval {
-    pub min: f64,
-    pub max: f64,
+    Ok(recommended)
 }
 
-impl Default for Interval {
-    fn default() -> Interval {
-        Interval {
-            min: 0.0_f64,
-            max: 0.0_f64,
-        }
-    }
+/// Maximum number of validators that can be requested in one batch
+const BATCH_CAPACITY: usize = 16;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorsCountResponse {
+    pub total: u32,
+    pub active: u32,
+    pub era: EraIndex,
 }
 
-impl std::fmt::Display for Interval {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.min, self.max)
-    }
-}
-
-#[derive(Debug, Serialize, Clone, PartialEq)]
-pub struct BoardLimits {
-    pub inclusion_rate: Interval,
-    pub commission: Interval,
-    pub nominators: Interval,
-    pub avg_reward_points: Interval,
-    pub reward_staked: Interval,
-    pub active: Interval,
-    pub own_stake: Interval,
-    pub total_stake: Interval,
-    pub judgements: Interval,
-    pub sub_accounts: Interval,
-}
-
-impl Default for BoardLimits {
-    fn default() -> BoardLimits {
-        BoardLimits {
-            inclusion_rate: Interval::default(),
-            commission: Interval::default(),
-            nominators: Interval::default(),
-            avg_reward_points: Interval::default(),
-            reward_staked: Interval::default(),
-            active: Interval::default(),
-            own_stake: Interval::default(),
-            total_stake: Interval::default(),
-            judgements: Interval::default(),
-            sub_accounts: Interval::default(),
+/// Get the number of validators and active validators in the active era.
+/// Note: counted from the boards cardinality, so that it is cheap enough to be polled
+pub async fn get_validators_count(
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsCountResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let (total, active): (u32, u32) = redis::pipe()
+        .cmd("ZCARD")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            sync::BOARD_ALL_VALIDATORS.to_string(),
+        ))
+        .cmd("ZCARD")
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            sync::BOARD_ACTIVE_VALIDATORS.to_string(),
+        ))
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(ValidatorsCountResponse {
+        total,
+        active,
+        era: era_index,
+    })
+}
+
+/// Default and maximum number of validators hinted to each scan page
+const SCAN_DEFAULT_COUNT: u32 = 100;
+const SCAN_MAX_COUNT: u32 = 1000;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScanParams {
+    #[serde(default)]
+    cursor: u64,
+    #[serde(default)]
+    count: Option<u32>,
+    // Era of the board being scanned, should be kept across pages
+    #[serde(default)]
+    era: Option<EraIndex>,
+    #[serde(default)]
+    exclude_blocked: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorsScanResponse {
+    pub items: Vec<String>,
+    /// Zero when the whole board has been scanned
+    pub next_cursor: u64,
+    pub era: EraIndex,
+}
+
+/// ZSCAN replies with members and scores interleaved, only members are kept
+fn scanned_members(scanned: Vec<String>) -> Vec<String> {
+    scanned.into_iter().step_by(2).collect()
+}
+
+/// Page through all validators with a cursor, starting with cursor 0 until the
+/// next cursor returned is 0 again.
+/// Note: backed by ZSCAN, so validators in the board during the whole iteration are
+/// always returned, although the number of items per page is only a hint
+pub async fn get_validators_scan(
+    params: Query<ScanParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsScanResponse>, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = match params.era {
+        Some(era_index) => era_index,
+        None => redis::cmd("GET")
+            .arg(sync::Key::ActiveEra)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?,
+    };
+
+    let key = sync::Key::BoardAtEra(
+        era_index,
+        unscored_board_name(
+            sync::BOARD_ALL_VALIDATORS,
+            sync::BOARD_ALL_UNBLOCKED_VALIDATORS,
+            params.exclude_blocked,
+        ),
+    );
+    let count = params
+        .count
+        .unwrap_or(SCAN_DEFAULT_COUNT)
+        .min(SCAN_MAX_COUNT)
+        .max(1);
+
+    let (next_cursor, scanned): (u64, Vec<String>) = redis::cmd("ZSCAN")
+        .arg(key)
+        .arg(params.cursor)
+        .arg("COUNT")
+        .arg(count)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(ValidatorsScanResponse {
+        items: scanned_members(scanned),
+        next_cursor,
+        era: era_index,
+    })
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct BatchParams {
+    #[serde(default)]
+    stashes: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorBatchItem {
+    #[serde(flatten)]
+    pub validator: Validator,
+    pub found: bool,
+}
+
+type ValidatorsBatchResponse = Vec<ValidatorBatchItem>;
+
+/// Pair each requested stash with its cached data, keeping the request order.
+/// Stashes not found are flagged so that clients can line up the results
+fn batch_items(
+    stashes: &Vec<AccountId32>,
+    data: Vec<ValidatorCache>,
+    token_decimals: u8,
+) -> Vec<ValidatorBatchItem> {
+    stashes
+        .iter()
+        .zip(data.into_iter())
+        .map(|(stash, mut data)| {
+            let found = data.len() != 0;
+            data.insert("stash".to_string(), stash.to_string());
+            ValidatorBatchItem {
+                validator: Validator::from(data).with_tokens(token_decimals),
+                found,
+            }
+        })
+        .collect()
+}
+
+/// Parse the comma-separated list of stashes of a batch request
+fn parse_batch_stashes(
+    stashes: &str,
+    ss58_format: Option<u16>,
+) -> Result<Vec<AccountId32>, ApiError> {
+    let stashes_as_strvec: Vec<&str> = stashes
+        .split(",")
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    if stashes_as_strvec.len() == 0 || stashes_as_strvec.len() > BATCH_CAPACITY {
+        let msg = format!(
+            "Parameter stashes must be a comma-separated list of 1 up to {} addresses",
+            BATCH_CAPACITY
+        );
+        warn!("{}", msg);
+        return Err(ApiError::BadRequest(msg));
+    }
+
+    let mut stashes: Vec<AccountId32> = Vec::with_capacity(stashes_as_strvec.len());
+    for stash in stashes_as_strvec {
+        match decode_stash(stash, ss58_format) {
+            Ok(account) => stashes.push(account),
+            Err(ApiError::BadRequest(msg)) => {
+                let msg = format!("Parameter stashes has an invalid address. {}", msg);
+                warn!("{}", msg);
+                return Err(ApiError::BadRequest(msg));
+            }
+            Err(e) => return Err(e),
         }
     }
+    Ok(stashes)
 }
 
-impl std::fmt::Display for BoardLimits {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Note: the position of the traits is important, it should be the same as the position in weights
-        write!(
-            f,
-            "{},{},{},{},{},{},{},{},{},{}",
-            self.inclusion_rate.to_string(),
-            self.commission.to_string(),
-            self.nominators.to_string(),
-            self.avg_reward_points.to_string(),
-            self.reward_staked.to_string(),
-            self.active.to_string(),
-            self.own_stake.to_string(),
-            self.total_stake.to_string(),
-            self.judgements.to_string(),
-            self.sub_accounts.to_string()
-        )
+/// Get a list of validators side by side
+pub async fn get_validators_batch(
+    params: Query<BatchParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsBatchResponse>, ApiError> {
+    let stashes = parse_batch_stashes(&params.stashes, get_ss58_format(cache.clone()).await?)?;
+
+    let mut conn = get_conn(&cache).await?;
+    let mut pipe = redis::pipe();
+    for stash in stashes.iter() {
+        pipe.cmd("HGETALL").arg(sync::Key::Validator(stash.clone()));
     }
+    let data: Vec<ValidatorCache> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    respond_json(batch_items(&stashes, data, token_decimals))
+}
+
+/// Maximum number of validators returned in one search
+const SEARCH_CAPACITY: u32 = 100;
+
+/// Maximum number of names index members matched by one search
+const SEARCH_MAX_MATCHES: u32 = 1000;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SearchParams {
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    offset: Quantity,
+    #[serde(default = "default_search_n")]
+    n: Quantity,
+}
+
+fn default_search_n() -> Quantity {
+    20
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorName {
+    pub stash: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorsSearchResponse {
+    pub total: u32,
+    /// The matches were capped at SEARCH_MAX_MATCHES, so the total is only a lower bound
+    pub truncated: bool,
+    pub validators: Vec<ValidatorName>,
 }
 
-impl From<&Intervals> for BoardLimits {
-    fn from(data: &Intervals) -> Self {
-        BoardLimits {
-            inclusion_rate: *data.get(0).unwrap_or(&Interval::default()),
-            commission: *data.get(1).unwrap_or(&Interval::default()),
-            nominators: *data.get(2).unwrap_or(&Interval::default()),
-            avg_reward_points: *data.get(3).unwrap_or(&Interval::default()),
-            reward_staked: *data.get(4).unwrap_or(&Interval::default()),
-            active: *data.get(5).unwrap_or(&Interval::default()),
-            own_stake: *data.get(6).unwrap_or(&Interval::default()),
-            total_stake: *data.get(7).unwrap_or(&Interval::default()),
-            judgements: *data.get(8).unwrap_or(&Interval::default()),
-            sub_accounts: *data.get(9).unwrap_or(&Interval::default()),
+/// Unique stashes of the names index members matched, keeping the order
+fn search_stashes(members: &Vec<String>) -> Vec<String> {
+    let mut stashes: Vec<String> = Vec::new();
+    for member in members.iter() {
+        if let Some(stash) = sync::parse_name_index_member(member) {
+            if !stashes.iter().any(|x| x == stash) {
+                stashes.push(stash.to_string());
+            }
         }
     }
+    stashes
 }
 
-impl From<BoardLimitsCache> for BoardLimits {
-    fn from(data: BoardLimitsCache) -> Self {
-        let default_min = 0.0_f64;
-        let default_max = 100.0_f64;
-        BoardLimits {
-            inclusion_rate: Interval {
-                min: 0.0_f64,
-                max: 1.0_f64,
-            },
-            commission: Interval {
-                min: 0.0_f64,
-                max: COMMISSION_PLANCK as f64,
-            },
-            nominators: Interval {
-                min: 0.0_f64,
-                max: NOMINATORS_OVERSUBSCRIBED_THRESHOLD as f64,
-            },
-            avg_reward_points: Interval {
-                min: *data.get("min_avg_reward_points").unwrap_or(&default_min),
-                max: *data.get("max_avg_reward_points").unwrap_or(&default_max),
-            },
-            reward_staked: Interval {
-                min: 0.0_f64,
-                max: 1.0_f64,
-            },
-            active: Interval {
-                min: 0.0_f64,
-                max: 1.0_f64,
-            },
-            own_stake: Interval {
-                min: *data.get("min_own_stake").unwrap_or(&default_min),
-                max: *data.get("max_own_stake").unwrap_or(&default_max),
-            },
-            total_stake: Interval {
-                min: *data.get("min_total_stake").unwrap_or(&default_min),
-                max: *data.get("max_total_stake").unwrap_or(&default_max),
-            },
-            judgements: Interval {
-                min: *data.get("min_judgements").unwrap_or(&default_min),
-                max: *data.get("max_judgements").unwrap_or(&default_max),
-            },
-            sub_accounts: Interval {
-                min: *data.get("min_sub_accounts").unwrap_or(&default_min),
-                max: *data.get("max_sub_accounts").unwrap_or(&default_max),
-            },
-        }
+/// Search validators by any substring of the identity name
+pub async fn get_validators_search(
+    params: Query<SearchParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsSearchResponse>, ApiError> {
+    let query = params.q.trim();
+    if query.is_empty() {
+        let msg = format!("Parameter q must be defined with part of the validator name");
+        warn!("{}", msg);
+        return Err(ApiError::BadRequest(msg));
+    }
+
+    let mut conn = get_conn(&cache).await?;
+    let (min, max) = sync::name_index_range(query);
+    let members: Vec<String> = redis::cmd("ZRANGEBYLEX")
+        .arg(sync::Key::ValidatorNamesIndex)
+        .arg(min)
+        .arg(max)
+        .arg("LIMIT")
+        .arg(0)
+        .arg(SEARCH_MAX_MATCHES)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let stashes = search_stashes(&members);
+    let n = if params.n == 0 || params.n > SEARCH_CAPACITY {
+        SEARCH_CAPACITY
+    } else {
+        params.n
+    };
+    let selected: Vec<String> = stashes
+        .iter()
+        .skip(params.offset as usize)
+        .take(n as usize)
+        .cloned()
+        .collect();
+
+    let mut pipe = redis::pipe();
+    for stash in selected.iter() {
+        let stash = AccountId32::from_str(stash)?;
+        pipe.cmd("HGET")
+            .arg(sync::Key::Validator(stash))
+            .arg("name");
     }
+    let names: Vec<Option<String>> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    respond_json(ValidatorsSearchResponse {
+        total: stashes.len() as u32,
+        truncated: members.len() >= SEARCH_MAX_MATCHES as usize,
+        validators: selected
+            .into_iter()
+            .zip(names.into_iter())
+            .map(|(stash, name)| ValidatorName {
+                stash,
+                name: name.unwrap_or_default(),
+            })
+            .collect(),
+    })
 }
 
 #[derive(Debug, Serialize, PartialEq)]
@@ -297,22 +538,103 @@ pub enum Status {
 pub struct ValidatorRankResponse {
     pub stash: String,
     pub rank: i64,
-    pub scores: Vec<f64>,
+    pub total: i64,
+    pub percentile: f64,
+    pub score: f64,
+    pub normalized_score: f64,
+    pub scores: Scores,
     pub status: Status,
     pub status_msg: String,
 }
 
+impl ValidatorRankResponse {
+    /// Response of a stash without rank, either not ready or not found
+    fn unavailable(stash: &AccountId32, status: Status, status_msg: String) -> Self {
+        Self {
+            stash: stash.to_string(),
+            rank: 0,
+            total: 0,
+            percentile: 0.0,
+            score: 0.0,
+            normalized_score: 0.0,
+            scores: Scores::Values(Vec::new()),
+            status,
+            status_msg,
+        }
+    }
+}
+
+/// Scores of a stash as cached in the board, comma separated
+fn parse_scores(scores: &str) -> Vec<f64> {
+    scores
+        .split(",")
+        .map(|x| x.parse::<f64>().unwrap_or_default())
+        .collect()
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LabeledScore {
+    pub criterion: String,
+    pub weight: u32,
+    pub value: f64,
+}
+
+/// Scores are returned as bare values, or labeled with the respective criterion if requested
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum Scores {
+    Values(Vec<f64>),
+    Labeled(Vec<LabeledScore>),
+}
+
+impl Scores {
+    fn new(values: Vec<f64>, weights: &[u32], labeled: bool) -> Self {
+        if !labeled {
+            return Scores::Values(values);
+        }
+        Scores::Labeled(
+            values
+                .into_iter()
+                .zip(CRITERIA.iter().zip(weights.iter()))
+                .map(|(value, (criterion, weight))| LabeledScore {
+                    criterion: criterion.to_string(),
+                    weight: *weight,
+                    value,
+                })
+                .collect(),
+        )
+    }
+}
+
 /// Get a validator rank
 pub async fn get_validator_rank(
     stash: Path<String>,
-    params: Query<Params>,
+    mut params: Query<Params>,
     cache: Data<RedisPool>,
 ) -> Result<Json<ValidatorRankResponse>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    // Excluded stashes are never ranked, even in boards generated before the stash
+    // was excluded
+    if CONFIG.is_stash_excluded(&stash.to_string()) {
+        let msg = format!("The rank for stash {} is not found.", stash);
+        warn!("{}", msg);
+        return respond_json(ValidatorRankResponse::unavailable(
+            &stash,
+            Status::NotFound,
+            msg,
+        ));
+    }
     let mut conn = get_conn(&cache).await?;
-    let stash = AccountId32::from_str(&*stash.to_string())?;
     // Set field rank if params are correctly defined
     let board_name = match params.q {
-        Queries::Board => get_board_name(&params.w, Some(&params.i)),
+        Queries::Board => smoothed_board_name(
+            formula_board_name(
+                get_board_name(&params.w, Some(&params.i), params.norm),
+                params.formula,
+            ),
+            params.smooth,
+        ),
         _ => {
             let msg = format!("Parameter q must be equal to one of the options: [Board]");
             warn!("{}", msg);
@@ -342,13 +664,11 @@ pub async fn get_validator_rank(
             stash
         );
         warn!("{}", msg);
-        return respond_json(ValidatorRankResponse {
-            stash: stash.to_string(),
-            rank: 0,
-            scores: Vec::new(),
-            status: Status::NotReady,
-            status_msg: msg,
-        });
+        return respond_json(ValidatorRankResponse::unavailable(
+            &stash,
+            Status::NotReady,
+            msg,
+        ));
     }
 
     // Get rank
@@ -367,13 +687,11 @@ pub async fn get_validator_rank(
         _ => {
             let msg = format!("The rank for stash {} is not found.", stash);
             warn!("{}", msg);
-            return respond_json(ValidatorRankResponse {
-                stash: stash.to_string(),
-                rank: 0,
-                scores: Vec::new(),
-                status: Status::NotFound,
-                status_msg: msg,
-            });
+            return respond_json(ValidatorRankResponse::unavailable(
+                &stash,
+                Status::NotFound,
+                msg,
+            ));
 
             // return Err(ApiError::NotFound(msg));
         }
@@ -393,13 +711,11 @@ pub async fn get_validator_rank(
             stash
         );
         warn!("{}", msg);
-        return respond_json(ValidatorRankResponse {
-            stash: stash.to_string(),
-            rank: 0,
-            scores: Vec::new(),
-            status: Status::NotReady,
-            status_msg: msg,
-        });
+        return respond_json(ValidatorRankResponse::unavailable(
+            &stash,
+            Status::NotReady,
+            msg,
+        ));
     }
 
     // Get scores
@@ -418,34 +734,295 @@ pub async fn get_validator_rank(
         }
     };
 
-    let scores_vec: Vec<&str> = scores_str.split(",").collect();
-    let scores: Vec<f64> = scores_vec
-        .iter()
-        .map(|x| x.parse::<f64>().unwrap_or_default())
-        .collect();
+    let scores = parse_scores(&scores_str);
+
+    // Get total and normalized score
+    let key_normalized = sync::Key::BoardAtEra(era_index, format!("{}:normalized", board_name));
+    let (score, normalized_score): (Option<f64>, Option<f64>) = redis::pipe()
+        .cmd("ZSCORE")
+        .arg(key.clone())
+        .arg(stash.to_string())
+        .cmd("HGET")
+        .arg(key_normalized)
+        .arg(stash.to_string())
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let total = get_board_total(key, cache.clone()).await? as i64;
 
     respond_json(ValidatorRankResponse {
         stash: stash.to_string(),
         rank: rank,
-        scores: scores,
+        total: total,
+        percentile: percentile(rank, total),
+        score: score.unwrap_or_default(),
+        // Boards cached before the normalized score was introduced are normalized on the fly
+        normalized_score: normalized_score
+            .unwrap_or_else(|| normalized_score_of(&scores, &params.w, params.formula)),
+        scores: Scores::new(scores, &params.w, params.labeled),
         status: Status::Ok,
         status_msg: "".to_string(),
     })
 }
 
+/// Rank, score, scores and normalized score of a stash in a board, as returned by the pipeline
+type RankBatchData = (Option<i64>, Option<f64>, Option<String>, Option<f64>);
+
+type ValidatorsRankBatchResponse = Vec<ValidatorRankResponse>;
+
+/// Pair each requested stash with its rank in the board, keeping the request order.
+/// Stashes not in the board or excluded are not found and stashes without scores are not ready yet
+fn rank_batch_items(
+    stashes: &Vec<AccountId32>,
+    data: Vec<RankBatchData>,
+    total: i64,
+    weights: &Weights,
+    formula: Formula,
+    labeled: bool,
+    is_excluded: impl Fn(&str) -> bool,
+) -> ValidatorsRankBatchResponse {
+    stashes
+        .iter()
+        .zip(data.into_iter())
+        .map(|(stash, (rank, score, scores, normalized_score))| {
+            // Redis rank is index based
+            let rank = match rank {
+                Some(rank) if !is_excluded(&stash.to_string()) => rank + 1,
+                _ => {
+                    let msg = format!("The rank for stash {} is not found.", stash);
+                    return ValidatorRankResponse::unavailable(stash, Status::NotFound, msg);
+                }
+            };
+            let scores = match scores {
+                Some(scores) => parse_scores(&scores),
+                None => {
+                    let msg = format!(
+                        "The scores for stash {} are not yet available. Wait a second and try again.",
+                        stash
+                    );
+                    return ValidatorRankResponse::unavailable(stash, Status::NotReady, msg);
+                }
+            };
+            ValidatorRankResponse {
+                stash: stash.to_string(),
+                rank,
+                total,
+                percentile: percentile(rank, total),
+                score: score.unwrap_or_default(),
+                // Boards cached before the normalized score was introduced are normalized on the fly
+                normalized_score: normalized_score
+                    .unwrap_or_else(|| normalized_score_of(&scores, weights, formula)),
+                scores: Scores::new(scores, weights, labeled),
+                status: Status::Ok,
+                status_msg: "".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Get the rank of a list of validators, the board is generated once for all of them
+pub async fn get_validators_rank_batch(
+    batch: Query<BatchParams>,
+    mut params: Query<Params>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsRankBatchResponse>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
+    let stashes = parse_batch_stashes(&batch.stashes, get_ss58_format(cache.clone()).await?)?;
+    if params.q != Queries::Board {
+        let msg = format!("Parameter q must be equal to one of the options: [Board]");
+        warn!("{}", msg);
+        return Err(ApiError::BadRequest(msg));
+    }
+
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    // The board can not be generated while syncing, in that case
+    // every stash is just flagged as not ready
+    let key = match generate_board(era_index, false, &params, cache.clone()).await {
+        Ok(key) => key,
+        Err(ApiError::ServiceUnavailable(_)) => {
+            return respond_json(
+                stashes
+                    .iter()
+                    .map(|stash| {
+                        let msg = format!(
+                            "The rank for stash {} is not yet available. Wait a second and try again.",
+                            stash
+                        );
+                        ValidatorRankResponse::unavailable(stash, Status::NotReady, msg)
+                    })
+                    .collect(),
+            );
+        }
+        Err(e) => return Err(e),
+    };
+
+    let board_name = smoothed_board_name(
+        formula_board_name(
+            get_board_name(&params.w, Some(&params.i), params.norm),
+            params.formula,
+        ),
+        params.smooth,
+    );
+    let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name));
+    let key_normalized = sync::Key::BoardAtEra(era_index, format!("{}:normalized", board_name));
+
+    let mut pipe = redis::pipe();
+    for stash in stashes.iter() {
+        pipe.cmd("ZREVRANK")
+            .arg(key.clone())
+            .arg(stash.to_string())
+            .cmd("ZSCORE")
+            .arg(key.clone())
+            .arg(stash.to_string())
+            .cmd("HGET")
+            .arg(key_scores.to_string())
+            .arg(stash.to_string())
+            .cmd("HGET")
+            .arg(key_normalized.to_string())
+            .arg(stash.to_string());
+    }
+    let data: Vec<RankBatchData> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let total = get_board_total(key, cache.clone()).await? as i64;
+
+    respond_json(rank_batch_items(
+        &stashes,
+        data,
+        total,
+        &params.w,
+        params.formula,
+        params.labeled,
+        |stash| CONFIG.is_stash_excluded(stash),
+    ))
+}
+
+/// Percentile of a rank in a board, e.g. rank 1 out of 4 is 1.0 and rank 4 is 0.25
+fn percentile(rank: i64, total: i64) -> f64 {
+    if rank <= 0 || total <= 0 {
+        return 0.0;
+    }
+    1.0 - (rank - 1) as f64 / total as f64
+}
+
+/// Maximum number of eras looked up in the rank history
+const RANK_HISTORY_MAX_ERAS: u32 = 84;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorRankAtEra {
+    pub era_index: u32,
+    pub rank: i64,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorRankHistoryResponse {
+    pub stash: String,
+    pub eras: Vec<ValidatorRankAtEra>,
+    /// Only the default board is kept for every era synced, any other board expires
+    /// after BOARD_TTL_SECONDS so its history only covers the eras recently requested
+    pub complete: bool,
+}
+
+/// Get a validator rank history for the last n eras.
+/// Note: Only the board defined by the default weights is generated on every sync and
+/// never expires, any other board only has history for the eras it was requested within
+/// BOARD_TTL_SECONDS. Boards filtered by intervals are user specific and are not
+/// considered in the history.
+pub async fn get_validator_rank_history(
+    stash: Path<String>,
+    mut params: Query<Params>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorRankHistoryResponse>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let board_name = smoothed_board_name(
+        formula_board_name(get_board_name(&params.w, None, params.norm), params.formula),
+        params.smooth,
+    );
+
+    let active_era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let n = if params.n == 0 || params.n > RANK_HISTORY_MAX_ERAS {
+        RANK_HISTORY_MAX_ERAS
+    } else {
+        params.n
+    };
+    let start_index = active_era_index.saturating_sub(n - 1);
+
+    let mut pipe = redis::pipe();
+    for era_index in start_index..=active_era_index {
+        let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+        pipe.cmd("ZREVRANK").arg(key.clone()).arg(stash.to_string());
+        pipe.cmd("ZSCORE").arg(key).arg(stash.to_string());
+    }
+    let ranks: Vec<(Option<i64>, Option<f64>)> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    // Skip eras where the board was not generated or the stash was not ranked.
+    // Note: excluded stashes are never ranked, even in boards generated before
+    // the stash was excluded
+    let excluded = CONFIG.is_stash_excluded(&stash.to_string());
+    let mut eras: Vec<ValidatorRankAtEra> = ranks
+        .into_iter()
+        .filter(|_| !excluded)
+        .zip(start_index..=active_era_index)
+        .filter_map(|((rank, score), era_index)| match (rank, score) {
+            (Some(rank), Some(score)) => Some(ValidatorRankAtEra {
+                era_index,
+                // Redis rank is index based
+                rank: rank + 1,
+                score,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    // Sort eras by era_index
+    eras.sort_by(|a, b| b.era_index.cmp(&a.era_index));
+    respond_json(ValidatorRankHistoryResponse {
+        stash: stash.to_string(),
+        eras,
+        complete: is_default_board(&board_name),
+    })
+}
+
 #[derive(Debug, Serialize, PartialEq)]
 pub struct ValidatorEra {
     pub era_index: u32,
     pub own_stake: u128,
+    pub own_stake_tokens: f64,
     pub total_stake: u128,
+    pub total_stake_tokens: f64,
     pub others_stake: u128,
+    pub others_stake_tokens: f64,
     pub stakers: u32,
     pub others_stake_clipped: u128,
+    pub others_stake_clipped_tokens: f64,
     pub stakers_clipped: u32,
     pub reward_points: u32,
     pub commission: u32,
     pub blocked: bool,
     pub active: bool,
+    /// If the era rewards have been paid out, not known for the active era or
+    /// while the validator ledger is not synced
+    pub rewards_claimed: Option<bool>,
 }
 
 #[derive(Debug, Serialize, PartialEq)]
@@ -468,16 +1045,19 @@ impl From<ValidatorEraCache> for ValidatorEra {
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            own_stake_tokens: 0.0,
             total_stake: data
                 .get("total_stake")
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            total_stake_tokens: 0.0,
             others_stake: data
                 .get("others_stake")
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            others_stake_tokens: 0.0,
             stakers: data
                 .get("stakers")
                 .unwrap_or(&zero)
@@ -488,6 +1068,7 @@ impl From<ValidatorEraCache> for ValidatorEra {
                 .unwrap_or(&zero)
                 .parse::<u128>()
                 .unwrap_or_default(),
+            others_stake_clipped_tokens: 0.0,
             stakers_clipped: data
                 .get("stakers_clipped")
                 .unwrap_or(&zero)
@@ -513,19 +1094,66 @@ impl From<ValidatorEraCache> for ValidatorEra {
                 .unwrap_or(&zero)
                 .parse::<bool>()
                 .unwrap_or_default(),
+            rewards_claimed: data
+                .get("rewards_claimed")
+                .and_then(|v| v.parse::<bool>().ok()),
         }
     }
 }
 
-/// Get a validator eras
-pub async fn get_validator_eras(
-    stash: Path<String>,
+impl ValidatorEra {
+    /// Set the token-denominated stakes, for display only
+    fn with_tokens(mut self, token_decimals: u8) -> Self {
+        self.own_stake_tokens = to_tokens(self.own_stake, token_decimals);
+        self.total_stake_tokens = to_tokens(self.total_stake, token_decimals);
+        self.others_stake_tokens = to_tokens(self.others_stake, token_decimals);
+        self.others_stake_clipped_tokens = to_tokens(self.others_stake_clipped, token_decimals);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ErasParams {
+    #[serde(default)]
+    from_era: Option<EraIndex>,
+    #[serde(default)]
+    to_era: Option<EraIndex>,
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+/// Select the validator era keys within the eras interval requested, ordered by the
+/// most recent era first and limited to the number of eras requested.
+/// Note: the era index is the segment before ":era:", so that keys can be prefixed
+fn select_era_keys(keys: Vec<String>, params: &ErasParams) -> Vec<(EraIndex, String)> {
+    let mut selected: Vec<(EraIndex, String)> = keys
+        .into_iter()
+        .filter_map(|key| {
+            let end = key.find(":era:")?;
+            let start = key[..end].rfind(':').map_or(0, |i| i + 1);
+            let era_index = key[start..end].parse::<EraIndex>().ok()?;
+            Some((era_index, key))
+        })
+        .filter(|(era_index, _)| {
+            *era_index >= params.from_era.unwrap_or(EraIndex::MIN)
+                && *era_index <= params.to_era.unwrap_or(EraIndex::MAX)
+        })
+        .collect();
+    selected.sort_by(|a, b| b.0.cmp(&a.0));
+    if let Some(limit) = params.limit {
+        selected.truncate(limit as usize);
+    }
+    selected
+}
+
+/// Scan all the era keys cached for a validator
+async fn scan_validator_era_keys(
+    stash: &AccountId32,
     cache: Data<RedisPool>,
-) -> Result<Json<ValidatorEraResponse>, ApiError> {
+) -> Result<Vec<String>, ApiError> {
     let mut conn = get_conn(&cache).await?;
 
-    let stash = AccountId32::from_str(&*stash.to_string())?;
-    let mut eras: Vec<ValidatorEra> = vec![];
+    let mut keys: Vec<String> = vec![];
     let mut optional = Some(-1);
     while let Some(i) = optional {
         if i == 0 {
@@ -535,7 +1163,7 @@ pub async fn get_validator_eras(
             let cursor = if i == -1 { 0 } else { i };
 
             // Scan redis
-            let (cursor, keys): (i32, Vec<String>) = redis::cmd("SCAN")
+            let (cursor, scanned): (i32, Vec<String>) = redis::cmd("SCAN")
                 .arg(cursor)
                 .arg("MATCH")
                 .arg(sync::Key::ValidatorAtEraScan(stash.clone()))
@@ -547,876 +1175,1144 @@ pub async fn get_validator_eras(
 
             optional = Some(cursor);
 
-            for key in keys {
-                let mut data: ValidatorEraCache = redis::cmd("HGETALL")
-                    .arg(key.clone())
-                    .query_async(&mut conn as &mut Connection)
-                    .await
-                    .map_err(CacheError::RedisCMDError)?;
-
-                if data.len() == 0 {
-                    let msg = format!("cache key {} not available", key);
-                    error!("{}", msg);
-                    continue;
-                }
-                if let Some(x) = key.find(':') {
-                    data.insert("era_index".to_string(), String::from(&key[..x]));
-                }
-                eras.push(data.into());
-            }
+            keys.extend(scanned);
         }
     }
 
-    // Sort eras by era_index
-    eras.sort_by(|a, b| b.era_index.cmp(&a.era_index));
+    Ok(keys)
+}
+
+/// Get a validator eras
+pub async fn get_validator_eras(
+    stash: Path<String>,
+    params: Query<ErasParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorEraResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let keys = scan_validator_era_keys(&stash, cache.clone()).await?;
+
+    let mut conn = get_conn(&cache).await?;
+
+    // Only fetch the eras requested, sorted by era_index
+    let selected = select_era_keys(keys, &params);
+    let mut pipe = redis::pipe();
+    for (_, key) in selected.iter() {
+        pipe.cmd("HGETALL").arg(key);
+    }
+    let data: Vec<ValidatorEraCache> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    let mut eras: Vec<ValidatorEra> = Vec::with_capacity(selected.len());
+    for ((era_index, key), mut data) in selected.into_iter().zip(data.into_iter()) {
+        if data.len() == 0 {
+            let msg = format!("cache key {} not available", key);
+            error!("{}", msg);
+            continue;
+        }
+        data.insert("era_index".to_string(), era_index.to_string());
+        eras.push(ValidatorEra::from(data).with_tokens(token_decimals));
+    }
+
     respond_json(ValidatorEraResponse {
         stash: stash.to_string(),
         eras: eras,
     })
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
-enum Queries {
-    All = 1,
-    Active = 2,
-    Board = 3,
-    Other = 4,
+#[derive(Debug, Deserialize, Clone)]
+pub struct EraDeltaParams {
+    from: EraIndex,
+    to: EraIndex,
 }
 
-impl std::fmt::Display for Queries {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::All => write!(f, "all"),
-            Self::Active => write!(f, "active"),
-            Self::Board => write!(f, "board"),
-            Self::Other => write!(f, "other"),
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Delta {
+    pub from: u128,
+    pub to: u128,
+    pub change: i128,
+    /// Change relative to the from value, none if the from value is zero
+    pub change_percent: Option<f64>,
+}
+
+impl Delta {
+    fn new(from: u128, to: u128) -> Self {
+        Delta {
+            from,
+            to,
+            change: to as i128 - from as i128,
+            change_percent: if from == 0 {
+                None
+            } else {
+                Some((to as f64 - from as f64) / from as f64 * 100.0)
+            },
         }
     }
 }
 
-// TODO: get this constants from chain
-const NOMINATORS_OVERSUBSCRIBED_THRESHOLD: u32 = 256;
-const COMMISSION_PLANCK: u32 = 1000000000;
-
-/// Weight can be any value in a 10-point scale. Higher the weight more important
-/// is the criteria to the user
-type Weight = u32;
-
-/// Weights represent an array of points, where the points in each position represents
-/// the weight for the respective criteria
-/// Position 0 - Higher Inclusion rate is preferrable
-/// Position 1 - Lower Commission is preferrable
-/// Position 2 - Lower Nominators is preferrable (limit to 256 -> oversubscribed)
-/// Position 3 - Higher Reward Points is preferrable
-/// Position 4 - If reward is staked is preferrable
-/// Position 5 - If in active set is preferrable
-/// Position 6 - Higher own stake is preferrable
-/// Position 7 - Lower total stake is preferrable
-/// Position 8 - Higher number of Reasonable or KnownGood judgements is preferrable
-/// Position 9 - Lower number of sub-accounts is preferrable
-type Weights = Vec<Weight>;
-
-type Intervals = Vec<Interval>;
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorEraDeltaResponse {
+    pub stash: String,
+    pub from_era: EraIndex,
+    pub to_era: EraIndex,
+    pub reward_points: Delta,
+    pub total_stake: Delta,
+    pub own_stake: Delta,
+    pub commission: Delta,
+    pub stakers: Delta,
+}
 
-/// Current weighs capacity
-const WEIGHTS_CAPACITY: usize = 10;
+impl ValidatorEraDeltaResponse {
+    fn new(stash: String, from: &ValidatorEra, to: &ValidatorEra) -> Self {
+        ValidatorEraDeltaResponse {
+            stash,
+            from_era: from.era_index,
+            to_era: to.era_index,
+            reward_points: Delta::new(from.reward_points.into(), to.reward_points.into()),
+            total_stake: Delta::new(from.total_stake, to.total_stake),
+            own_stake: Delta::new(from.own_stake, to.own_stake),
+            commission: Delta::new(from.commission.into(), to.commission.into()),
+            stakers: Delta::new(from.stakers.into(), to.stakers.into()),
+        }
+    }
+}
 
-/// Current limits capacity
-const INTERVALS_CAPACITY: usize = 10;
+/// Get the changes of a validator between two eras, e.g. reward points up 12% since
+/// the previous era
+pub async fn get_validator_era_delta(
+    stash: Path<String>,
+    params: Query<EraDeltaParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorEraDeltaResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
 
-// Number of elements to return
-type Quantity = u32;
+    let mut eras: Vec<ValidatorEra> = Vec::with_capacity(2);
+    for era_index in [params.from, params.to].iter() {
+        let mut data: ValidatorEraCache = redis::cmd("HGETALL")
+            .arg(sync::Key::ValidatorAtEra(*era_index, stash.clone()))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        if data.len() == 0 {
+            let msg = format!(
+                "Validator {} data at era {} is not available, either the era is not synced or the validator was not active in it",
+                stash, era_index
+            );
+            warn!("{}", msg);
+            return Err(ApiError::NotFound(msg));
+        }
+        data.insert("era_index".to_string(), era_index.to_string());
+        eras.push(ValidatorEra::from(data));
+    }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
-pub struct Params {
-    #[serde(default = "default_queries")]
-    q: Queries,
-    #[serde(default = "default_weights")]
-    #[serde(deserialize_with = "parse_weights")]
-    w: Weights,
-    #[serde(default = "default_intervals")]
-    #[serde(deserialize_with = "parse_intervals")]
-    i: Intervals,
-    #[serde(default)]
-    n: Quantity,
+    respond_json(ValidatorEraDeltaResponse::new(
+        stash.to_string(),
+        &eras[0],
+        &eras[1],
+    ))
 }
 
-fn default_queries() -> Queries {
-    Queries::Other
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorCommissionAtEra {
+    pub era_index: EraIndex,
+    pub commission: u32,
 }
 
-fn default_weights() -> Weights {
-    vec![0; WEIGHTS_CAPACITY]
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorCommissionHistoryResponse {
+    pub stash: String,
+    pub eras: Vec<ValidatorCommissionAtEra>,
 }
 
-fn default_intervals() -> Intervals {
-    vec![]
-}
+/// Get a validator commission at every era synced, most recent era first
+pub async fn get_validator_commission_history(
+    stash: Path<String>,
+    params: Query<ErasParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorCommissionHistoryResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let keys = scan_validator_era_keys(&stash, cache.clone()).await?;
 
-fn parse_weights<'de, D>(d: D) -> Result<Weights, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    Deserialize::deserialize(d).map(|x: Option<_>| {
-        let weights_as_csv = x.unwrap_or("".to_string());
+    let mut conn = get_conn(&cache).await?;
 
-        let mut weights_as_strvec: Vec<&str> = weights_as_csv.split(",").collect();
-        weights_as_strvec.resize(WEIGHTS_CAPACITY, "5");
+    let selected = select_era_keys(keys, &params);
+    let mut pipe = redis::pipe();
+    for (_, key) in selected.iter() {
+        pipe.cmd("HGET").arg(key).arg("commission");
+    }
+    let commissions: Vec<Option<u32>> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
 
-        let mut weights: Weights = Vec::with_capacity(WEIGHTS_CAPACITY);
-        for i in 0..WEIGHTS_CAPACITY {
-            let weight: u32 = weights_as_strvec.get(i).unwrap_or(&"0").parse().unwrap();
-            let weight = if weight > 9 { 9 } else { weight };
-            weights.push(weight);
-        }
-        weights
-    })
-}
+    let eras = selected
+        .into_iter()
+        .zip(commissions.into_iter())
+        .filter_map(|((era_index, _), commission)| {
+            Some(ValidatorCommissionAtEra {
+                era_index,
+                commission: commission?,
+            })
+        })
+        .collect();
 
-fn parse_intervals<'de, D>(d: D) -> Result<Intervals, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    Deserialize::deserialize(d).map(|x: Option<_>| {
-        let intervals_as_csv = x.unwrap_or("".to_string());
-        let mut intervals_as_strvec: Vec<&str> = intervals_as_csv.split(",").collect();
-        intervals_as_strvec.resize(INTERVALS_CAPACITY, "0");
-        let mut intervals: Intervals = Vec::with_capacity(INTERVALS_CAPACITY);
-        for i in 0..INTERVALS_CAPACITY {
-            let interval_as_strvec: Vec<&str> = intervals_as_strvec[i].split(":").collect();
-            let interval = Interval {
-                min: interval_as_strvec.get(0).unwrap_or(&"0").parse().unwrap(),
-                max: interval_as_strvec.get(1).unwrap_or(&"0").parse().unwrap(),
-            };
-            intervals.push(interval);
-        }
-        intervals
+    respond_json(ValidatorCommissionHistoryResponse {
+        stash: stash.to_string(),
+        eras,
     })
 }
 
 #[derive(Debug, Serialize, PartialEq)]
-pub struct MetaResponse {
-    pub limits: String,
-}
-
-impl Default for MetaResponse {
-    fn default() -> MetaResponse {
-        MetaResponse {
-            limits: String::default(),
-        }
-    }
+pub struct ValidatorPointsAtEra {
+    pub era_index: EraIndex,
+    pub points: u32,
 }
 
 #[derive(Debug, Serialize, PartialEq)]
-pub struct ValidatorsResponse {
-    pub addresses: Vec<String>,
-    pub meta: MetaResponse,
+pub struct ValidatorPointsResponse {
+    pub stash: String,
+    pub eras: Vec<ValidatorPointsAtEra>,
 }
 
-fn get_board_name(weights: &Weights, intervals: Option<&Intervals>) -> String {
-    match intervals {
-        Some(i) => {
-            if i.is_empty() {
-                return format!("{}", weights_to_string(weights));
-            }
-            format!("{}|{}", weights_to_string(weights), intervals_to_string(i),)
-        }
-        None => format!("{}", weights_to_string(weights)),
-    }
-}
+/// Get a validator reward points at every era it was active, most recent era first
+pub async fn get_validator_points(
+    stash: Path<String>,
+    params: Query<ErasParams>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorPointsResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
 
-fn weights_to_string(weights: &Weights) -> String {
-    weights
-        .iter()
-        .enumerate()
-        .map(|(i, x)| {
-            if i == 0 {
-                return x.to_string();
-            }
-            format!(",{}", x)
-        })
-        .collect()
-}
+    // Members are encoded as era:points and scored by era
+    let max = params
+        .to_era
+        .map_or("+inf".to_string(), |era_index| era_index.to_string());
+    let min = params
+        .from_era
+        .map_or("-inf".to_string(), |era_index| era_index.to_string());
+    let count: i64 = params.limit.map_or(-1, |limit| limit as i64);
+    let members: Vec<String> = redis::cmd("ZRANGE")
+        .arg(sync::Key::ActiveErasByValidator(stash.clone()))
+        .arg(max)
+        .arg(min)
+        .arg("BYSCORE")
+        .arg("REV")
+        .arg("LIMIT")
+        .arg(0)
+        .arg(count)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
 
-fn intervals_to_string(intervals: &Intervals) -> String {
-    intervals
+    let eras = members
         .iter()
-        .enumerate()
-        .map(|(i, x)| {
-            if i == 0 {
-                return format!("{}", x);
+        .filter_map(|member| match sync::parse_era_points(member) {
+            Some((era_index, points)) => Some(ValidatorPointsAtEra { era_index, points }),
+            None => {
+                warn!("Skipping malformed era points member {}", member);
+                None
             }
-            format!(",{}", x)
         })
-        .collect()
-}
+        .collect();
 
-/// Normalize value between min and max
-fn normalize_value(value: f64, min: f64, max: f64) -> f64 {
-    if value == 0.0 || value < min {
-        return 0.0;
-    }
-    if value > max {
-        return 1.0;
-    }
-    (value - min) / (max - min)
+    respond_json(ValidatorPointsResponse {
+        stash: stash.to_string(),
+        eras,
+    })
 }
 
-/// Reverse normalization
-fn reverse_normalize_value(value: f64, min: f64, max: f64) -> f64 {
-    1.0 - normalize_value(value, min, max)
+#[derive(Debug, Deserialize, Clone)]
+pub struct SessionsParams {
+    #[serde(default)]
+    limit: Option<u32>,
 }
 
-/// Normalize commission between 0 - 1
-fn normalize_commission(commission: u32) -> f64 {
-    (commission as f64 / COMMISSION_PLANCK as f64) as f64
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorPointsAtSession {
+    pub session_index: SessionIndex,
+    pub era_index: EraIndex,
+    pub points: u32,
 }
 
-/// Reverse Normalize commission between 0 - 1
-/// lower commission the better
-fn reverse_normalize_commission(commission: u32, min: f64, max: f64) -> f64 {
-    reverse_normalize_value(
-        normalize_commission(commission),
-        (min / COMMISSION_PLANCK as f64) as f64,
-        (max / COMMISSION_PLANCK as f64) as f64,
-    )
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorSessionsResponse {
+    pub stash: String,
+    pub sessions: Vec<ValidatorPointsAtSession>,
 }
 
-/// Normalize boolean flag between 0 - 1
-fn normalize_flag(flag: bool) -> f64 {
-    (flag as u32) as f64
-}
-
-async fn calculate_avg_points(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
-    let mut conn = get_conn(&cache).await?;
-    let v: Vec<(EraIndex, u32)> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(0, name.to_string()))
-        .arg("-inf")
-        .arg("+inf")
-        .arg("BYSCORE")
-        .arg("WITHSCORES")
-        .query_async(&mut conn as &mut Connection)
-        .await
-        .map_err(CacheError::RedisCMDError)?;
-    // Convert Vec<(EraIndex, u32)> to Vec<u32> to easily calculate average
-    let scores: Vec<u32> = v.into_iter().map(|(_, score)| score).collect();
-    let avg = stats::mean(&scores);
-    Ok(avg)
-}
-
-async fn _calculate_confidence_interval_95(
+/// Get a validator reward points earned at every session it was active, most recent
+/// session first. Sessions are only recorded if SYNC_SESSION_POINTS is enabled
+pub async fn get_validator_sessions(
+    stash: Path<String>,
+    params: Query<SessionsParams>,
     cache: Data<RedisPool>,
-    name: &str,
-) -> Result<(f64, f64), ApiError> {
+) -> Result<Json<ValidatorSessionsResponse>, ApiError> {
+    let stash = parse_stash(&stash, cache.clone()).await?;
     let mut conn = get_conn(&cache).await?;
-    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(0, name.to_string()))
-        .arg("-inf")
-        .arg("+inf")
-        .arg("BYSCORE")
-        .arg("WITHSCORES")
+
+    // Members are encoded as session:era:points and scored by session
+    let stop: i64 = params.limit.map_or(-1, |limit| limit.max(1) as i64 - 1);
+    let members: Vec<String> = redis::cmd("ZRANGE")
+        .arg(sync::Key::SessionsByValidator(stash.clone()))
+        .arg(0)
+        .arg(stop)
+        .arg("REV")
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
-    // Convert Vec<(EraIndex, u32)> to Vec<u32> to easily make the calculation
-    let scores: Vec<f64> = v.into_iter().map(|(_, score)| score).collect();
-    let min_max = stats::confidence_interval_95(&scores);
-    Ok(min_max)
+
+    let sessions = members
+        .iter()
+        .filter_map(|member| match sync::parse_session_points(member) {
+            Some((session_index, era_index, points)) => Some(ValidatorPointsAtSession {
+                session_index,
+                era_index,
+                points,
+            }),
+            None => {
+                warn!("Skipping malformed session points member {}", member);
+                None
+            }
+        })
+        .collect();
+
+    respond_json(ValidatorSessionsResponse {
+        stash: stash.to_string(),
+        sessions,
+    })
 }
 
-async fn calculate_min_max_interval(
-    cache: Data<RedisPool>,
-    name: &str,
-) -> Result<(f64, f64), ApiError> {
-    let max = calculate_max_limit(cache.clone(), name).await?;
-    let min = calculate_min_limit(cache.clone(), name).await?;
-    Ok((min, max))
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+enum Queries {
+    All = 1,
+    Active = 2,
+    Board = 3,
+    Other = 4,
 }
 
-async fn calculate_min_limit(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
-    let mut conn = get_conn(&cache).await?;
-    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(0, name.to_string()))
-        .arg("-inf")
-        .arg("+inf")
-        .arg("BYSCORE")
-        .arg("LIMIT")
-        .arg("0")
-        .arg("1")
-        .arg("WITHSCORES")
-        .query_async(&mut conn as &mut Connection)
-        .await
-        .map_err(CacheError::RedisCMDError)?;
-    if v.len() == 0 {
-        return Ok(0.0);
+impl std::fmt::Display for Queries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Active => write!(f, "active"),
+            Self::Board => write!(f, "board"),
+            Self::Other => write!(f, "other"),
+        }
     }
-    Ok(v[0].1)
 }
 
-async fn calculate_max_limit(cache: Data<RedisPool>, name: &str) -> Result<f64, ApiError> {
-    let mut conn = get_conn(&cache).await?;
-    let v: Vec<(String, f64)> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(0, name.to_string()))
-        .arg("+inf")
-        .arg("-inf")
-        .arg("BYSCORE")
-        .arg("REV")
-        .arg("LIMIT")
-        .arg("0")
-        .arg("1")
-        .arg("WITHSCORES")
-        .query_async(&mut conn as &mut Connection)
-        .await
-        .map_err(CacheError::RedisCMDError)?;
-    if v.len() == 0 {
-        return Ok(0.0);
+/// Criterion name of each weights position
+const CRITERIA: [&str; WEIGHTS_CAPACITY] = [
+    "inclusion_rate",
+    "commission",
+    "nominators",
+    "avg_reward_points",
+    "reward_staked",
+    "active",
+    "own_stake",
+    "total_stake",
+    "judgements",
+    "sub_accounts",
+    "slashes",
+    "commission_volatility",
+    "self_stake_ratio",
+];
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Params {
+    #[serde(default = "default_queries")]
+    q: Queries,
+    #[serde(default = "default_weights")]
+    #[serde(deserialize_with = "parse_weights")]
+    w: Weights,
+    #[serde(default = "default_intervals")]
+    #[serde(deserialize_with = "parse_intervals")]
+    i: Intervals,
+    #[serde(default)]
+    n: Quantity,
+    #[serde(default)]
+    offset: Quantity,
+    #[serde(default)]
+    labeled: bool,
+    #[serde(default)]
+    norm: Norm,
+    #[serde(default)]
+    formula: Formula,
+    #[serde(default)]
+    era: Option<EraIndex>,
+    #[serde(default)]
+    exclude_blocked: bool,
+    #[serde(default)]
+    min_score: Option<f64>,
+    // Id of a stored preset, replaces the weights and intervals given
+    #[serde(default)]
+    preset: Option<String>,
+    #[serde(default)]
+    smooth: bool,
+}
+
+/// Replace the weights and intervals of the params by the ones of the preset requested
+async fn expand_preset(params: &mut Params, cache: Data<RedisPool>) -> Result<(), ApiError> {
+    if let Some(id) = &params.preset {
+        let preset = get_preset(id, cache).await?;
+        let (weights, intervals) = parse_preset(&preset.weights, &preset.intervals)?;
+        params.w = weights;
+        params.i = intervals;
     }
-    Ok(v[0].1)
+    Ok(())
 }
 
-async fn cache_board_limits(
-    era_index: EraIndex,
-    board_name: String,
-    cache: Data<RedisPool>,
-) -> Result<BoardLimits, ApiError> {
-    let mut conn = get_conn(&cache).await?;
+fn default_queries() -> Queries {
+    Queries::Other
+}
 
-    let mut limits: BoardLimitsCache = BTreeMap::new();
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaResponse {
+    pub limits: String,
+    pub total: u32,
+    pub stale: bool,
+}
 
-    // let max_avg_reward_points =
-    //     calculate_avg_points(cache.clone(), sync::BOARD_MAX_POINTS_ERAS).await?;
-    // limits.insert("max_avg_reward_points".to_string(), max_avg_reward_points);
-    // let min_avg_reward_points =
-    //     calculate_avg_points(cache.clone(), sync::BOARD_MIN_POINTS_ERAS).await?;
-    // limits.insert("min_avg_reward_points".to_string(), min_avg_reward_points);
+impl Default for MetaResponse {
+    fn default() -> MetaResponse {
+        MetaResponse {
+            limits: String::default(),
+            total: 0,
+            stale: false,
+        }
+    }
+}
 
-    let avg_reward_points_interval =
-        calculate_min_max_interval(cache.clone(), sync::BOARD_AVG_POINTS_ERAS).await?;
-    limits.insert(
-        "min_avg_reward_points".to_string(),
-        avg_reward_points_interval.0,
-    );
-    limits.insert(
-        "max_avg_reward_points".to_string(),
-        avg_reward_points_interval.1,
-    );
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValidatorsResponse {
+    pub addresses: Vec<String>,
+    pub meta: MetaResponse,
+}
 
-    let own_stake_interval =
-        calculate_min_max_interval(cache.clone(), sync::BOARD_OWN_STAKE_VALIDATORS).await?;
-    // let own_stake_interval = calculate_confidence_interval_95(cache.clone(), sync::BOARD_OWN_STAKE_VALIDATORS).await?;
-    limits.insert("min_own_stake".to_string(), own_stake_interval.0);
-    limits.insert("max_own_stake".to_string(), own_stake_interval.1);
-
-    let total_stake_interval =
-        calculate_min_max_interval(cache.clone(), sync::BOARD_TOTAL_STAKE_VALIDATORS).await?;
-    // let total_stake_interval = calculate_confidence_interval_95(cache.clone(), sync::BOARD_TOTAL_STAKE_VALIDATORS).await?;
-    limits.insert("min_total_stake".to_string(), total_stake_interval.0);
-    limits.insert("max_total_stake".to_string(), total_stake_interval.1);
-
-    let judgements_interval =
-        calculate_min_max_interval(cache.clone(), sync::BOARD_JUDGEMENTS_VALIDATORS).await?;
-    // let judgements_interval = calculate_confidence_interval_95(cache.clone(), sync::BOARD_JUDGEMENTS_VALIDATORS).await?;
-    limits.insert("min_judgements".to_string(), judgements_interval.0);
-    limits.insert("max_judgements".to_string(), judgements_interval.1);
-
-    let sub_accounts_interval =
-        calculate_min_max_interval(cache.clone(), sync::BOARD_SUB_ACCOUNTS_VALIDATORS).await?;
-    // let sub_accounts_interval = calculate_confidence_interval_95(cache.clone(), sync::BOARD_SUB_ACCOUNTS_VALIDATORS).await?;
-    limits.insert("min_sub_accounts".to_string(), sub_accounts_interval.0);
-    limits.insert("max_sub_accounts".to_string(), sub_accounts_interval.1);
-
-    let key_limits = sync::Key::BoardAtEra(era_index, format!("{}:limits", board_name));
-    // Cache board limits
-    let _: () = redis::cmd("HSET")
-        .arg(key_limits.to_string())
-        .arg(limits.clone())
+/// Active era used to tag the responses that only change once per era.
+/// Note: no era is returned while syncing so that partial data is not tagged
+pub async fn get_etag_era(cache: Data<RedisPool>) -> Result<Option<EraIndex>, ApiError> {
+    if is_syncing(cache.clone()).await? {
+        return Ok(None);
+    }
+    let mut conn = get_conn(&cache).await?;
+    let era_index: Option<EraIndex> = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    Ok(limits.into())
+    Ok(era_index)
 }
-async fn is_syncing(cache: Data<RedisPool>) -> Result<bool, ApiError> {
+
+/// Increase board stats counter
+async fn increase_board_stats(key: sync::Key, cache: Data<RedisPool>) -> Result<(), ApiError> {
     let mut conn = get_conn(&cache).await?;
 
-    let res: Option<String> = redis::cmd("HGET")
-        .arg(sync::Key::Info)
-        .arg("syncing")
+    let _: () = redis::cmd("HINCRBY")
+        .arg(sync::Key::Stats)
+        .arg(key)
+        .arg(1)
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
-    let syncing = match res {
-        Some(v) => v.parse::<bool>().unwrap_or_default(),
-        None => false,
-    };
 
-    Ok(syncing)
+    Ok(())
 }
 
-fn calculate_scores(
-    validator: &Validator,
-    limits: &BoardLimits,
-    weights: &Weights,
-) -> Result<Vec<f64>, ApiError> {
-    let mut scores: Vec<f64> = Vec::with_capacity(WEIGHTS_CAPACITY);
-
-    scores.push(
-        normalize_value(
-            validator.inclusion_rate as f64,
-            limits.inclusion_rate.min,
-            limits.inclusion_rate.max,
-        ) * weights[0] as f64,
-    );
-    scores.push(
-        reverse_normalize_commission(
-            validator.commission,
-            limits.commission.min,
-            limits.commission.max,
-        ) * weights[1] as f64,
-    );
-    scores.push(
-        reverse_normalize_value(
-            validator.nominators as f64,
-            limits.nominators.min,
-            limits.nominators.max,
-        ) * weights[2] as f64,
-    );
-    scores.push(
-        normalize_value(
-            validator.avg_reward_points,
-            limits.avg_reward_points.min,
-            limits.avg_reward_points.max,
-        ) * weights[3] as f64,
-    );
-    scores.push(normalize_flag(validator.reward_staked) * weights[4] as f64);
-    scores.push(normalize_flag(validator.active) * weights[5] as f64);
-    scores.push(
-        normalize_value(
-            validator.own_stake as f64,
-            limits.own_stake.min,
-            limits.own_stake.max,
-        ) * weights[6] as f64,
-    );
-    scores.push(
-        reverse_normalize_value(
-            (validator.own_stake + validator.nominators_stake) as f64,
-            limits.total_stake.min,
-            limits.total_stake.max,
-        ) * weights[7] as f64,
-    );
-    scores.push(
-        normalize_value(
-            validator.judgements as f64,
-            limits.judgements.min,
-            limits.judgements.max,
-        ) * weights[8] as f64,
-    );
-    scores.push(
-        reverse_normalize_value(
-            validator.sub_accounts as f64,
-            limits.sub_accounts.min,
-            limits.sub_accounts.max,
-        ) * weights[9] as f64,
-    );
+/// Page of the board stashes without the excluded stashes
+fn page_without_excluded(
+    stashes: Vec<String>,
+    offset: Quantity,
+    n: Quantity,
+    is_excluded: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    stashes
+        .into_iter()
+        .filter(|stash| !is_excluded(stash))
+        .skip(offset as usize)
+        .take(n as usize)
+        .collect()
+}
 
-    Ok(scores)
+/// Get a page of the board stashes without the excluded stashes.
+/// Note: enough stashes are read from the start of the board for the excluded stashes
+/// not to shift the pages
+async fn get_unexcluded_validators_stashes(
+    key: sync::Key,
+    offset: Quantity,
+    n: Quantity,
+    cache: Data<RedisPool>,
+) -> Result<Vec<String>, ApiError> {
+    if CONFIG.excluded_stashes.is_empty() {
+        return get_validators_stashes(key, offset, n, None, cache).await;
+    }
+    let count = offset
+        .saturating_add(n)
+        .saturating_add(CONFIG.excluded_stashes.len() as Quantity);
+    let stashes = get_validators_stashes(key, 0, count, None, cache).await?;
+    Ok(page_without_excluded(stashes, offset, n, |stash| {
+        CONFIG.is_stash_excluded(stash)
+    }))
 }
 
-async fn generate_board_scores(
-    era_index: EraIndex,
-    weights: &Weights,
+/// Get the number of members in a board without the excluded stashes
+async fn get_unexcluded_board_total(
+    key: sync::Key,
     cache: Data<RedisPool>,
-) -> Result<(), ApiError> {
+) -> Result<u32, ApiError> {
+    let total = get_board_total(key.clone(), cache.clone()).await?;
+    if CONFIG.excluded_stashes.is_empty() {
+        return Ok(total);
+    }
     let mut conn = get_conn(&cache).await?;
+    let mut pipe = redis::pipe();
+    for stash in CONFIG.excluded_stashes.iter() {
+        pipe.cmd("ZSCORE").arg(key.clone()).arg(stash.trim());
+    }
+    let scores: Vec<Option<f64>> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let excluded = scores.into_iter().flatten().count() as u32;
 
-    let board_name = get_board_name(weights, None);
-    let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+    Ok(total.saturating_sub(excluded))
+}
 
-    let exists: bool = redis::cmd("EXISTS")
-        .arg(key.clone())
+/// Get the number of members in a board
+async fn get_board_total(key: sync::Key, cache: Data<RedisPool>) -> Result<u32, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+
+    let total: u32 = redis::cmd("ZCARD")
+        .arg(key)
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    // If board is already cached do nothing
-    if exists {
-        return Ok(());
-    }
+    Ok(total)
+}
 
-    // Only generate board if cache is not syncing
-    if !exists && is_syncing(cache.clone()).await? {
-        let msg = format!(
-            "The system is currently syncing. Usually doesn't take long 5 - 10min. Please just wait a few minutes before you try again. Thank you.");
-        warn!("{}", msg);
-        return Err(ApiError::NotFound(msg));
+/// Get the number of members in a board with a score of at least min_score
+async fn get_board_total_by_score(
+    key: sync::Key,
+    min_score: Option<f64>,
+    cache: Data<RedisPool>,
+) -> Result<u32, ApiError> {
+    if min_score.is_none() {
+        return get_board_total(key, cache).await;
     }
+    let min = min_score_bound(min_score)?;
+    let mut conn = get_conn(&cache).await?;
 
-    // Cache board limits based on all validators
-    let limits: BoardLimits = cache_board_limits(era_index, board_name.clone(), cache).await?;
-
-    let stashes: Vec<String> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(
-            era_index,
-            sync::BOARD_ALL_VALIDATORS.to_string(),
-        ))
-        .arg("-inf")
+    let total: u32 = redis::cmd("ZCOUNT")
+        .arg(key)
+        .arg(min)
         .arg("+inf")
-        .arg("BYSCORE")
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    for stash in stashes {
-        let stash = AccountId32::from_str(&*stash.to_string())?;
-        let data: ValidatorCache = redis::cmd("HGETALL")
-            .arg(sync::Key::Validator(stash.clone()))
-            .query_async(&mut conn as &mut Connection)
-            .await
-            .map_err(CacheError::RedisCMDError)?;
-
-        let validator: Validator = data.into();
-        // If the validator does not accept nominations
-        // score is not given
-        if validator.blocked {
-            continue;
-        }
-
-        // Calculate scores
-        let scores = calculate_scores(&validator, &limits, weights)?;
-        let score = scores.iter().fold(0.0, |acc, x| acc + x);
-
-        // Cache total score
-        let _: () = redis::cmd("ZADD")
-            .arg(key.to_string())
-            .arg(score) // score
-            .arg(stash.to_string()) // member
-            .query_async(&mut conn as &mut Connection)
-            .await
-            .map_err(CacheError::RedisCMDError)?;
-
-        let scores_str: String = scores
-            .iter()
-            .enumerate()
-            .map(|(i, x)| {
-                if i == 0 {
-                    return x.to_string();
-                }
-                format!(",{}", x)
-            })
-            .collect();
+    Ok(total)
+}
 
-        // Cache partial scores
-        let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name.clone()));
-        let _: () = redis::cmd("HSET")
-            .arg(key_scores.to_string())
-            .arg(stash.to_string())
-            .arg(scores_str.to_string())
-            .query_async(&mut conn as &mut Connection)
-            .await
-            .map_err(CacheError::RedisCMDError)?;
+/// Name of the board of all or active validators, without the validators that don't
+/// accept nominations if requested
+fn unscored_board_name(board: &str, unblocked_board: &str, exclude_blocked: bool) -> String {
+    if exclude_blocked {
+        return unblocked_board.to_string();
     }
+    board.to_string()
+}
 
-    Ok(())
+/// Get active validators
+async fn get_active_validators(
+    era_index: EraIndex,
+    offset: Quantity,
+    n: Quantity,
+    exclude_blocked: bool,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsResponse>, ApiError> {
+    let key = sync::Key::BoardAtEra(
+        era_index,
+        unscored_board_name(
+            sync::BOARD_ACTIVE_VALIDATORS,
+            sync::BOARD_ACTIVE_UNBLOCKED_VALIDATORS,
+            exclude_blocked,
+        ),
+    );
+    respond_json(ValidatorsResponse {
+        addresses: get_unexcluded_validators_stashes(key.clone(), offset, n, cache.clone()).await?,
+        meta: MetaResponse {
+            total: get_unexcluded_board_total(key, cache).await?,
+            ..Default::default()
+        },
+    })
 }
 
-async fn generate_board_filtered_by_intervals(
+/// Get all validators
+async fn get_all_validators(
     era_index: EraIndex,
-    weights: &Weights,
-    intervals: &Intervals,
+    offset: Quantity,
+    n: Quantity,
+    exclude_blocked: bool,
     cache: Data<RedisPool>,
-) -> Result<(), ApiError> {
-    let mut conn = get_conn(&cache).await?;
+) -> Result<Json<ValidatorsResponse>, ApiError> {
+    let key = sync::Key::BoardAtEra(
+        era_index,
+        unscored_board_name(
+            sync::BOARD_ALL_VALIDATORS,
+            sync::BOARD_ALL_UNBLOCKED_VALIDATORS,
+            exclude_blocked,
+        ),
+    );
+    respond_json(ValidatorsResponse {
+        addresses: get_unexcluded_validators_stashes(key.clone(), offset, n, cache.clone()).await?,
+        meta: MetaResponse {
+            total: get_unexcluded_board_total(key, cache).await?,
+            ..Default::default()
+        },
+    })
+}
 
-    let board_name = get_board_name(weights, Some(intervals));
-    let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+/// Era of the board requested, either a past era synced or the active era by default.
+/// Returns the era and if it is a past era
+async fn get_board_era(
+    active_era_index: EraIndex,
+    params: &Params,
+    cache: Data<RedisPool>,
+) -> Result<(EraIndex, bool), ApiError> {
+    let era_index = match params.era {
+        Some(era_index) if era_index < active_era_index => era_index,
+        Some(era_index) if era_index > active_era_index => {
+            let msg = format!(
+                "Parameter era={} must not be greater than the active era {}",
+                era_index, active_era_index
+            );
+            warn!("{}", msg);
+            return Err(ApiError::BadRequest(msg));
+        }
+        _ => return Ok((active_era_index, false)),
+    };
 
+    let mut conn = get_conn(&cache).await?;
     let exists: bool = redis::cmd("EXISTS")
-        .arg(key.clone())
+        .arg(sync::Key::BoardAtEra(
+            era_index,
+            sync::BOARD_POINTS_VALIDATORS.to_string(),
+        ))
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
-
-    // If board is already cached do nothing
-    // if exists {
-    //     return Ok(());
-    // }
-
-    // Only generate board if cache is not syncing
-    if !exists && is_syncing(cache.clone()).await? {
+    if !exists {
         let msg = format!(
-            "The system is currently syncing. Usually doesn't take long 5 - 10min. Please just wait a few minutes before you try again. Thank you.");
+            "Era {} is not synced. Only eras within the synced history depth are available.",
+            era_index
+        );
         warn!("{}", msg);
         return Err(ApiError::NotFound(msg));
     }
 
-    let limits: BoardLimits = intervals.into();
+    Ok((era_index, true))
+}
 
-    let stashes: Vec<String> = redis::cmd("ZRANGE")
-        .arg(sync::Key::BoardAtEra(
-            era_index,
-            sync::BOARD_ALL_VALIDATORS.to_string(),
-        ))
-        .arg("-inf")
-        .arg("+inf")
-        .arg("BYSCORE")
+/// Key of the board defined by the params
+fn get_board_key(era_index: EraIndex, historical: bool, params: &Params) -> sync::Key {
+    sync::Key::BoardAtEra(
+        era_index,
+        historical_board_name(
+            smoothed_board_name(
+                formula_board_name(
+                    get_board_name(&params.w, Some(&params.i), params.norm),
+                    params.formula,
+                ),
+                params.smooth,
+            ),
+            historical,
+        ),
+    )
+}
+
+/// Most recent past era, within BOARD_STALE_FALLBACK_ERAS, with the board defined by the
+/// params already cached. Only searched while syncing and the board at the active era
+/// is not cached yet, so that a slightly stale board is served instead of an error
+async fn get_stale_board_era(
+    era_index: EraIndex,
+    params: &Params,
+    cache: Data<RedisPool>,
+) -> Result<Option<EraIndex>, ApiError> {
+    if CONFIG.board_stale_fallback_eras == 0 {
+        return Ok(None);
+    }
+
+    let mut conn = get_conn(&cache).await?;
+    let exists: bool = redis::cmd("EXISTS")
+        .arg(get_board_key(era_index, false, params))
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
+    if exists || !is_syncing(cache.clone()).await? {
+        return Ok(None);
+    }
 
-    for stash in stashes {
-        let stash = AccountId32::from_str(&*stash.to_string())?;
-        let data: ValidatorCache = redis::cmd("HGETALL")
-            .arg(sync::Key::Validator(stash.clone()))
+    for depth in 1..=CONFIG.board_stale_fallback_eras {
+        let stale_era_index = match era_index.checked_sub(depth) {
+            Some(i) => i,
+            None => break,
+        };
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(get_board_key(stale_era_index, false, params))
             .query_async(&mut conn as &mut Connection)
             .await
             .map_err(CacheError::RedisCMDError)?;
-
-        let validator: Validator = data.into();
-        // If the validator does not accept nominations
-        // score is not given
-        if validator.blocked {
-            continue;
+        if exists {
+            return Ok(Some(stale_era_index));
         }
+    }
 
-        // Verify if validator traits are within the respective interval defined by user
-        // Position 0 - Higher Inclusion rate is preferrable
-        // Position 1 - Lower Commission is preferrable
-        // Position 2 - Lower Nominators is preferrable (limit to 256 -> oversubscribed)
-        // Position 3 - Higher Reward Points is preferrable
-        // Position 4 - If reward is staked is preferrable
-        // Position 5 - If in active set is preferrable
-        // Position 6 - Higher own stake is preferrable
-        // Position 7 - Lower total stake is preferrable
-        // Position 8 - Higher number of Reasonable or KnownGood judgements is preferrable
-        // Position 9 - Lower number of sub-accounts is preferrable
-
-        if (validator.inclusion_rate as f64) < limits.inclusion_rate.min
-            || (validator.inclusion_rate as f64) > limits.inclusion_rate.max
-        {
-            continue;
-        }
-        if (validator.commission as f64) < limits.commission.min
-            || (validator.commission as f64) > limits.commission.max
-        {
-            continue;
-        }
-        if (validator.nominators as f64) < limits.nominators.min
-            || ((validator.nominators as f64) > limits.nominators.max
-                && limits.nominators.max < NOMINATORS_OVERSUBSCRIBED_THRESHOLD as f64)
-        {
-            continue;
-        }
-        if validator.avg_reward_points < limits.avg_reward_points.min
-            || validator.avg_reward_points > limits.avg_reward_points.max
-        {
-            continue;
-        }
-        if normalize_flag(validator.reward_staked) != limits.reward_staked.min
-            && limits.reward_staked.min == limits.reward_staked.max
-        {
-            continue;
-        }
-        if normalize_flag(validator.active) != limits.active.min
-            && limits.active.min == limits.active.max
-        {
-            continue;
-        }
-        if validator.own_stake < limits.own_stake.min as u128
-            || validator.own_stake > limits.own_stake.max as u128
-        {
-            continue;
-        }
-        if (validator.own_stake + validator.nominators_stake) < limits.total_stake.min as u128
-            || (validator.own_stake + validator.nominators_stake) > limits.total_stake.max as u128
-        {
-            continue;
-        }
-        if (validator.judgements as f64) < limits.judgements.min
-            || (validator.judgements as f64) > limits.judgements.max
-        {
-            continue;
-        }
-        if (validator.sub_accounts as f64) < limits.sub_accounts.min
-            || (validator.sub_accounts as f64) > limits.sub_accounts.max
-        {
-            continue;
-        }
+    Ok(None)
+}
 
-        // Calculate scores
-        let scores = calculate_scores(&validator, &limits, weights)?;
-        let score = scores.iter().fold(0.0, |acc, x| acc + x);
+/// Generate the board defined by the params and return its key
+async fn generate_board(
+    era_index: EraIndex,
+    historical: bool,
+    params: &Params,
+    cache: Data<RedisPool>,
+) -> Result<sync::Key, ApiError> {
+    let key = get_board_key(era_index, historical, params);
 
-        // Cache total score
-        let _: () = redis::cmd("ZADD")
-            .arg(key.to_string())
-            .arg(score) // score
-            .arg(stash.to_string()) // member
-            .query_async(&mut conn as &mut Connection)
-            .await
-            .map_err(CacheError::RedisCMDError)?;
+    // Generate leaderboard scores and cache it
+    generate_board_scores(
+        era_index,
+        &params.w,
+        params.norm,
+        params.formula,
+        params.smooth,
+        historical,
+        cache.clone(),
+    )
+    .await?;
 
-        let scores_str: String = scores
-            .iter()
-            .enumerate()
-            .map(|(i, x)| {
-                if i == 0 {
-                    return x.to_string();
-                }
-                format!(",{}", x)
-            })
-            .collect();
+    // Generate filtered leaderboard and cache it
+    generate_board_filtered_by_intervals(
+        era_index,
+        &params.w,
+        &params.i,
+        params.norm,
+        params.formula,
+        params.smooth,
+        historical,
+        cache.clone(),
+    )
+    .await?;
 
-        // Cache partial scores
-        let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name.clone()));
-        let _: () = redis::cmd("HSET")
-            .arg(key_scores.to_string())
-            .arg(stash.to_string())
-            .arg(scores_str.to_string())
-            .query_async(&mut conn as &mut Connection)
-            .await
-            .map_err(CacheError::RedisCMDError)?;
+    // Increase board stats counter, boards at a past era are only used for backtesting
+    if !historical {
+        increase_board_stats(key.clone(), cache.clone()).await?;
     }
 
-    Ok(())
+    Ok(key)
 }
 
-/// Increase board stats counter
-async fn increase_board_stats(key: sync::Key, cache: Data<RedisPool>) -> Result<(), ApiError> {
-    let mut conn = get_conn(&cache).await?;
+/// Get board validators
+async fn get_board_validators(
+    era_index: EraIndex,
+    params: Query<Params>,
+    cache: Data<RedisPool>,
+) -> Result<Json<ValidatorsResponse>, ApiError> {
+    let (era_index, historical) = get_board_era(era_index, &params, cache.clone()).await?;
 
-    let _: () = redis::cmd("HINCRBY")
-        .arg(sync::Key::Stats)
-        .arg(key)
-        .arg(1)
+    // While syncing, serve the board of a past era if the active one is not available yet
+    let stale_era_index = if historical {
+        None
+    } else {
+        get_stale_board_era(era_index, &params, cache.clone()).await?
+    };
+    let (era_index, key) = match stale_era_index {
+        Some(stale_era_index) => {
+            warn!(
+                "Board {} served from era {} while syncing",
+                get_board_name(&params.w, Some(&params.i), params.norm),
+                stale_era_index
+            );
+            (
+                stale_era_index,
+                get_board_key(stale_era_index, false, &params),
+            )
+        }
+        None => (
+            era_index,
+            generate_board(era_index, historical, &params, cache.clone()).await?,
+        ),
+    };
+
+    let limits: BoardLimits = get_board_limits(
+        era_index,
+        &limits_board_name(&params.w, params.norm, params.formula, historical),
+        cache.clone(),
+    )
+    .await?;
+
+    respond_json(ValidatorsResponse {
+        addresses: get_validators_stashes(
+            key.clone(),
+            params.offset,
+            params.n,
+            params.min_score,
+            cache.clone(),
+        )
+        .await?,
+        meta: MetaResponse {
+            limits: limits.to_string(),
+            total: get_board_total_by_score(key.clone(), params.min_score, cache.clone()).await?,
+            stale: stale_era_index.is_some(),
+        },
+    })
+}
+
+/// CSV header of a board export, followed by the criterion of each score component
+fn board_csv_header() -> String {
+    let mut columns = vec!["stash", "name", "score", "rank"];
+    columns.extend_from_slice(&CRITERIA);
+    format!("{}\n", columns.join(","))
+}
+
+/// CSV row of a board export, the score components are left empty if not available
+fn board_csv_row(stash: &str, name: &str, score: f64, rank: u32, scores: Option<String>) -> String {
+    let scores = scores.unwrap_or_else(|| vec![""; WEIGHTS_CAPACITY].join(","));
+    format!(
+        "{},{},{},{},{}\n",
+        csv_field(stash),
+        csv_field(name),
+        score,
+        rank,
+        scores
+    )
+}
+
+/// Get board validators as CSV, with the individual score components of each validator
+async fn get_board_validators_csv(
+    era_index: EraIndex,
+    params: Query<Params>,
+    cache: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (era_index, historical) = get_board_era(era_index, &params, cache.clone()).await?;
+    let key = generate_board(era_index, historical, &params, cache.clone()).await?;
+    let board_name = historical_board_name(
+        smoothed_board_name(
+            formula_board_name(
+                get_board_name(&params.w, Some(&params.i), params.norm),
+                params.formula,
+            ),
+            params.smooth,
+        ),
+        historical,
+    );
+    let key_scores = sync::Key::BoardAtEra(era_index, format!("{}:scores", board_name));
+
+    let stashes = get_validators_stashes(
+        key.clone(),
+        params.offset,
+        params.n,
+        params.min_score,
+        cache.clone(),
+    )
+    .await?;
+
+    let mut conn = get_conn(&cache).await?;
+    let mut pipe = redis::pipe();
+    for stash in stashes.iter() {
+        let account = AccountId32::from_str(stash)?;
+        pipe.cmd("HGET")
+            .arg(sync::Key::Validator(account))
+            .arg("name");
+        pipe.cmd("ZSCORE").arg(key.clone()).arg(stash);
+        pipe.cmd("HGET").arg(key_scores.clone()).arg(stash);
+    }
+    let data: Vec<(Option<String>, Option<f64>, Option<String>)> = pipe
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    Ok(())
+    let offset = params.offset;
+    let rows = stashes.into_iter().zip(data.into_iter()).enumerate().map(
+        move |(i, (stash, (name, score, scores)))| {
+            let rank = offset + i as u32 + 1;
+            board_csv_row(
+                &stash,
+                &name.unwrap_or_default(),
+                score.unwrap_or_default(),
+                rank,
+                scores,
+            )
+        },
+    );
+    let body: String = std::iter::once(board_csv_header()).chain(rows).collect();
+
+    Ok(HttpResponse::Ok().content_type("text/csv").body(body))
 }
 
-async fn get_board_limits(
+/// Scores of the stashes in the board defined by the default weights, none for the
+/// stashes not scored. The board is generated if not cached yet.
+/// Note: boards are not generated while syncing, so the default board of the previous
+/// era is used until the one of the era is available, since default boards never expire
+pub async fn get_default_board_scores(
     era_index: EraIndex,
-    weights: &Weights,
+    stashes: &Vec<AccountId32>,
     cache: Data<RedisPool>,
-) -> Result<BoardLimits, ApiError> {
+) -> Result<Vec<Option<f64>>, ApiError> {
+    let weights = default_board_weights();
     let mut conn = get_conn(&cache).await?;
-
-    // Check if limits key is already available
-    let key = sync::Key::BoardAtEra(
+    let board_name = get_board_name(&weights, None, Norm::MinMax);
+    let era_index = if is_syncing(cache.clone()).await? {
+        let (expected, members) =
+            get_board_members_count(era_index, &board_name, cache.clone()).await?;
+        if is_board_complete(expected, members) {
+            era_index
+        } else {
+            era_index.saturating_sub(1)
+        }
+    } else {
+        era_index
+    };
+    generate_board_scores(
         era_index,
-        format!("{}:limits", get_board_name(weights, None)),
-    );
-    if let redis::Value::Int(0) = redis::cmd("EXISTS")
-        .arg(key.clone())
+        &weights,
+        Norm::MinMax,
+        Formula::Sum,
+        false,
+        false,
+        cache.clone(),
+    )
+    .await?;
+
+    let key = sync::Key::BoardAtEra(era_index, board_name);
+    let mut pipe = redis::pipe();
+    for stash in stashes.iter() {
+        pipe.cmd("ZSCORE").arg(key.clone()).arg(stash.to_string());
+    }
+    let scores: Vec<Option<f64>> = pipe
         .query_async(&mut conn as &mut Connection)
         .await
-        .map_err(CacheError::RedisCMDError)?
-    {
-        let msg = format!(
-            "Limits not yet available for Leaderboard {:?}. Wait a second and try again.",
-            weights
-        );
-        error!("{}", msg);
-        return Err(ApiError::NotFound(msg));
-    }
-    // Get limits
-    let limits: BoardLimitsCache = redis::cmd("HGETALL")
-        .arg(key)
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(scores)
+}
+
+/// Get the limits of the board defined by the weights, without generating the board.
+/// Limits already cached for the board are reused, otherwise they are calculated and cached
+pub async fn get_limits(
+    mut params: Query<Params>,
+    cache: Data<RedisPool>,
+) -> Result<Json<BoardLimits>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let board_name = limits_board_name(&params.w, params.norm, params.formula, false);
+    let exists: bool = redis::cmd("EXISTS")
+        .arg(board_limits_key(era_index, &board_name))
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
+    if exists {
+        return respond_json(get_board_limits(era_index, &board_name, cache).await?);
+    }
 
-    if limits.len() == 0 {
-        let msg = format!("Limits not available for Leaderboard {:?}", weights);
-        error!("{}", msg);
-        return Err(ApiError::InternalServerError(msg));
+    // Only calculate limits if cache is not syncing
+    if is_syncing(cache.clone()).await? {
+        let msg = format!(
+            "The system is currently syncing. Usually doesn't take long 5 - 10min. Please just wait a few minutes before you try again. Thank you.");
+        warn!("{}", msg);
+        return Err(ApiError::ServiceUnavailable(msg));
     }
 
-    Ok(limits.into())
+    let era_limits = get_era_limits(era_index, params.norm, cache.clone()).await?;
+    let limits = cache_board_limits(era_index, &board_name, era_limits, cache.clone()).await?;
+    expire_board(era_index, &board_name, cache.clone()).await?;
+
+    respond_json(limits)
 }
 
-async fn get_validators_stashes(
-    key: sync::Key,
-    n: Quantity,
+/// Default and maximum number of validators sampled by the board debug endpoint
+const BOARD_DEBUG_DEFAULT_SAMPLE: Quantity = 10;
+const BOARD_DEBUG_MAX_SAMPLE: Quantity = 100;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BoardDebugScore {
+    pub stash: String,
+    pub score: f64,
+    pub scores: Scores,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BoardDebugResponse {
+    pub era: EraIndex,
+    pub board: String,
+    /// Limits used to normalize the partial scores, none if not cached yet
+    pub limits: Option<BoardLimits>,
+    pub total: u32,
+    /// Number of members expected by the board completion marker
+    pub expected: Option<u32>,
+    /// Validators at the top of the board with their partial scores
+    pub sample: Vec<BoardDebugScore>,
+}
+
+/// Diagnostic only, guarded by an API key. Inspect what is cached for the board
+/// defined by the weights in the active era, e.g. to tell why every validator scores
+/// near zero, usually a limit max blown out by an outlier. Nothing is generated
+pub async fn get_board_debug(
+    _auth: Authorized,
+    mut params: Query<Params>,
     cache: Data<RedisPool>,
-) -> Result<Vec<String>, ApiError> {
+) -> Result<Json<BoardDebugResponse>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
     let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
 
-    let stashes: Vec<String> = redis::cmd("ZRANGE")
+    let board_name = smoothed_board_name(
+        formula_board_name(get_board_name(&params.w, None, params.norm), params.formula),
+        params.smooth,
+    );
+    let key = sync::Key::BoardAtEra(era_index, board_name.clone());
+    let unsmoothed_board_name = limits_board_name(&params.w, params.norm, params.formula, false);
+    let key_limits = board_limits_key(era_index, &unsmoothed_board_name);
+    let key_meta = sync::Key::BoardAtEra(era_index, format!("{}:meta", board_name));
+
+    let (limits_cached, total, expected): (bool, u32, Option<u32>) = redis::pipe()
+        .cmd("EXISTS")
+        .arg(key_limits)
+        .cmd("ZCARD")
+        .arg(key.clone())
+        .cmd("HGET")
+        .arg(key_meta)
+        .arg("members")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    let limits = if limits_cached {
+        Some(get_board_limits(era_index, &unsmoothed_board_name, cache.clone()).await?)
+    } else {
+        None
+    };
+
+    let n = match params.n {
+        0 => BOARD_DEBUG_DEFAULT_SAMPLE,
+        n => n.min(BOARD_DEBUG_MAX_SAMPLE),
+    };
+    let top: Vec<(String, f64)> = redis::cmd("ZRANGE")
         .arg(key)
-        .arg("+inf")
-        .arg("0")
-        .arg("BYSCORE")
+        .arg(0)
+        .arg(n - 1)
         .arg("REV")
-        .arg("LIMIT")
-        .arg("0")
-        .arg(n)
+        .arg("WITHSCORES")
         .query_async(&mut conn as &mut Connection)
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    Ok(stashes)
-}
+    let mut sample: Vec<BoardDebugScore> = Vec::with_capacity(top.len());
+    if !top.is_empty() {
+        let scores: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(sync::Key::BoardAtEra(
+                era_index,
+                format!("{}:scores", board_name),
+            ))
+            .arg(top.iter().map(|(stash, _)| stash).collect::<Vec<&String>>())
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        for ((stash, score), scores) in top.into_iter().zip(scores.into_iter()) {
+            let values: Vec<f64> = scores
+                .unwrap_or_default()
+                .split(",")
+                .filter_map(|x| x.parse::<f64>().ok())
+                .collect();
+            sample.push(BoardDebugScore {
+                stash,
+                score,
+                scores: Scores::new(values, &params.w, true),
+            });
+        }
+    }
 
-/// Get active validators
-async fn get_active_validators(
-    era_index: EraIndex,
-    n: Quantity,
-    cache: Data<RedisPool>,
-) -> Result<Json<ValidatorsResponse>, ApiError> {
-    let key = sync::Key::BoardAtEra(era_index, sync::BOARD_ACTIVE_VALIDATORS.to_string());
-    respond_json(ValidatorsResponse {
-        addresses: get_validators_stashes(key, n, cache).await?,
-        meta: MetaResponse::default(),
+    respond_json(BoardDebugResponse {
+        era: era_index,
+        board: board_name,
+        limits,
+        total,
+        expected,
+        sample,
     })
 }
 
-/// Get all validators
-async fn get_all_validators(
-    era_index: EraIndex,
-    n: Quantity,
-    cache: Data<RedisPool>,
-) -> Result<Json<ValidatorsResponse>, ApiError> {
-    let key = sync::Key::BoardAtEra(era_index, sync::BOARD_ALL_VALIDATORS.to_string());
-    respond_json(ValidatorsResponse {
-        addresses: get_validators_stashes(key, n, cache).await?,
-        meta: MetaResponse::default(),
-    })
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CriterionConfig {
+    pub position: usize,
+    pub criterion: String,
+    pub default_weight: Weight,
+    /// Interval given in percent (0-100) rather than in the criterion unit
+    pub percent_interval: bool,
 }
 
-/// Get board validators
-async fn get_board_validators(
-    era_index: EraIndex,
-    params: Query<Params>,
-    cache: Data<RedisPool>,
-) -> Result<Json<ValidatorsResponse>, ApiError> {
-    let key = sync::Key::BoardAtEra(era_index, get_board_name(&params.w, Some(&params.i)));
-
-    // Generate leaderboard scores and cache it
-    generate_board_scores(era_index, &params.w, cache.clone()).await?;
-
-    // Generate filtered leaderboard and cache it
-    generate_board_filtered_by_intervals(era_index, &params.w, &params.i, cache.clone()).await?;
-
-    // Increase board stats counter
-    increase_board_stats(key.clone(), cache.clone()).await?;
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaConfigResponse {
+    pub api_path: String,
+    pub weights_capacity: usize,
+    pub intervals_capacity: usize,
+    pub max_weight: Weight,
+    pub criteria: Vec<CriterionConfig>,
+    pub commission_planck: u32,
+    /// Validators with more nominators than this are oversubscribed
+    pub max_nominator_rewarded_per_validator: u32,
+}
 
-    let limits: BoardLimits = get_board_limits(era_index, &params.w, cache.clone()).await?;
+/// Weight position definitions, in the order expected by the board queries
+fn criteria_config() -> Vec<CriterionConfig> {
+    CRITERIA
+        .iter()
+        .zip(DEFAULT_WEIGHTS.iter())
+        .enumerate()
+        .map(|(position, (criterion, weight))| CriterionConfig {
+            position,
+            criterion: criterion.to_string(),
+            default_weight: weight.parse::<Weight>().unwrap_or_default(),
+            percent_interval: PERCENT_INTERVALS.contains(&position),
+        })
+        .collect()
+}
 
-    respond_json(ValidatorsResponse {
-        addresses: get_validators_stashes(key.clone(), params.n, cache.clone()).await?,
-        meta: MetaResponse {
-            limits: limits.to_string(),
-        },
+/// Get the public parameters needed to build board queries, so that integrators
+/// don't hardcode values that might change across chains or versions
+pub async fn get_meta_config(cache: Data<RedisPool>) -> Result<Json<MetaConfigResponse>, ApiError> {
+    respond_json(MetaConfigResponse {
+        api_path: API_PATH.to_string(),
+        weights_capacity: WEIGHTS_CAPACITY,
+        intervals_capacity: INTERVALS_CAPACITY,
+        max_weight: MAX_WEIGHT,
+        criteria: criteria_config(),
+        commission_planck: COMMISSION_PLANCK,
+        max_nominator_rewarded_per_validator: get_max_nominator_rewarded(cache).await?,
     })
 }
 
 /// Get validators
 pub async fn get_validators(
-    params: Query<Params>,
+    req: HttpRequest,
+    mut params: Query<Params>,
     cache: Data<RedisPool>,
-) -> Result<Json<ValidatorsResponse>, ApiError> {
+) -> Result<HttpResponse, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
     let mut conn = get_conn(&cache).await?;
     let era_index: EraIndex = redis::cmd("GET")
         .arg(sync::Key::ActiveEra)
@@ -1424,16 +2320,31 @@ pub async fn get_validators(
         .await
         .map_err(CacheError::RedisCMDError)?;
 
-    match params.q {
+    let res = match params.q {
         Queries::Active => {
-            return get_active_validators(era_index, params.n, cache).await;
+            get_active_validators(
+                era_index,
+                params.offset,
+                params.n,
+                params.exclude_blocked,
+                cache,
+            )
+            .await
         }
         Queries::All => {
-            return get_all_validators(era_index, params.n, cache).await;
+            get_all_validators(
+                era_index,
+                params.offset,
+                params.n,
+                params.exclude_blocked,
+                cache,
+            )
+            .await
         }
-        Queries::Board => {
-            return get_board_validators(era_index, params, cache).await;
+        Queries::Board if accepts_csv(&req) => {
+            return get_board_validators_csv(era_index, params, cache).await;
         }
+        Queries::Board => get_board_validators(era_index, params, cache).await,
         _ => {
             let msg = format!(
                 "Parameter q={} must be equal to one of the options: [Active, All, Board]",
@@ -1442,5 +2353,594 @@ pub async fn get_validators(
             warn!("{}", msg);
             return Err(ApiError::BadRequest(msg));
         }
+    };
+
+    Ok(HttpResponse::Ok().json(res?.into_inner()))
+}
+
+/// Maximum number of targets a nominator can submit if not available from the chain
+const DEFAULT_MAX_NOMINATIONS: u32 = 16;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NominationCandidate {
+    pub stash: String,
+    pub name: String,
+    pub commission: u32,
+    pub own_stake: u128,
+    pub own_stake_tokens: f64,
+    pub total_stake: u128,
+    pub total_stake_tokens: f64,
+}
+
+impl NominationCandidate {
+    fn new(validator: Validator, token_decimals: u8) -> Self {
+        let total_stake = validator.own_stake + validator.nominators_stake;
+        NominationCandidate {
+            stash: validator.stash,
+            name: validator.name,
+            commission: validator.commission,
+            own_stake: validator.own_stake,
+            own_stake_tokens: to_tokens(validator.own_stake, token_decimals),
+            total_stake,
+            total_stake_tokens: to_tokens(total_stake, token_decimals),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NominateSuggestResponse {
+    pub era_index: EraIndex,
+    /// Stashes ready to be submitted as the targets of a nominate extrinsic
+    pub targets: Vec<String>,
+    pub candidates: Vec<NominationCandidate>,
+}
+
+/// Number of targets suggested, capped by the maximum number of nominations
+fn nominations_count(n: Quantity, max_nominations: u32) -> Quantity {
+    if n == 0 || n > max_nominations {
+        return max_nominations;
+    }
+    n
+}
+
+/// Get the maximum number of targets a nominator can submit
+async fn get_max_nominations(cache: Data<RedisPool>) -> Result<u32, ApiError> {
+    let mut conn = get_conn(&cache).await?;
+    let max_nominations: Option<u32> = redis::cmd("HGET")
+        .arg(sync::Key::Network)
+        .arg("max_nominations")
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    Ok(max_nominations.unwrap_or(DEFAULT_MAX_NOMINATIONS))
+}
+
+/// Suggest the top validators of a board as nomination targets, with the details
+/// of every candidate so that no follow-up request is needed per stash
+pub async fn post_nominate_suggest(
+    mut params: Json<Params>,
+    cache: Data<RedisPool>,
+) -> Result<Json<NominateSuggestResponse>, ApiError> {
+    expand_preset(&mut params, cache.clone()).await?;
+    let mut conn = get_conn(&cache).await?;
+    let era_index: EraIndex = redis::cmd("GET")
+        .arg(sync::Key::ActiveEra)
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let n = nominations_count(params.n, get_max_nominations(cache.clone()).await?);
+    let key = generate_board(era_index, false, &params, cache.clone()).await?;
+    let targets = get_validators_stashes(key, 0, n, params.min_score, cache.clone()).await?;
+
+    let mut pipe = redis::pipe();
+    for stash in targets.iter() {
+        let stash = AccountId32::from_str(stash)?;
+        pipe.cmd("HGETALL").arg(sync::Key::Validator(stash));
+    }
+    let data: Vec<ValidatorCache> = pipe
+        .query_async(&mut conn as &mut Connection)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+
+    let token_decimals = get_token_decimals(cache.clone()).await?;
+    let candidates = targets
+        .iter()
+        .zip(data.into_iter())
+        .map(|(stash, mut data)| {
+            data.insert("stash".to_string(), stash.to_string());
+            NominationCandidate::new(data.into(), token_decimals)
+        })
+        .collect();
+
+    respond_json(NominateSuggestResponse {
+        era_index,
+        targets,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{intervals_to_string, is_commission_within, weights_to_string, Interval};
+    use crate::errors::query_error_handler;
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    async fn params_handler(params: Query<Params>) -> HttpResponse {
+        HttpResponse::Ok().body(weights_to_string(&params.w))
+    }
+
+    async fn intervals_handler(params: Query<Params>) -> HttpResponse {
+        HttpResponse::Ok().body(intervals_to_string(&params.i))
+    }
+
+    #[actix_rt::test]
+    async fn it_parses_empty_intervals() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(intervals_handler)),
+        )
+        .await;
+        for uri in &["/", "/?i="] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = test::read_body(resp).await;
+            assert_eq!(body, "");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn it_parses_single_sided_intervals() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(intervals_handler)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/?i=0.5,:10").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let intervals: Vec<&str> = body.split(",").collect();
+        assert_eq!(intervals.len(), INTERVALS_CAPACITY);
+        assert_eq!(intervals[0], "0.5:inf");
+        // Commission is given in percent and converted into planck
+        assert_eq!(intervals[1], "-inf:100000000");
+        assert!(intervals[2..].iter().all(|x| *x == "-inf:inf"));
+    }
+
+    #[actix_rt::test]
+    async fn it_filters_commission_in_percent() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(intervals_handler)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/?i=,5:10").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let intervals: Vec<&str> = body.split(",").collect();
+        assert_eq!(intervals[1], "50000000:100000000");
+
+        let commission = Interval {
+            min: 5.0,
+            max: 10.0,
+        }
+        .percent_to_planck();
+        // 7% commission
+        assert!(is_commission_within(70_000_000, &commission));
+        assert!(!is_commission_within(110_000_000, &commission));
+        assert!(!is_commission_within(10_000_000, &commission));
+    }
+
+    #[actix_rt::test]
+    async fn it_responds_bad_request_with_malformed_intervals() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(intervals_handler)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/?i=abc:def").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Parameter i has an invalid interval 'abc:def' at position 0"));
+    }
+
+    #[test]
+    fn it_dedups_searched_stashes() {
+        let members: Vec<String> = vec!["aa\u{0}A", "aa\u{0}B", "aaa\u{0}A", "invalid"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(search_stashes(&members), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn it_caps_nominations_count() {
+        assert_eq!(nominations_count(0, 16), 16);
+        assert_eq!(nominations_count(8, 16), 8);
+        assert_eq!(nominations_count(24, 16), 16);
+        assert_eq!(nominations_count(24, 24), 24);
+    }
+
+    #[test]
+    fn it_keeps_missing_stashes_in_batch_items() {
+        let known = AccountId32::new([1; 32]);
+        let unknown = AccountId32::new([2; 32]);
+        let mut data: ValidatorCache = BTreeMap::new();
+        data.insert("name".to_string(), "TURBOFLAKES".to_string());
+        data.insert("commission".to_string(), "10".to_string());
+        data.insert("own_stake".to_string(), "2500000000000".to_string());
+
+        let items = batch_items(
+            &vec![unknown.clone(), known.clone()],
+            vec![BTreeMap::new(), data],
+            12,
+        );
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].found, false);
+        assert_eq!(items[0].validator.stash, unknown.to_string());
+        assert_eq!(items[0].validator.name, "");
+        assert_eq!(items[1].found, true);
+        assert_eq!(items[1].validator.stash, known.to_string());
+        assert_eq!(items[1].validator.name, "TURBOFLAKES");
+        assert_eq!(items[1].validator.commission, 10);
+        assert_eq!(items[1].validator.own_stake, 2_500_000_000_000);
+        assert_eq!(items[1].validator.own_stake_tokens, 2.5);
+    }
+
+    #[test]
+    fn it_calculates_era_deltas() {
+        assert_eq!(
+            Delta::new(500, 560),
+            Delta {
+                from: 500,
+                to: 560,
+                change: 60,
+                change_percent: Some(12.0),
+            }
+        );
+        assert_eq!(Delta::new(560, 280).change, -280);
+        assert_eq!(Delta::new(560, 280).change_percent, Some(-50.0));
+        // No relative change from zero
+        assert_eq!(Delta::new(0, 10).change_percent, None);
+    }
+
+    #[test]
+    fn it_selects_era_keys() {
+        let keys: Vec<String> = vec![12, 10, 14, 11, 13]
+            .iter()
+            .map(|era| format!("{}:era:stash:val", era))
+            .collect();
+
+        let selected = select_era_keys(keys.clone(), &ErasParams::default());
+        let eras: Vec<EraIndex> = selected.iter().map(|(era, _)| *era).collect();
+        assert_eq!(eras, vec![14, 13, 12, 11, 10]);
+        assert_eq!(selected[0].1, "14:era:stash:val");
+
+        let params = ErasParams {
+            from_era: Some(11),
+            to_era: Some(13),
+            limit: None,
+        };
+        let eras: Vec<EraIndex> = select_era_keys(keys.clone(), &params)
+            .iter()
+            .map(|(era, _)| *era)
+            .collect();
+        assert_eq!(eras, vec![13, 12, 11]);
+
+        let params = ErasParams {
+            from_era: Some(11),
+            to_era: None,
+            limit: Some(2),
+        };
+        let eras: Vec<EraIndex> = select_era_keys(keys, &params)
+            .iter()
+            .map(|(era, _)| *era)
+            .collect();
+        assert_eq!(eras, vec![14, 13]);
+
+        // Keys prefixed by REDIS_KEY_PREFIX
+        let keys: Vec<String> = vec![12, 10]
+            .iter()
+            .map(|era| format!("ksm:{}:era:stash:val", era))
+            .collect();
+        let selected = select_era_keys(keys, &ErasParams::default());
+        assert_eq!(selected[0], (12, "ksm:12:era:stash:val".to_string()));
+        assert_eq!(selected[1].0, 10);
+    }
+
+    #[test]
+    fn it_keeps_members_of_scanned_pages() {
+        let scanned = vec![
+            "stash_a".to_string(),
+            "1".to_string(),
+            "stash_b".to_string(),
+            "2".to_string(),
+        ];
+        assert_eq!(
+            scanned_members(scanned),
+            vec!["stash_a".to_string(), "stash_b".to_string()]
+        );
+        assert!(scanned_members(vec![]).is_empty());
+    }
+
+    #[test]
+    fn it_describes_criteria_positions() {
+        let criteria = criteria_config();
+        assert_eq!(criteria.len(), WEIGHTS_CAPACITY);
+        assert_eq!(
+            criteria[1],
+            CriterionConfig {
+                position: 1,
+                criterion: "commission".to_string(),
+                default_weight: 5,
+                percent_interval: true,
+            }
+        );
+        assert_eq!(criteria[12].criterion, "self_stake_ratio");
+        assert_eq!(criteria[12].default_weight, 0);
+    }
+
+    #[test]
+    fn it_labels_scores() {
+        assert_eq!(
+            Scores::new(vec![0.5, 0.25], &[9, 1], false),
+            Scores::Values(vec![0.5, 0.25])
+        );
+        assert_eq!(
+            Scores::new(vec![0.5, 0.25], &[9, 1], true),
+            Scores::Labeled(vec![
+                LabeledScore {
+                    criterion: "inclusion_rate".to_string(),
+                    weight: 9,
+                    value: 0.5,
+                },
+                LabeledScore {
+                    criterion: "commission".to_string(),
+                    weight: 1,
+                    value: 0.25,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn it_parses_batch_stashes() {
+        let stash = AccountId32::new([1; 32]);
+        assert_eq!(
+            parse_batch_stashes(&format!(" {}, ,{}", stash, stash), None).unwrap(),
+            vec![stash.clone(), stash.clone()]
+        );
+        assert!(parse_batch_stashes("", None).is_err());
+        assert!(parse_batch_stashes(&format!("{},unknown", stash), None).is_err());
+        assert!(
+            parse_batch_stashes(&vec![stash.to_string(); BATCH_CAPACITY + 1].join(","), None)
+                .is_err()
+        );
+        // Addresses of another network are rejected
+        let polkadot = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+        let kusama = "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F";
+        assert_eq!(parse_batch_stashes(polkadot, Some(0)).unwrap().len(), 1);
+        match parse_batch_stashes(&format!("{},{}", polkadot, kusama), Some(0)) {
+            Err(ApiError::BadRequest(msg)) => assert!(msg.contains("another network")),
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_keeps_stash_statuses_in_rank_batch_items() {
+        let ranked = AccountId32::new([1; 32]);
+        let not_ready = AccountId32::new([2; 32]);
+        let not_found = AccountId32::new([3; 32]);
+
+        let items = rank_batch_items(
+            &vec![not_found.clone(), ranked.clone(), not_ready.clone()],
+            vec![
+                (None, None, None, None),
+                (
+                    Some(0),
+                    Some(0.75),
+                    Some("0.5,0.25".to_string()),
+                    Some(75.0),
+                ),
+                (Some(1), Some(0.5), None, None),
+            ],
+            4,
+            &vec![9, 1],
+            Formula::Sum,
+            false,
+            |_| false,
+        );
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].stash, not_found.to_string());
+        assert_eq!(items[0].status, Status::NotFound);
+        assert_eq!(items[0].rank, 0);
+        assert_eq!(items[1].stash, ranked.to_string());
+        assert_eq!(items[1].status, Status::Ok);
+        assert_eq!(items[1].rank, 1);
+        assert_eq!(items[1].total, 4);
+        assert_eq!(items[1].percentile, 1.0);
+        assert_eq!(items[1].score, 0.75);
+        assert_eq!(items[1].normalized_score, 75.0);
+        assert_eq!(items[1].scores, Scores::Values(vec![0.5, 0.25]));
+        assert_eq!(items[2].stash, not_ready.to_string());
+        assert_eq!(items[2].status, Status::NotReady);
+        assert_eq!(items[2].total, 0);
+
+        // Excluded stashes are not found even if still cached in the board
+        let items = rank_batch_items(
+            &vec![ranked.clone()],
+            vec![(
+                Some(0),
+                Some(0.75),
+                Some("0.5,0.25".to_string()),
+                Some(75.0),
+            )],
+            4,
+            &vec![9, 1],
+            Formula::Sum,
+            false,
+            |stash| stash == ranked.to_string(),
+        );
+        assert_eq!(items[0].status, Status::NotFound);
+        assert_eq!(items[0].rank, 0);
+    }
+
+    #[test]
+    fn it_calculates_rank_percentile() {
+        assert_eq!(percentile(1, 100), 1.0);
+        assert_eq!(percentile(51, 100), 0.5);
+        assert_eq!(percentile(4, 4), 0.25);
+        assert_eq!(percentile(0, 100), 0.0);
+        assert_eq!(percentile(1, 0), 0.0);
+    }
+
+    #[test]
+    fn it_formats_board_csv() {
+        let header = board_csv_header();
+        assert!(header.starts_with("stash,name,score,rank,inclusion_rate,commission,"));
+        assert_eq!(header.trim_end().split(",").count(), 4 + WEIGHTS_CAPACITY);
+        assert_eq!(
+            board_csv_row("x", "Flakes, Inc", 42.5, 1, Some("1,2".to_string())),
+            "x,\"Flakes, Inc\",42.5,1,1,2\n"
+        );
+        let row = board_csv_row("x", "y", 0.0, 2, None);
+        assert_eq!(row.trim_end().split(",").count(), 4 + WEIGHTS_CAPACITY);
+    }
+
+    #[test]
+    fn it_projects_validator_fields() {
+        let mut data: ValidatorCache = ValidatorCache::new();
+        data.insert("name".to_string(), "TURBOFLAKES".to_string());
+        data.insert("commission".to_string(), "10000000".to_string());
+        data.insert("active".to_string(), "true".to_string());
+        data.insert("own_stake".to_string(), u128::MAX.to_string());
+        let validator = serde_json::to_string(&Validator::from(data)).unwrap();
+        let value: ValidatorFields = serde_json::from_str(&validator).unwrap();
+        let to_string = |fields: ValidatorFields| serde_json::to_string(&fields).unwrap();
+
+        assert_eq!(
+            to_string(project_fields(value.clone(), None).unwrap()),
+            to_string(value.clone())
+        );
+        assert_eq!(
+            to_string(project_fields(value.clone(), Some("")).unwrap()),
+            to_string(value.clone())
+        );
+        assert_eq!(
+            to_string(project_fields(value.clone(), Some("name, commission,active,name")).unwrap()),
+            r#"{"active":true,"commission":10000000,"name":"TURBOFLAKES"}"#
+        );
+        // Planck values above u64::MAX are kept as they are
+        assert_eq!(
+            to_string(project_fields(value.clone(), Some("own_stake")).unwrap()),
+            format!(r#"{{"own_stake":{}}}"#, u128::MAX)
+        );
+        match project_fields(value, Some("name,comission")) {
+            Err(ApiError::BadRequest(msg)) => {
+                assert!(msg.contains("invalid field 'comission'"))
+            }
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_decodes_stash_of_the_chain_network() {
+        // Same account encoded for Polkadot (0) and Kusama (2)
+        let polkadot = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+        let kusama = "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F";
+        let account = decode_stash(polkadot, Some(0)).unwrap();
+        assert_eq!(account, decode_stash(kusama, Some(2)).unwrap());
+        // Any network is accepted while the chain ss58 format is unknown
+        assert_eq!(decode_stash(kusama, None).unwrap(), account);
+    }
+
+    #[test]
+    fn it_rejects_stash_of_another_network() {
+        let kusama = "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F";
+        match decode_stash(kusama, Some(0)) {
+            Err(ApiError::BadRequest(msg)) => {
+                assert!(msg.contains("another network"));
+                assert!(msg.contains("ss58 format 2"));
+                assert!(msg.contains("ss58 format 0"));
+            }
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+        assert!(matches!(
+            decode_stash("not-an-address", Some(0)),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn it_pages_without_excluded_stashes() {
+        let stashes: Vec<String> = vec!["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect();
+        let is_excluded = |stash: &str| stash == "b" || stash == "z";
+        assert_eq!(
+            page_without_excluded(stashes.clone(), 0, 2, is_excluded),
+            vec!["a", "c"]
+        );
+        assert_eq!(
+            page_without_excluded(stashes.clone(), 2, 2, is_excluded),
+            vec!["d", "e"]
+        );
+        assert!(page_without_excluded(stashes, 4, 2, is_excluded).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn it_parses_weights() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(params_handler)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/?w=1,2,12").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let weights: Vec<&str> = body.split(",").collect();
+        assert_eq!(weights.len(), WEIGHTS_CAPACITY);
+        assert_eq!(
+            weights[..10],
+            ["1", "2", "9", "5", "5", "5", "5", "5", "5", "5"]
+        );
+        // Positions added after the first 10 criteria default to 0
+        assert!(weights[10..].iter().all(|x| *x == "0"));
+    }
+
+    #[actix_rt::test]
+    async fn it_responds_bad_request_with_malformed_weights() {
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/", web::get().to(params_handler)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/?q=Board&w=a,b,c")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(resp).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Parameter w has an invalid weight 'a' at position 0"));
+        assert!(body.contains("values between 0-9"));
     }
 }
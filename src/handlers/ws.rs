@@ -0,0 +1,195 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::cache::create_pubsub;
+use crate::config::CONFIG;
+use crate::errors::{CacheError, ErrorCode, ErrorResponse};
+use crate::sync::sync;
+use actix::{Actor, ActorContext, AsyncContext, Handler, Message, Recipient, StreamHandler};
+use actix_web::{
+    web::{Data, Payload},
+    Error, HttpRequest, HttpResponse,
+};
+use actix_web_actors::ws;
+use async_std::task;
+use futures::StreamExt;
+use log::{error, info, warn};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// How often heartbeat pings are sent
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long before lack of client response causes a timeout
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Notification pushed to every connected client
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+pub struct Notification(pub String);
+
+/// Registry of the websocket sessions currently connected
+#[derive(Default)]
+pub struct Sessions {
+    next_id: AtomicUsize,
+    recipients: Mutex<BTreeMap<usize, Recipient<Notification>>>,
+}
+
+impl Sessions {
+    fn insert(&self, recipient: Recipient<Notification>) -> usize {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.recipients.lock().unwrap().insert(id, recipient);
+        id
+    }
+
+    fn remove(&self, id: usize) {
+        self.recipients.lock().unwrap().remove(&id);
+    }
+
+    fn len(&self) -> usize {
+        self.recipients.lock().unwrap().len()
+    }
+
+    fn broadcast(&self, notification: Notification) {
+        for recipient in self.recipients.lock().unwrap().values() {
+            recipient.do_send(notification.clone()).unwrap_or_default();
+        }
+    }
+}
+
+/// Websocket session of a single client
+struct WsSession {
+    id: usize,
+    heartbeat: Instant,
+    sessions: Data<Sessions>,
+}
+
+impl WsSession {
+    fn new(sessions: Data<Sessions>) -> Self {
+        WsSession {
+            id: 0,
+            heartbeat: Instant::now(),
+            sessions,
+        }
+    }
+
+    /// Ping the client every HEARTBEAT_INTERVAL and drop it if it stops responding
+    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.heartbeat) > CLIENT_TIMEOUT {
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+}
+
+impl Actor for WsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.heartbeat(ctx);
+        self.id = self.sessions.insert(ctx.address().recipient());
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.sessions.remove(self.id);
+    }
+}
+
+impl Handler<Notification> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: Notification, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.heartbeat = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.heartbeat = Instant::now();
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(_) => ctx.stop(),
+            _ => (),
+        }
+    }
+}
+
+/// Handler to open a websocket that pushes a notification every time an era is synced
+pub async fn get_ws(
+    req: HttpRequest,
+    stream: Payload,
+    sessions: Data<Sessions>,
+) -> Result<HttpResponse, Error> {
+    if sessions.len() >= CONFIG.ws_max_connections {
+        let msg =
+            format!("Maximum number of websocket connections reached. Please try again later.");
+        warn!("{}", msg);
+        return Ok(HttpResponse::ServiceUnavailable()
+            .json(ErrorResponse::new(ErrorCode::RateLimited, &msg)));
+    }
+    ws::start(WsSession::new(sessions), &req, stream)
+}
+
+/// Bridge the notifications published by the sync task to the websocket sessions
+async fn subscribe_notifications(sessions: &Sessions) -> Result<(), CacheError> {
+    let mut pubsub = create_pubsub(CONFIG.clone()).await?;
+    pubsub
+        .subscribe(sync::Key::ChannelEraSynced)
+        .await
+        .map_err(CacheError::RedisCMDError)?;
+    info!("Subscribed to channel {}", sync::Key::ChannelEraSynced);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = msg.get_payload().map_err(CacheError::RedisTypeError)?;
+        sessions.broadcast(Notification(payload));
+    }
+    Err(CacheError::Other("Subscription finished".into()))
+}
+
+pub fn spawn_and_restart_notifications_on_error(sessions: Data<Sessions>) {
+    task::spawn(async move {
+        loop {
+            if let Err(e) = subscribe_notifications(&sessions).await {
+                error!("{}", e);
+                task::sleep(Duration::from_millis(1000)).await;
+            };
+        }
+    });
+}
@@ -20,11 +20,27 @@
 // SOFTWARE.
 
 use crate::errors::ApiError;
+use crate::sync::sync::{is_node_connected, EraIndex};
 use actix_web::{
     body::Body,
-    web::{HttpResponse, Json},
+    http::header,
+    web::{HttpRequest, HttpResponse, Json},
 };
 use serde::Serialize;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Fail fast operations that depend on the substrate node while it is unreachable
+pub fn node_connected_or_unavailable() -> Result<(), ApiError> {
+    if !is_node_connected() {
+        return Err(ApiError::NodeUnavailable(
+            "The substrate node is currently unreachable. Please try again in a moment.".into(),
+        ));
+    }
+    Ok(())
+}
 
 /// Helper function to reduce boilerplate of an OK/Json response
 pub fn respond_json<T>(data: T) -> Result<Json<T>, ApiError>
@@ -34,6 +50,132 @@ where
     Ok(Json(data))
 }
 
+/// Convert a planck value into tokens given the chain token decimals.
+/// Note: f64 can't represent every planck value exactly, so token-denominated values
+/// are meant for display only, the raw planck values should be used for accounting
+pub fn to_tokens(planck: u128, token_decimals: u8) -> f64 {
+    match 10_u128.checked_pow(token_decimals as u32) {
+        // Convert the integer and fractional parts separately so that large totals
+        // only lose precision in the least significant digits
+        Some(unit) => (planck / unit) as f64 + (planck % unit) as f64 / unit as f64,
+        None => 0.0,
+    }
+}
+
+/// Weak ETag defined by the era the data belongs to and the hash of the payload
+fn weak_etag(era_index: EraIndex, body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("W/\"{}-{:x}\"", era_index, hasher.finish())
+}
+
+/// Check if any of the If-None-Match tags matches the ETag, using weak comparison
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// Helper function to respond json with a weak ETag, or Not Modified if the client
+/// already has the same payload. Without an era (e.g. while syncing) no ETag is sent
+/// so that partial data doesn't get cached
+pub fn respond_json_with_etag<T>(
+    req: &HttpRequest,
+    data: T,
+    era_index: Option<EraIndex>,
+) -> Result<HttpResponse, ApiError>
+where
+    T: Serialize,
+{
+    let body =
+        serde_json::to_vec(&data).map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let era_index = match era_index {
+        Some(era_index) => era_index,
+        None => {
+            return Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .body(body))
+        }
+    };
+    let etag = weak_etag(era_index, &body);
+    if let Some(if_none_match) = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|x| x.to_str().ok())
+    {
+        if etag_matches(if_none_match, &etag) {
+            return Ok(HttpResponse::NotModified()
+                .header(header::ETAG, etag)
+                .finish());
+        }
+    }
+    Ok(HttpResponse::Ok()
+        .header(header::ETAG, etag)
+        .content_type("application/json")
+        .body(body))
+}
+
+/// Media ranges of an Accept header with their quality, 1 if not given
+fn accept_ranges(accept: &str) -> Vec<(String, f32)> {
+    accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let media_range = parts.next()?.trim().to_lowercase();
+            if media_range.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| {
+                    let mut param = param.splitn(2, '=');
+                    match (param.next()?.trim(), param.next()) {
+                        ("q", Some(value)) => value.trim().parse::<f32>().ok(),
+                        _ => None,
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+            Some((media_range, quality))
+        })
+        .collect()
+}
+
+/// Quality of the most specific media range matching a media type, the ranges matching
+/// it given from the most to the least specific. None if no range matches it
+fn accept_quality(ranges: &[(String, f32)], matching: &[&str]) -> Option<f32> {
+    matching.iter().find_map(|media_range| {
+        ranges
+            .iter()
+            .find(|(x, _)| x == media_range)
+            .map(|(_, quality)| *quality)
+    })
+}
+
+/// Check if the client prefers a CSV response, JSON being the default otherwise.
+/// Note: CSV has to be requested explicitly and rank strictly higher than JSON
+pub fn accepts_csv(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|x| x.to_str().ok())
+        .map(|accept| {
+            let ranges = accept_ranges(accept);
+            let csv = accept_quality(&ranges, &["text/csv"]).unwrap_or(0.0);
+            let json = accept_quality(&ranges, &["application/json", "application/*", "*/*"])
+                .unwrap_or(0.0);
+            csv > json
+        })
+        .unwrap_or(false)
+}
+
+/// Escape a CSV field, quoting it if it contains a separator, a quote or a line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    value.to_string()
+}
+
 /// Helper function to reduce boilerplate of an empty OK response
 #[allow(dead_code)]
 pub fn respond_ok() -> Result<HttpResponse, ApiError> {
@@ -59,6 +201,108 @@ mod tests {
         assert_eq!(result.unwrap().into_inner(), response);
     }
 
+    #[test]
+    fn it_converts_planck_to_tokens() {
+        assert_eq!(to_tokens(1_500_000_000_000, 12), 1.5);
+        assert_eq!(to_tokens(25_000_000_000, 10), 2.5);
+        assert_eq!(to_tokens(42, 0), 42.0);
+        assert_eq!(to_tokens(0, 12), 0.0);
+        assert_eq!(to_tokens(1, 255), 0.0);
+    }
+
+    #[test]
+    fn it_matches_weak_etags() {
+        let etag = weak_etag(2470, b"{}");
+        assert!(etag.starts_with("W/\"2470-"));
+        assert_eq!(etag, weak_etag(2470, b"{}"));
+        assert_ne!(etag, weak_etag(2471, b"{}"));
+        assert_ne!(etag, weak_etag(2470, b"[]"));
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&etag.trim_start_matches("W/"), &etag));
+        assert!(etag_matches(&format!("\"other\", {}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("W/\"other\"", &etag));
+    }
+
+    #[test]
+    fn it_escapes_csv_fields() {
+        assert_eq!(csv_field("TURBOFLAKES"), "TURBOFLAKES");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn it_accepts_csv() {
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "text/csv")
+            .to_http_request();
+        assert!(accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "application/json, text/csv;q=0.5")
+            .to_http_request();
+        assert!(!accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "application/json;q=0.5, text/csv")
+            .to_http_request();
+        assert!(accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "text/csv, */*;q=0.1")
+            .to_http_request();
+        assert!(accepts_csv(&req));
+        // Equal quality keeps JSON as the default
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "text/csv, application/json")
+            .to_http_request();
+        assert!(!accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "text/csv;q=0")
+            .to_http_request();
+        assert!(!accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "*/*")
+            .to_http_request();
+        assert!(!accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default()
+            .header(header::ACCEPT, "application/json")
+            .to_http_request();
+        assert!(!accepts_csv(&req));
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        assert!(!accepts_csv(&req));
+    }
+
+    #[actix_rt::test]
+    async fn it_compresses_csv_responses() {
+        use actix_web::{http::ContentEncoding, middleware::Compress, test, web, App};
+
+        let mut app = test::init_service(App::new().wrap(Compress::default()).route(
+            "/",
+            web::get().to(|| async {
+                HttpResponse::Ok()
+                    .content_type("text/csv")
+                    .body("stash,name\n".repeat(100))
+            }),
+        ))
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            ContentEncoding::Gzip.as_str()
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/csv"
+        );
+
+        // Clients not advertising an encoding get the identity response
+        let req = test::TestRequest::get().uri("/").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
     #[test]
     fn it_responds_ok() {
         let result = respond_ok();
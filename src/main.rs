@@ -19,6 +19,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod board;
 mod cache;
 mod config;
 mod errors;
@@ -29,12 +30,15 @@ mod sync;
 
 use crate::cache::add_pool;
 use crate::config::CONFIG;
+use crate::errors::query_error_handler;
+use crate::handlers::admin::authorize_all;
+use crate::handlers::ws::{spawn_and_restart_notifications_on_error, Sessions};
 use crate::routes::routes;
-use crate::sync::sync::Sync;
+use crate::sync::sync::{clear_interrupted_sync, Sync};
 use actix_cors::Cors;
-use actix_web::{http, middleware, App, HttpServer};
+use actix_web::{dev::Service, middleware, web, App, HttpServer};
+use futures::future::{err, Either};
 use log::info;
-use std::env;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -45,32 +49,65 @@ async fn main() -> std::io::Result<()> {
         "Starting {} version {} <{}>",
         env!("CARGO_PKG_NAME"),
         env!("CARGO_PKG_VERSION"),
-        config.substrate_ws_url
+        config.substrate_ws_urls().join(",")
     );
 
     // Spawn history and subscription sincronization tasks
     Sync::run();
 
+    // Spawn websocket notifications bridge
+    let sessions = web::Data::new(Sessions::default());
+    spawn_and_restart_notifications_on_error(sessions.clone());
+
     // Start http server
     let addr = format!("{}:{}", config.turboflakes_host, config.turboflakes_port);
     HttpServer::new(move || {
+        // Note: the origin matched is echoed back in the response
         let cors = Cors::default()
-            .allowed_origin_fn(|origin, _req_head| {
-                let allowed_origin =
-                    env::var("TURBOFLAKES_CORS_ALLOW_ORIGIN").unwrap_or("*".to_string());
-                origin.as_bytes().ends_with(allowed_origin.as_bytes())
+            .allowed_origin_fn(|origin, _req_head| match origin.to_str() {
+                Ok(origin) => CONFIG.is_cors_origin_allowed(origin),
+                Err(_) => false,
             })
-            .allowed_methods(vec!["GET", "OPTIONS"])
-            .allowed_headers(vec![http::header::CONTENT_TYPE])
+            .allowed_methods(
+                CONFIG
+                    .turboflakes_cors_allow_methods
+                    .iter()
+                    .map(|x| x.trim()),
+            )
+            .allowed_headers(
+                CONFIG
+                    .turboflakes_cors_allow_headers
+                    .iter()
+                    .map(|x| x.trim()),
+            )
             .supports_credentials()
             .max_age(3600);
         App::new()
+            // Read-only routes are open unless REQUIRE_AUTH_ALL is set.
+            // Note: the guard is inside CORS, so that preflight requests are answered
+            // and responses refused carry the CORS headers
+            .wrap_fn(|req, srv| match authorize_all(req.request()) {
+                Ok(()) => Either::Left(srv.call(req)),
+                Err(e) => Either::Right(err(e.into())),
+            })
+            // Note: compression is the innermost middleware so that responses are
+            // encoded before CORS headers are set, and websocket upgrades are left as is
+            .wrap(middleware::Compress::default())
             .wrap(middleware::Logger::default())
             .wrap(cors)
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .app_data(sessions.clone())
             .configure(add_pool)
             .configure(routes)
     })
     .bind(addr)?
     .run()
-    .await
+    .await?;
+
+    // Note: the server stops gracefully on SIGTERM, SIGINT or SIGQUIT, so that a sync
+    // interrupted by the shutdown is only cleared once in-flight requests are handled
+    clear_interrupted_sync().await;
+    info!("Shutdown complete");
+
+    Ok(())
 }
@@ -19,14 +19,34 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+#[cfg(feature = "metrics")]
+use crate::handlers::metrics::get_metrics;
 use crate::handlers::{
-    era::get_era,
+    admin::post_resync,
+    board::get_board_stats,
+    era::{
+        get_active_set_diff, get_era, get_era_points_distribution, get_era_points_raw, get_eras,
+    },
     health::get_health,
-    info::get_info,
-    validator::{get_validator, get_validator_eras, get_validator_rank, get_validators},
+    info::{get_info, get_sync_errors},
+    network::{get_network, get_network_stats},
+    nominator::get_nominator,
+    preset::{get_presets, post_preset},
+    validator::{
+        get_board_debug, get_limits, get_meta_config, get_validator,
+        get_validator_commission_history, get_validator_era_delta, get_validator_eras,
+        get_validator_points, get_validator_rank, get_validator_rank_history,
+        get_validator_sessions, get_validators, get_validators_batch, get_validators_count,
+        get_validators_rank_batch, get_validators_scan, get_validators_search,
+        post_nominate_suggest,
+    },
+    ws::get_ws,
 };
 use actix_web::web;
 
+/// Path of the versioned API routes
+pub const API_PATH: &'static str = "/api/v1";
+
 /// All routes are placed here
 pub fn routes(cfg: &mut web::ServiceConfig) {
     cfg
@@ -34,20 +54,86 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
         .route("/", web::get().to(get_info))
         // Healthcheck
         .route("/health", web::get().to(get_health))
+        // Era notifications
+        .route("/ws", web::get().to(get_ws))
         // /api/v1 routes
         .service(
-            web::scope("/api/v1")
+            web::scope(API_PATH)
                 // API info
                 .route("", web::get().to(get_info))
+                .route("/config", web::get().to(get_meta_config))
+                .route("/info/sync", web::get().to(get_sync_errors))
+                // NETWORK routes
+                .route("/network", web::get().to(get_network))
+                .route("/network/stats", web::get().to(get_network_stats))
+                // PRESET routes
+                .service(
+                    web::scope("/preset")
+                        .route("", web::get().to(get_presets))
+                        .route("", web::post().to(post_preset)),
+                )
+                // ADMIN routes
+                .service(
+                    web::scope("/admin")
+                        .route("/resync", web::post().to(post_resync))
+                        // Diagnostic only
+                        .route("/board/debug", web::get().to(get_board_debug)),
+                )
+                // BOARD routes
+                .service(
+                    web::scope("/board")
+                        .route("/stats", web::get().to(get_board_stats))
+                        .route("/limits", web::get().to(get_limits)),
+                )
                 // ERA routes
-                .service(web::scope("/era").route("/{era_index}", web::get().to(get_era)))
+                .service(
+                    web::scope("/era")
+                        .route("", web::get().to(get_eras))
+                        .route("/active/diff", web::get().to(get_active_set_diff))
+                        .route("/{era_index}", web::get().to(get_era))
+                        .route(
+                            "/{era_index}/points",
+                            web::get().to(get_era_points_distribution),
+                        )
+                        .route("/{era_index}/points/raw", web::get().to(get_era_points_raw)),
+                )
+                // NOMINATE routes
+                .service(
+                    web::scope("/nominate")
+                        .route("/suggest", web::post().to(post_nominate_suggest)),
+                )
+                // NOMINATOR routes
+                .service(web::scope("/nominator").route("/{stash}", web::get().to(get_nominator)))
                 // VALIDATOR routes
                 .service(
                     web::scope("/validator")
+                        .route("/batch", web::get().to(get_validators_batch))
+                        .route("/count", web::get().to(get_validators_count))
+                        .route("/scan", web::get().to(get_validators_scan))
+                        .route("/search", web::get().to(get_validators_search))
+                        .route("/rank/batch", web::get().to(get_validators_rank_batch))
                         .route("/{stash}", web::get().to(get_validator))
                         .route("/{stash}/rank", web::get().to(get_validator_rank))
+                        .route(
+                            "/{stash}/rank/history",
+                            web::get().to(get_validator_rank_history),
+                        )
                         .route("/{stash}/eras", web::get().to(get_validator_eras))
+                        .route(
+                            "/{stash}/eras/delta",
+                            web::get().to(get_validator_era_delta),
+                        )
+                        .route("/{stash}/points", web::get().to(get_validator_points))
+                        .route("/{stash}/sessions", web::get().to(get_validator_sessions))
+                        .route(
+                            "/{stash}/commission/history",
+                            web::get().to(get_validator_commission_history),
+                        )
                         .route("", web::get().to(get_validators)),
                 ),
         );
+
+    // Prometheus metrics
+    #[cfg(feature = "metrics")]
+    cfg.route("/metrics", web::get().to(get_metrics));
 }
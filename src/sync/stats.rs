@@ -36,6 +36,16 @@ pub fn mean_f64(list: &Vec<f64>) -> f64 {
     sum / (list.len() as f64)
 }
 
+/// Mean of the values weighted by the respective weights, zero if weights add up to zero
+pub fn weighted_mean(values: &Vec<f64>, weights: &Vec<f64>) -> f64 {
+    let total: f64 = weights.iter().take(values.len()).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let sum: f64 = values.iter().zip(weights.iter()).map(|(v, w)| v * w).sum();
+    sum / total
+}
+
 pub fn _mean_u128(list: &Vec<u128>) -> f64 {
     if list.len() == 0 {
         return 0.0;
@@ -44,15 +54,25 @@ pub fn _mean_u128(list: &Vec<u128>) -> f64 {
     (sum as f64) / (list.len() as f64)
 }
 
+/// Median of the list. For even-length lists the two middle values are averaged
+/// and the result is rounded half up to the nearest u32, e.g. median of [3, 4] is 4
 pub fn median(list: &mut Vec<u32>) -> u32 {
     if list.len() == 0 {
         return 0;
     }
     list.sort();
     let mid = list.len() / 2;
+    if list.len() % 2 == 0 {
+        let sum = list[mid - 1] as u64 + list[mid] as u64;
+        return ((sum + 1) / 2) as u32;
+    }
     list[mid]
 }
 
+pub fn to_f64(list: &Vec<u32>) -> Vec<f64> {
+    list.iter().map(|&x| f64::from(x)).collect()
+}
+
 pub fn min(list: &Vec<u32>) -> u32 {
     match list.iter().min() {
         Some(v) => *v,
@@ -67,6 +87,20 @@ pub fn max(list: &Vec<u32>) -> u32 {
     }
 }
 
+pub fn min_u128(list: &Vec<u128>) -> u128 {
+    match list.iter().min() {
+        Some(v) => *v,
+        None => 0,
+    }
+}
+
+pub fn max_u128(list: &Vec<u128>) -> u128 {
+    match list.iter().max() {
+        Some(v) => *v,
+        None => 0,
+    }
+}
+
 pub fn standard_deviation(list: &Vec<f64>) -> f64 {
     let m = mean_f64(list);
     let mut variance: Vec<f64> = list
@@ -76,9 +110,52 @@ pub fn standard_deviation(list: &Vec<f64>) -> f64 {
     mean_f64(&mut variance).sqrt()
 }
 
+/// Gini coefficient of the list, from 0 when all values are equal up to 1 when a single
+/// value holds the total, zero if the list is empty or adds up to zero
+pub fn gini_u128(list: &Vec<u128>) -> f64 {
+    let total: f64 = list.iter().map(|&x| x as f64).sum();
+    if list.len() == 0 || total == 0.0 {
+        return 0.0;
+    }
+    let mut sorted = list.clone();
+    sorted.sort();
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| (i as f64 + 1.0) * x as f64)
+        .sum();
+    (2.0 * weighted) / (n * total) - (n + 1.0) / n
+}
+
+/// Minimum number of values, largest first, that add up to more than the share of the
+/// total, e.g. the Nakamoto coefficient of the stakes is given by a share of 1/3.
+/// Zero if the list is empty or adds up to zero
+pub fn nakamoto_coefficient(list: &Vec<u128>, share: f64) -> u32 {
+    let total: u128 = list.iter().sum();
+    if total == 0 {
+        return 0;
+    }
+    let mut sorted = list.clone();
+    sorted.sort_by(|a, b| b.cmp(a));
+    let mut accumulated: u128 = 0;
+    let mut count: u32 = 0;
+    for x in sorted {
+        if accumulated as f64 > share * total as f64 {
+            break;
+        }
+        accumulated += x;
+        count += 1;
+    }
+    count
+}
+
 // Calculate 95% confidence interval
 // https://www.mathsisfun.com/data/confidence-interval.html
 pub fn confidence_interval_95(list: &Vec<f64>) -> (f64, f64) {
+    if list.len() == 0 {
+        return (0.0, 0.0);
+    }
     let m = mean_f64(list);
     let sd = standard_deviation(list);
     let v = 1.96 * (sd / ((list.len() as f64).sqrt()));
@@ -107,6 +184,28 @@ mod tests {
         assert_eq!(median(&mut v), 4);
     }
 
+    #[test]
+    fn calculate_median_even_length() {
+        let mut v = vec![1, 2, 3, 4, 5, 4, 2, 8, 9, 10];
+        assert_eq!(median(&mut v), 4);
+        let mut v = vec![10, 20];
+        assert_eq!(median(&mut v), 15);
+        let mut v = vec![1, 2, 4, 7];
+        assert_eq!(median(&mut v), 3);
+        let mut v = vec![u32::MAX, u32::MAX];
+        assert_eq!(median(&mut v), u32::MAX);
+    }
+
+    #[test]
+    fn calculate_median_odd_length() {
+        let mut v = vec![5, 1, 3];
+        assert_eq!(median(&mut v), 3);
+        let mut v = vec![1, 2, 3, 4, 5, 4, 2, 6, 9];
+        assert_eq!(median(&mut v), 4);
+        let mut v = vec![7];
+        assert_eq!(median(&mut v), 7);
+    }
+
     #[test]
     fn calculate_min() {
         let mut v = vec![1, 2, 3, 4, 5, 4, 2, 6];
@@ -119,6 +218,39 @@ mod tests {
         assert_eq!(max(&mut v), 6);
     }
 
+    #[test]
+    fn calculate_min_max_u128_keeps_precision() {
+        // Two stakes that only differ in the low 30 bits collapse to the same f64
+        let a: u128 = (1 << 100) + 1;
+        let b: u128 = (1 << 100) + (1 << 29);
+        assert_eq!(a as f64, b as f64);
+        let v = vec![b, a];
+        assert_eq!(min_u128(&v), a);
+        assert_eq!(max_u128(&v), b);
+        assert_ne!(min_u128(&v), max_u128(&v));
+    }
+
+    #[test]
+    fn calculate_gini_u128() {
+        assert_eq!(gini_u128(&vec![]), 0.0);
+        assert_eq!(gini_u128(&vec![0, 0]), 0.0);
+        assert_eq!(gini_u128(&vec![5, 5, 5, 5]), 0.0);
+        assert_eq!(gini_u128(&vec![0, 0, 0, 10]), 0.75);
+        assert_eq!(gini_u128(&vec![3, 1, 2]), 0.22222222222222232);
+    }
+
+    #[test]
+    fn calculate_nakamoto_coefficient() {
+        assert_eq!(nakamoto_coefficient(&vec![], 1.0 / 3.0), 0);
+        assert_eq!(
+            nakamoto_coefficient(&vec![10, 10, 10, 10, 10, 10], 1.0 / 3.0),
+            3
+        );
+        assert_eq!(nakamoto_coefficient(&vec![1, 1, 50, 1, 1], 1.0 / 3.0), 1);
+        assert_eq!(nakamoto_coefficient(&vec![0, 0], 1.0 / 3.0), 0);
+        assert_eq!(nakamoto_coefficient(&vec![30, 20, 20, 20, 10], 0.5), 3);
+    }
+
     #[test]
     fn calculate_standard_deviation() {
         let mut v = vec![600.0, 470.0, 170.0, 430.0, 300.0];
@@ -131,5 +263,24 @@ mod tests {
             confidence_interval_95(&mut v),
             (264.86589420296434, 523.1341057970357)
         );
+        assert_eq!(confidence_interval_95(&vec![]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn calculate_weighted_mean() {
+        let v = vec![1.0, 2.0, 3.0, 4.0];
+        assert_eq!(weighted_mean(&v, &vec![1.0, 1.0, 1.0, 1.0]), mean_f64(&v));
+        assert_eq!(weighted_mean(&v, &vec![2.0, 2.0, 2.0, 2.0]), 2.5);
+        assert_eq!(weighted_mean(&v, &vec![1.0, 2.0, 3.0, 4.0]), 3.0);
+        assert_eq!(weighted_mean(&v, &vec![0.0, 0.0, 0.0, 1.0]), 4.0);
+        assert_eq!(weighted_mean(&v, &vec![0.0, 0.0, 0.0, 0.0]), 0.0);
+        assert_eq!(weighted_mean(&vec![], &vec![]), 0.0);
+    }
+
+    #[test]
+    fn convert_to_f64() {
+        let v = vec![600, 470, 170, 430, 300];
+        assert_eq!(to_f64(&v), vec![600.0, 470.0, 170.0, 430.0, 300.0]);
+        assert_eq!(standard_deviation(&to_f64(&v)), 147.32277488562318);
     }
 }
@@ -19,8 +19,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::board::{generate_default_board, generate_recommended_board};
 use crate::cache::{create_or_await_pool, RedisPool};
-use crate::config::{Config, CONFIG};
+use crate::config::{Config, SyncMode, Weighting, CONFIG};
 use crate::errors::{CacheError, SyncError};
 use crate::sync::runtime::{
     node_runtime,
@@ -30,14 +31,26 @@ use crate::sync::runtime::{
         DefaultConfig,
     },
 };
-use crate::sync::stats::{max, mean, median, min};
+use crate::sync::stats::{
+    confidence_interval_95, max, mean, mean_f64, median, min, nakamoto_coefficient,
+    standard_deviation, to_f64, weighted_mean,
+};
 use async_recursion::async_recursion;
-use async_std::task;
+use async_std::{future::timeout, task};
 use chrono::Utc;
 use codec::Decode;
+use futures::stream::{self, StreamExt};
 use log::{debug, error, info, warn};
 use redis::aio::Connection;
-use std::{collections::BTreeMap, convert::TryInto, env, result::Result, thread, time};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    result::Result,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread, time,
+};
 use subxt::{
     sp_core::{crypto, storage::StorageKey},
     sp_runtime::AccountId32,
@@ -50,46 +63,427 @@ pub type EraIndex = u32;
 /// Counter for the number of "reward" points earned by a given validator.
 pub type RewardPoint = u32;
 
+/// Simple index type with which we can count sessions.
+pub type SessionIndex = u32;
+
 pub const BOARD_TOTAL_POINTS_ERAS: &'static str = "total:points:era";
 pub const BOARD_AVG_POINTS_ERAS: &'static str = "avg:points:era";
 pub const BOARD_MAX_POINTS_ERAS: &'static str = "max:points:era";
 pub const BOARD_MIN_POINTS_ERAS: &'static str = "min:points:era";
 pub const BOARD_ACTIVE_VALIDATORS: &'static str = "active:val";
 pub const BOARD_ALL_VALIDATORS: &'static str = "all:val";
+pub const BOARD_BLOCKED_VALIDATORS: &'static str = "blocked:val";
+pub const BOARD_ALL_UNBLOCKED_VALIDATORS: &'static str = "all:unblocked:val";
+pub const BOARD_ACTIVE_UNBLOCKED_VALIDATORS: &'static str = "active:unblocked:val";
 pub const BOARD_POINTS_VALIDATORS: &'static str = "points:val";
 pub const BOARD_OWN_STAKE_VALIDATORS: &'static str = "own:stake:val";
 pub const BOARD_TOTAL_STAKE_VALIDATORS: &'static str = "total:stake:val";
 pub const BOARD_JUDGEMENTS_VALIDATORS: &'static str = "judgements:val";
 pub const BOARD_SUB_ACCOUNTS_VALIDATORS: &'static str = "sub:accounts:val";
+pub const BOARD_SLASHED_VALIDATORS: &'static str = "slashed:val";
+pub const BOARD_COMMISSION_VOLATILITY_VALIDATORS: &'static str = "commission:volatility:val";
+pub const BOARD_SELF_STAKE_RATIO_VALIDATORS: &'static str = "self:stake:ratio:val";
+pub const BOARD_RECOMMENDED_VALIDATORS: &'static str = "recommended:val";
+
+/// Share of the total active stake used to calculate the Nakamoto coefficient
+pub const NAKAMOTO_STAKE_SHARE: f64 = 1.0 / 3.0;
+
+/// Maximum number of sessions kept in the session points series of a validator
+pub const SESSION_POINTS_CAPACITY: isize = 1008;
+
+/// Set while this process is running a sync, so that a sync interrupted by the process
+/// shutting down can be cleared from the cache
+static SYNC_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Seconds the era payout sync lock is held at most, so that the lock of a process
+/// killed in the middle of a sync does not block the era payouts forever
+const SYNC_LOCK_SECONDS: usize = 3600;
+
+/// Cleared while the substrate node is unreachable and set again once reconnected, so
+/// that the API fails fast on operations that depend on the node
+static NODE_CONNECTED: AtomicBool = AtomicBool::new(true);
+
+/// Delay between connection attempts to the substrate node endpoints
+pub const NODE_RECONNECT_DELAY_SECS: u64 = 6;
+
+pub fn is_node_connected() -> bool {
+    NODE_CONNECTED.load(Ordering::SeqCst)
+}
+
+fn set_node_connected(connected: bool) {
+    if NODE_CONNECTED.swap(connected, Ordering::SeqCst) != connected {
+        if connected {
+            info!("Substrate node connection restored");
+        } else {
+            warn!("Substrate node unreachable");
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EraSyncedEvent {
+    event: &'static str,
+    era_index: EraIndex,
+}
+
+impl EraSyncedEvent {
+    fn new(era_index: EraIndex) -> Self {
+        EraSyncedEvent {
+            event: "era_synced",
+            era_index,
+        }
+    }
+}
+
+/// Maximum number of sync errors kept for diagnostics, the oldest are dropped first
+pub const SYNC_ERRORS_CAPACITY: isize = 20;
+
+/// Sync error recorded for diagnostics
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct SyncErrorRecord {
+    pub timestamp: i64,
+    pub phase: String,
+    pub message: String,
+}
+
+impl SyncErrorRecord {
+    fn new(phase: &str, error: &SyncError) -> Self {
+        SyncErrorRecord {
+            timestamp: Utc::now().timestamp(),
+            phase: phase.to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Number of trailing eras used to estimate the validator APR and APY
+pub const EST_APR_ERAS: u32 = 7;
+
+/// Maximum number of nominators rewarded per validator if not available from the chain
+pub const DEFAULT_MAX_NOMINATOR_REWARDED_PER_VALIDATOR: u32 = 256;
+
+/// Number of eras slashes are deferred by if not available from the chain
+pub const DEFAULT_SLASH_DEFER_DURATION: u32 = 28;
+
+/// Perbill values (e.g. commission) are represented in parts per billion
+const PERBILL_PLANCK: f64 = 1_000_000_000.0;
+
+const MILLISECONDS_PER_DAY: f64 = 86_400_000.0;
+
+/// Base delay between retries of a failed chain storage read
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Retry a chain storage read on RPC errors, up to SYNC_RETRIES times with exponential
+/// backoff plus jitter. The expression is evaluated again on every attempt.
+/// Note: only subxt errors are retried, cache errors are returned straight away
+macro_rules! with_retry {
+    ($op:expr) => {{
+        let mut attempt: u32 = 0;
+        loop {
+            match $op.await {
+                Ok(value) => break Ok(value),
+                Err(e) if attempt < CONFIG.sync_retries => {
+                    attempt += 1;
+                    let seed = Utc::now().timestamp_subsec_millis() as u64;
+                    let delay = backoff_delay(attempt, seed);
+                    warn!(
+                        "Storage read failed ({}), retry {}/{} in {:?}",
+                        e, attempt, CONFIG.sync_retries, delay
+                    );
+                    task::sleep(delay).await;
+                }
+                Err(e) => break Err(SyncError::from(e)),
+            }
+        }
+    }};
+}
+
+/// Exponential delay for the given retry attempt (starting at 1), plus a jitter
+/// of up to the base delay
+fn backoff_delay(attempt: u32, seed: u64) -> time::Duration {
+    let exponential = RETRY_BASE_DELAY_MS.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let jitter = seed % RETRY_BASE_DELAY_MS;
+    time::Duration::from_millis(exponential + jitter)
+}
+
+/// Maximum delay between restarts of a failed subscription
+const SUBSCRIPTION_MAX_DELAY_MS: u64 = 60_000;
+
+/// Exponential delay to restart a subscription, capped at SUBSCRIPTION_MAX_DELAY_MS
+fn subscription_delay(attempt: u32, seed: u64) -> time::Duration {
+    backoff_delay(attempt, seed).min(time::Duration::from_millis(SUBSCRIPTION_MAX_DELAY_MS))
+}
+
+/// Number of times the active era is read again while not available
+const ACTIVE_ERA_MAX_ATTEMPTS: u32 = 5;
+
+/// Delay between reads of the active era while not available
+const ACTIVE_ERA_RETRY_DELAY_SECS: u64 = 6;
+
+/// Separator between the name suffix and the stash of a names index member
+const NAME_INDEX_SEPARATOR: char = '\u{0}';
+
+/// Members of the names index for a validator, one for every suffix of the lowercased
+/// name, so that any substring of the name is the prefix of one of the members,
+/// e.g. `flakes` matches the member `flakes\0{stash}` from the name `TURBOFLAKES`
+pub fn name_index_members(name: &str, stash: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    name.char_indices()
+        .map(|(i, _)| format!("{}{}{}", &name[i..], NAME_INDEX_SEPARATOR, stash))
+        .collect()
+}
+
+/// Lexicographical range of the names index members starting with the query
+pub fn name_index_range(query: &str) -> (Vec<u8>, Vec<u8>) {
+    let query = query.to_lowercase();
+    let mut min = b"[".to_vec();
+    min.extend_from_slice(query.as_bytes());
+    let mut max = b"[".to_vec();
+    max.extend_from_slice(query.as_bytes());
+    // 0xff is greater than any byte of an utf-8 encoded name
+    max.push(0xff);
+    (min, max)
+}
+
+/// Stash of a names index member
+pub fn parse_name_index_member(member: &str) -> Option<&str> {
+    member
+        .rfind(NAME_INDEX_SEPARATOR)
+        .map(|i| &member[i + NAME_INDEX_SEPARATOR.len_utf8()..])
+}
+
+/// Era and reward points of a validator active eras member, encoded as `{era}:{points}`
+pub fn parse_era_points(member: &str) -> Option<(EraIndex, u32)> {
+    let i = member.find(':')?;
+    let era_index = member[..i].parse::<EraIndex>().ok()?;
+    let points = member[i + 1..].parse::<u32>().ok()?;
+    Some((era_index, points))
+}
+
+/// Session, era and reward points of a validator sessions member, encoded as
+/// `{session}:{era}:{points}`
+pub fn parse_session_points(member: &str) -> Option<(SessionIndex, EraIndex, u32)> {
+    let mut parts = member.split(':');
+    let session_index = parts.next()?.parse::<SessionIndex>().ok()?;
+    let era_index = parts.next()?.parse::<EraIndex>().ok()?;
+    let points = parts.next()?.parse::<u32>().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((session_index, era_index, points))
+}
+
+/// Reward points earned during a session, given the era points at the end of the session
+/// and the snapshot taken at the end of the previous session. The snapshot only counts
+/// if it was taken in the same era, era points start from zero in the era after.
+/// Note: none is returned if the previous session was not tracked, e.g. right after a restart
+fn session_points_delta(
+    era_index: EraIndex,
+    points: u32,
+    snapshot_era_index: Option<EraIndex>,
+    snapshot_points: Option<u32>,
+) -> Option<u32> {
+    match snapshot_era_index {
+        Some(i) if i == era_index => Some(points.saturating_sub(snapshot_points.unwrap_or(0))),
+        Some(i) if i + 1 == era_index => Some(points),
+        _ => None,
+    }
+}
+
+/// Split the active eras members into eras and points, skipping malformed members
+/// [era1:points1, era2:points2, ..] -> ([era1, era2, ..], [points1, points2, ..])
+fn split_era_points(members: &Vec<String>) -> (Vec<EraIndex>, Vec<u32>) {
+    members
+        .iter()
+        .filter_map(|member| {
+            let era_points = parse_era_points(member);
+            if era_points.is_none() {
+                warn!("Skipping malformed era points member {}", member);
+            }
+            era_points
+        })
+        .unzip()
+}
+
+/// First era a validator was active, the number of eras since then and if the first era is
+/// only a lower bound, i.e. the validator was already active in the oldest era synced.
+/// Validators never active are given the active era and 0 eras
+fn tenure(
+    first_active_era_index: Option<EraIndex>,
+    active_era_index: EraIndex,
+    oldest_era_index: EraIndex,
+) -> (EraIndex, u32, bool) {
+    match first_active_era_index {
+        Some(first) => (
+            first,
+            active_era_index.saturating_sub(first),
+            first <= oldest_era_index,
+        ),
+        None => (active_era_index, 0, false),
+    }
+}
+
+/// Rewards claimed status of each era the validator was active. Rewards of the active
+/// era are only payable once the era ends, so the active era is not given a status
+fn rewards_claimed_status(
+    active_eras: &[EraIndex],
+    claimed_rewards: &[EraIndex],
+    active_era_index: EraIndex,
+) -> Vec<(EraIndex, bool)> {
+    active_eras
+        .iter()
+        .filter(|&&era_index| era_index < active_era_index)
+        .map(|&era_index| (era_index, claimed_rewards.contains(&era_index)))
+        .collect()
+}
+
+/// Standard deviation of the era points of a validator, 0 with fewer than two eras
+/// since there is no variation to measure yet
+fn reward_points_stddev(points: &Vec<u32>) -> f64 {
+    if points.len() < 2 {
+        return 0.0;
+    }
+    standard_deviation(&to_f64(points))
+}
+
+/// Share of the validator own stake in its total stake, 0 if the validator has no stake
+fn self_stake_ratio(own_stake: u128, nominators_stake: u128) -> f64 {
+    let total_stake = own_stake.saturating_add(nominators_stake);
+    if total_stake == 0 {
+        return 0.0;
+    }
+    own_stake as f64 / total_stake as f64
+}
+
+/// The cached active era is behind when it's not cached yet or differs from the chain
+fn is_active_era_behind(cached_era_index: Option<EraIndex>, active_era_index: EraIndex) -> bool {
+    cached_era_index != Some(active_era_index)
+}
+
+/// Eras paid since the cached active era, only the era before the active era if no era
+/// is cached yet or the cached era is ahead of the chain.
+/// Note: at most the last max_eras are returned, older eras are out of the history synced
+fn missed_eras(
+    cached_era_index: Option<EraIndex>,
+    active_era_index: EraIndex,
+    max_eras: u32,
+) -> Vec<EraIndex> {
+    let start_index = match cached_era_index {
+        Some(era_index) if era_index < active_era_index => era_index,
+        _ => active_era_index.saturating_sub(1),
+    };
+    let start_index = start_index.max(active_era_index.saturating_sub(max_eras));
+    (start_index..active_era_index).collect()
+}
+
+/// Share of the validator total stake in the total stake of the active set, 0 if the
+/// active set has no stake
+fn stake_share(total_stake: u128, total_active_stake: u128) -> f64 {
+    if total_active_stake == 0 {
+        return 0.0;
+    }
+    total_stake as f64 / total_active_stake as f64
+}
+
+/// Number of slashes of a validator, counted from a single source. The slashing spans are
+/// recorded as soon as a slash is computed, even if it is deferred, so the unapplied slashes
+/// only count for validators without slashing spans yet and are never counted twice.
+/// Note: every slash ends a span, a spans record without any span ended still means one slash
+fn count_slashes(span_index: Option<u32>, unapplied: u32) -> u32 {
+    match span_index {
+        Some(span_index) => std::cmp::max(span_index, 1),
+        None => unapplied,
+    }
+}
+
+/// Number of eras per day, none if the era duration is not known
+fn calculate_eras_per_day(
+    sessions_per_era: u32,
+    epoch_duration: u64,
+    expected_block_time: u64,
+) -> Option<f64> {
+    let era_duration = sessions_per_era as u64 * epoch_duration * expected_block_time;
+    if era_duration == 0 {
+        return None;
+    }
+    Some(MILLISECONDS_PER_DAY / era_duration as f64)
+}
+
+/// Name of the companion hash that keeps the exact planck values of a stake board
+pub fn planck_board_name(board_name: &str) -> String {
+    format!("{}:planck", board_name)
+}
+
+/// Temporary key where a stake board, or its planck hash, is rebuilt during the sync
+fn stake_board_tmp_key(board_name: &str) -> String {
+    format!("{}:tmp", Key::BoardAtEra(0, board_name.to_string()))
+}
+
+/// Digits of the largest planck value, u128::MAX
+const STAKE_MEMBER_DIGITS: usize = 39;
+
+/// Member of a stake board, the zero-padded planck stake followed by the stash.
+/// Note: sorted set scores are doubles, so stake boards are all scored 0 and ranked
+/// by member instead, which keeps the exact order of any u128 stake
+pub fn stake_board_member(stake: u128, stash: &str) -> String {
+    format!("{:0width$}:{}", stake, stash, width = STAKE_MEMBER_DIGITS)
+}
+
+/// Planck stake and stash of a stake board member
+pub fn parse_stake_board_member(member: &str) -> Option<(u128, &str)> {
+    let mut parts = member.splitn(2, ':');
+    let stake = parts.next()?.parse::<u128>().ok()?;
+    Some((stake, parts.next()?))
+}
 
 pub async fn create_substrate_node_client(
-    config: Config,
+    url: &str,
 ) -> Result<Client<DefaultConfig>, subxt::Error> {
     ClientBuilder::new()
-        .set_url(config.substrate_ws_url)
+        .set_url(url)
         .build::<DefaultConfig>()
         .await
 }
 
-pub async fn create_or_await_substrate_node_client(config: Config) -> Client<DefaultConfig> {
+/// Connect to the first endpoint available, trying the next endpoint configured on
+/// connection failure and awaiting once all of them failed.
+/// Note: endpoints are always tried in order so that the primary node is preferred, and a
+/// connection that doesn't complete within the timeout is abandoned (e.g. half-open)
+pub async fn create_or_await_substrate_node_client(
+    config: Config,
+) -> (Client<DefaultConfig>, String) {
+    let urls = config.substrate_ws_urls();
+    let connect_timeout = time::Duration::from_secs(config.substrate_ws_connect_timeout_seconds);
     loop {
-        match create_substrate_node_client(config.clone()).await {
-            Ok(client) => {
-                info!(
-                    "Connected to {} network using {} * Substrate node {} v{}",
-                    client.chain_name(),
-                    config.substrate_ws_url,
-                    client.node_name(),
-                    client.node_version()
-                );
-                break client;
-            }
-            Err(e) => {
-                error!("{}", e);
-                info!("Awaiting for Substrate node client to be ready");
-                thread::sleep(time::Duration::from_secs(6));
+        for url in urls.iter() {
+            match timeout(connect_timeout, create_substrate_node_client(url)).await {
+                Ok(Ok(client)) => {
+                    info!(
+                        "Connected to {} network using {} * Substrate node {} v{}",
+                        client.chain_name(),
+                        url,
+                        client.node_name(),
+                        client.node_version()
+                    );
+                    set_node_connected(true);
+                    return (client, url.to_string());
+                }
+                Ok(Err(e)) => {
+                    error!("{}", e);
+                    warn!("Substrate node {} not available", url);
+                }
+                Err(_) => {
+                    warn!(
+                        "Substrate node {} connection timed out after {:?}",
+                        url, connect_timeout
+                    );
+                }
             }
         }
+        // Every endpoint failed, so the node is flagged unreachable until one connects
+        set_node_connected(false);
+        info!("Awaiting for Substrate node client to be ready");
+        thread::sleep(time::Duration::from_secs(NODE_RECONNECT_DELAY_SECS));
     }
 }
 
@@ -111,10 +505,27 @@ pub enum Key {
     ValidatorAtEraScan(AccountId32),
     Validator(AccountId32),
     ActiveErasByValidator(AccountId32),
+    ValidatorNotFound(AccountId32),
+    ValidatorNamesIndex,
+    Nominator(AccountId32),
+    SyncErrors,
+    SyncLock,
+    SessionsByValidator(AccountId32),
+    SessionPointsSnapshot,
+    Presets,
+    NetworkStatsAtEra(EraIndex),
+    LimitsAtEra(EraIndex),
+    // Pub/sub channel notified every time an era is synced
+    ChannelEraSynced,
 }
 
 impl std::fmt::Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // NOTE: keys and channels are prefixed, so that instances of different chains
+        // never collide in a shared database (pub/sub channels are shared by all databases)
+        if !CONFIG.redis_key_prefix.is_empty() {
+            write!(f, "{}:", CONFIG.redis_key_prefix)?;
+        }
         match self {
             Self::Network => write!(f, "network"),
             Self::Info => write!(f, "info"),
@@ -130,6 +541,19 @@ impl std::fmt::Display for Key {
             Self::ActiveErasByValidator(stash_account) => {
                 write!(f, "{}:val:eras:active", stash_account)
             }
+            Self::ValidatorNotFound(stash_account) => write!(f, "notfound:{}", stash_account),
+            Self::ValidatorNamesIndex => write!(f, "val:names"),
+            Self::Nominator(stash_account) => write!(f, "nominator:{}", stash_account),
+            Self::SyncErrors => write!(f, "sync:errors"),
+            Self::SyncLock => write!(f, "sync:lock"),
+            Self::SessionsByValidator(stash_account) => {
+                write!(f, "{}:val:sessions", stash_account)
+            }
+            Self::SessionPointsSnapshot => write!(f, "session:points"),
+            Self::Presets => write!(f, "presets"),
+            Self::NetworkStatsAtEra(era_index) => write!(f, "{}:era:network:stats", era_index),
+            Self::LimitsAtEra(era_index) => write!(f, "{}:era:limits", era_index),
+            Self::ChannelEraSynced => write!(f, "era:synced"),
         }
     }
 }
@@ -151,11 +575,13 @@ pub enum Status {
 pub struct Sync {
     pub cache_pool: RedisPool,
     api: node_runtime::RuntimeApi<DefaultConfig>,
+    // Substrate node endpoint currently connected
+    url: String,
 }
 
 impl Sync {
     pub async fn new() -> Sync {
-        let client = create_or_await_substrate_node_client(CONFIG.clone()).await;
+        let (client, url) = create_or_await_substrate_node_client(CONFIG.clone()).await;
         let api: node_runtime::RuntimeApi<DefaultConfig> = client.clone().to_runtime_api();
 
         let properties = client.properties();
@@ -167,6 +593,7 @@ impl Sync {
         Sync {
             cache_pool: create_or_await_pool(CONFIG.clone()),
             api,
+            url,
         }
     }
 
@@ -178,6 +605,84 @@ impl Sync {
         &self.api
     }
 
+    /// Decode a pallet constant from the runtime metadata
+    fn constant<T: Decode>(
+        &self,
+        pallet: &'static str,
+        name: &'static str,
+    ) -> Result<T, SyncError> {
+        let metadata = self.client().metadata();
+        let constant = metadata.pallet(pallet)?.constant(name)?;
+        let value = T::decode(&mut &constant.value[..])?;
+        Ok(value)
+    }
+
+    /// Maximum number of nominators rewarded per validator.
+    /// Note: falls back to the previous fixed maximum if the constant is not available
+    fn max_nominator_rewarded(&self) -> u32 {
+        match self.constant::<u32>("Staking", "MaxNominatorRewardedPerValidator") {
+            Ok(max_nominator_rewarded) => max_nominator_rewarded,
+            Err(e) => {
+                warn!(
+                    "MaxNominatorRewardedPerValidator not available, using {} -> {}",
+                    DEFAULT_MAX_NOMINATOR_REWARDED_PER_VALIDATOR, e
+                );
+                DEFAULT_MAX_NOMINATOR_REWARDED_PER_VALIDATOR
+            }
+        }
+    }
+
+    /// Number of eras slashes are deferred by before being applied.
+    /// Note: falls back to the Polkadot defer duration if the constant is not available
+    fn slash_defer_duration(&self) -> u32 {
+        match self.constant::<u32>("Staking", "SlashDeferDuration") {
+            Ok(slash_defer_duration) => slash_defer_duration,
+            Err(e) => {
+                warn!(
+                    "SlashDeferDuration not available, using {} -> {}",
+                    DEFAULT_SLASH_DEFER_DURATION, e
+                );
+                DEFAULT_SLASH_DEFER_DURATION
+            }
+        }
+    }
+
+    /// Calculate the number of eras per day from the chain session and epoch constants.
+    /// Note: none if the constants are not available, e.g. chains without Babe, so that
+    /// the sync carries on without the estimates that depend on it
+    fn eras_per_day(&self) -> Option<f64> {
+        let constants = || -> Result<(u32, u64, u64), SyncError> {
+            Ok((
+                self.constant("Staking", "SessionsPerEra")?,
+                self.constant("Babe", "EpochDuration")?,
+                self.constant("Babe", "ExpectedBlockTime")?,
+            ))
+        };
+        match constants() {
+            Ok((sessions_per_era, epoch_duration, expected_block_time)) => {
+                let eras_per_day =
+                    calculate_eras_per_day(sessions_per_era, epoch_duration, expected_block_time);
+                if eras_per_day.is_none() {
+                    warn!("Eras per day not available -> era duration is 0");
+                }
+                eras_per_day
+            }
+            Err(e) => {
+                warn!("Eras per day not available -> {}", e);
+                None
+            }
+        }
+    }
+
+    /// Number of eras to sync, capped by SYNC_HISTORY_DEPTH when configured
+    async fn history_depth(&self) -> Result<u32, SyncError> {
+        let history_depth: u32 = with_retry!(self.api().storage().staking().history_depth(None))?;
+        Ok(capped_history_depth(
+            history_depth,
+            CONFIG.sync_history_depth,
+        ))
+    }
+
     async fn check_cache(&self) -> Result<(), SyncError> {
         let mut conn = self
             .cache_pool
@@ -204,30 +709,244 @@ impl Sync {
         }
     }
 
-    async fn history(&self) -> Result<(), SyncError> {
+    /// Read the chain active era, awaiting for it while not available (e.g. right
+    /// at genesis or during a stall) up to ACTIVE_ERA_MAX_ATTEMPTS times
+    async fn active_era_index_or_await(&self) -> Result<EraIndex, SyncError> {
+        let mut attempt: u32 = 0;
+        loop {
+            match with_retry!(self.api().storage().staking().active_era(None))? {
+                Some(active_era_info) => return Ok(active_era_info.index),
+                None if attempt < ACTIVE_ERA_MAX_ATTEMPTS => {
+                    attempt += 1;
+                    warn!(
+                        "Active era not available, retry {}/{} in {}s",
+                        attempt, ACTIVE_ERA_MAX_ATTEMPTS, ACTIVE_ERA_RETRY_DELAY_SECS
+                    );
+                    task::sleep(time::Duration::from_secs(ACTIVE_ERA_RETRY_DELAY_SECS)).await;
+                }
+                None => return Err(SyncError::Other("Active era not available".into())),
+            }
+        }
+    }
+
+    /// Sync all history, already synced eras are synced again if forced
+    async fn history(&self, force: Option<bool>) -> Result<(), SyncError> {
         self.ready_or_await().await;
 
         self.status(Status::Started).await?;
 
-        self.network().await?;
+        let eras_per_day = self.network().await?;
 
         let active_era = self.active_era().await?;
 
-        self.eras_history_depth(active_era).await?;
+        self.eras_history_depth(active_era, force).await?;
 
-        self.validators().await?;
+        self.validators(eras_per_day).await?;
 
         self.nominators().await?;
 
         self.active_validators().await?;
 
+        self.stake_concentration().await?;
+
+        self.clear_era_limits().await?;
+
+        self.status(Status::Finished).await?;
+
+        self.default_board().await;
+
+        self.recommended_validators().await;
+
+        Ok(())
+    }
+
+    /// Generate the default board once the sync is finished, so that it is kept for
+    /// every era synced and the rank history of the default board has no gaps.
+    /// Note: best effort, a failure is only logged and retried on the next sync
+    async fn default_board(&self) {
+        let cache = actix_web::web::Data::new(self.cache_pool.clone());
+        match generate_default_board(cache).await {
+            Ok(total) => info!(
+                "Successfully generated default board with {} validators",
+                total
+            ),
+            Err(e) => warn!("Default board not generated -> {}", e),
+        }
+    }
+
+    /// Generate the recommended board once the sync is finished, so that the
+    /// recommended flag is a cheap lookup rather than an on-demand board generation.
+    /// The board is regenerated even if complete, e.g. by a resync in the same era.
+    /// Note: best effort, a failure is only logged and retried on the next sync
+    async fn recommended_validators(&self) {
+        let cache = actix_web::web::Data::new(self.cache_pool.clone());
+        match generate_recommended_board(cache).await {
+            Ok(total) => info!("Successfully flagged {} recommended validators", total),
+            Err(e) => warn!("Recommended validators not flagged -> {}", e),
+        }
+    }
+
+    /// Record a sync error in the capped list of the most recent errors.
+    /// Note: recording is best effort, a failure is only logged so that it never
+    /// gets in the way of the sync restart
+    async fn record_error(&self, phase: &str, error: &SyncError) {
+        let record = SyncErrorRecord::new(phase, error);
+        let res = async {
+            let mut conn = self
+                .cache_pool
+                .get()
+                .await
+                .map_err(CacheError::RedisPoolError)?;
+            let payload = serde_json::to_string(&record)?;
+            let mut pipe = redis::pipe();
+            pipe.cmd("LPUSH").arg(Key::SyncErrors).arg(payload).ignore();
+            pipe.cmd("LTRIM")
+                .arg(Key::SyncErrors)
+                .arg(0)
+                .arg(SYNC_ERRORS_CAPACITY - 1)
+                .ignore();
+            let _: () = pipe
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+            Ok::<(), SyncError>(())
+        }
+        .await;
+        if let Err(e) = res {
+            warn!("Sync error not recorded -> {}", e);
+        }
+    }
+
+    /// Publish that a new era is synced and available
+    async fn notify_era_synced(&self, era_index: EraIndex) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let payload = serde_json::to_string(&EraSyncedEvent::new(era_index))?;
+        let _: () = redis::cmd("PUBLISH")
+            .arg(Key::ChannelEraSynced)
+            .arg(payload)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(())
+    }
+
+    /// Sync the eras paid and the current state of validators and nominators.
+    /// The sync is claimed first, so that the era payout event and the active era check
+    /// never sync at the same time. Returns false if the sync was already claimed, in
+    /// which case nothing is synced
+    async fn eras_payout(&self, era_indexes: &[EraIndex]) -> Result<bool, SyncError> {
+        if !self.lock_sync().await? {
+            return Ok(false);
+        }
+        let res = self.sync_eras_payout(era_indexes).await;
+        let unlocked = self.unlock_sync().await;
+        res?;
+        unlocked?;
+        Ok(true)
+    }
+
+    async fn sync_eras_payout(&self, era_indexes: &[EraIndex]) -> Result<(), SyncError> {
+        self.status(Status::Started).await?;
+        self.active_era().await?;
+        for era_index in era_indexes.iter() {
+            self.eras_history(*era_index, Some(true)).await?;
+            self.last_era_synced(*era_index).await?;
+        }
+        self.validators(self.eras_per_day()).await?;
+        self.nominators().await?;
+        self.active_validators().await?;
+        self.stake_concentration().await?;
+        self.clear_era_limits().await?;
         self.status(Status::Finished).await?;
+        self.recommended_validators().await;
+        for era_index in era_indexes.iter() {
+            self.notify_era_synced(*era_index).await?;
+        }
+        Ok(())
+    }
+
+    /// Claim the era payout sync, the lock expires after SYNC_LOCK_SECONDS
+    async fn lock_sync(&self) -> Result<bool, SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let res: Option<String> = redis::cmd("SET")
+            .arg(Key::SyncLock)
+            .arg(Utc::now().timestamp())
+            .arg("NX")
+            .arg("EX")
+            .arg(SYNC_LOCK_SECONDS)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(res.is_some())
+    }
+
+    async fn unlock_sync(&self) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let _: () = redis::cmd("DEL")
+            .arg(Key::SyncLock)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(())
+    }
 
+    /// Check the cached active era against the chain active era and sync every era paid
+    /// since the cached active era if they diverge, so that missed era payout events
+    /// don't leave the cache behind
+    async fn check_active_era(&self) -> Result<(), SyncError> {
+        self.ready_or_await().await;
+        if self.is_syncing().await? {
+            debug!("System is syncing skip active era check");
+            return Ok(());
+        }
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+        let cached_era_index: Option<EraIndex> = redis::cmd("GET")
+            .arg(Key::ActiveEra)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        let active_era_index = self.active_era_index_or_await().await?;
+        if !is_active_era_behind(cached_era_index, active_era_index) {
+            debug!("Active era {} is up to date", active_era_index);
+            return Ok(());
+        }
+        let history_depth: u32 = self.history_depth().await?;
+        let era_indexes = missed_eras(cached_era_index, active_era_index, history_depth);
+        warn!(
+            "Cached active era {:?} diverges from the chain active era {}, syncing eras {:?}",
+            cached_era_index, active_era_index, era_indexes
+        );
+        if !self.eras_payout(&era_indexes).await? {
+            debug!("Era payout already being synced skip active era check");
+        }
         Ok(())
     }
 
-    /// Sync previous era history every era payout
-    async fn subscribe_era_payout_events(&self) -> Result<(), SyncError> {
+    /// Sync previous era history every era payout.
+    /// Note: attempt is reset every time an event is received
+    async fn subscribe_era_payout_events(&self, attempt: &mut u32) -> Result<(), SyncError> {
         info!("Subscribe 'EraPaid' on-chain finalized event");
         self.ready_or_await().await;
         let client = self.client();
@@ -237,6 +956,7 @@ impl Sync {
         sub.filter_event::<node_runtime::staking::events::EraPaid>();
         while let Some(result) = sub.next().await {
             if let Ok(raw_event) = result {
+                *attempt = 0;
                 match node_runtime::staking::events::PayoutStarted::decode(&mut &raw_event.data[..])
                 {
                     Ok(event) => {
@@ -245,13 +965,9 @@ impl Sync {
                             warn!("System is syncing skip event event {:?}", event);
                             continue;
                         }
-                        self.status(Status::Started).await?;
-                        self.active_era().await?;
-                        self.eras_history(event.0, Some(true)).await?;
-                        self.validators().await?;
-                        self.active_validators().await?;
-                        self.nominators().await?;
-                        self.status(Status::Finished).await?;
+                        if !self.eras_payout(&[event.0]).await? {
+                            warn!("Era payout already being synced skip event {:?}", event);
+                        }
                     }
                     Err(e) => {
                         error!("Decoding event error: {:?}", e);
@@ -263,9 +979,10 @@ impl Sync {
         Err(SyncError::SubscriptionFinished)
     }
 
-    /// Sync all validators and nominators every session
-    #[allow(dead_code)]
-    async fn subscribe_new_session_events(&self) -> Result<(), SyncError> {
+    /// Sync the reward points earned by every validator every session.
+    /// Note: the full sync only runs every era payout, session events only write the
+    /// session points series so that they never interfere with it
+    async fn subscribe_new_session_events(&self, attempt: &mut u32) -> Result<(), SyncError> {
         info!("Starting new session subscription");
         self.ready_or_await().await;
         let client = self.client();
@@ -276,17 +993,11 @@ impl Sync {
         info!("Waiting for NewSession events");
         while let Some(result) = sub.next().await {
             if let Ok(raw_event) = result {
+                *attempt = 0;
                 match node_runtime::session::events::NewSession::decode(&mut &raw_event.data[..]) {
                     Ok(event) => {
                         info!("Successfully decoded event {:?}", event);
-                        if self.is_syncing().await? {
-                            warn!("System is syncing skip event event {:?}", event);
-                            continue;
-                        }
-                        self.status(Status::Started).await?;
-                        self.validators().await?;
-                        self.nominators().await?;
-                        self.status(Status::Finished).await?;
+                        self.session_points(event.0).await?;
                     }
                     Err(e) => {
                         error!("Decoding event error: {:?}", e);
@@ -299,15 +1010,21 @@ impl Sync {
     }
     /// Spawn history and subscription sincronization tasks
     pub fn run() {
-        spawn_and_restart_history_on_error();
+        spawn_and_restart_history_on_error(None);
         // Note: Just make a full sync every era payout event
         spawn_and_restart_era_payout_subscription_on_error();
-        // TODO: Single track events based on the feature that got changed
-        // spawn_and_restart_new_session_subscription_on_error();
+        if CONFIG.sync_session_points {
+            spawn_and_restart_new_session_subscription_on_error();
+        }
+        // Note: fallback in case an era payout event is missed
+        if CONFIG.sync_fallback_interval_seconds > 0 {
+            spawn_and_restart_sync_fallback_on_error();
+        }
     }
 
-    /// Cache network details
-    async fn network(&self) -> Result<(), SyncError> {
+    /// Cache network details, the eras per day calculated on the way are returned so that
+    /// they are only calculated once per sync
+    async fn network(&self) -> Result<Option<f64>, SyncError> {
         let mut conn = self
             .cache_pool
             .get()
@@ -332,23 +1049,48 @@ impl Sync {
             properties.ss58_format.to_string(),
         );
 
+        data.insert("substrate_node_url".to_string(), self.url.clone());
+
+        // Note: MaxNominations is only exposed as a constant by recent runtimes,
+        // otherwise the API falls back to the previous fixed maximum
+        match self.constant::<u32>("Staking", "MaxNominations") {
+            Ok(max_nominations) => {
+                data.insert("max_nominations".to_string(), max_nominations.to_string());
+            }
+            Err(e) => debug!("MaxNominations not available: {}", e),
+        }
+        let max_nominator_rewarded = self.max_nominator_rewarded();
         data.insert(
-            "substrate_node_url".to_string(),
-            env::var("SUBSTRATE_WS_URL").unwrap_or_default().into(),
+            "max_nominator_rewarded_per_validator".to_string(),
+            max_nominator_rewarded.to_string(),
         );
 
+        // Kusama and Polkadot have different era durations (6h vs 24h),
+        // so eras per day is derived from the chain constants
+        let eras_per_day = self.eras_per_day();
+        if let Some(eras_per_day) = eras_per_day {
+            data.insert("eras_per_day".to_string(), eras_per_day.to_string());
+        }
+
         // Cache genesis hash
         let genesis_hash = client.rpc().genesis_hash().await?;
         data.insert("genesis_hash".to_string(), format!("{:?}", genesis_hash));
 
-        let _: () = redis::cmd("HSET")
-            .arg(Key::Network)
-            .arg(data)
+        let mut pipe = redis::pipe();
+        pipe.cmd("HSET").arg(Key::Network).arg(data).ignore();
+        // Eras per day calculated by a previous sync are not kept if no longer available
+        if eras_per_day.is_none() {
+            pipe.cmd("HDEL")
+                .arg(Key::Network)
+                .arg("eras_per_day")
+                .ignore();
+        }
+        let _: () = pipe
             .query_async(&mut conn as &mut Connection)
             .await
             .map_err(CacheError::RedisCMDError)?;
 
-        Ok(())
+        Ok(eras_per_day)
     }
 
     /// Sync active era
@@ -358,12 +1100,8 @@ impl Sync {
             .get()
             .await
             .map_err(CacheError::RedisPoolError)?;
-        let api = self.api();
 
-        let active_era_index = match api.storage().staking().active_era(None).await? {
-            Some(active_era_info) => active_era_info.index,
-            None => return Err(SyncError::Other("Active era not available".into())),
-        };
+        let active_era_index = self.active_era_index_or_await().await?;
 
         let _: () = redis::cmd("SET")
             .arg(Key::ActiveEra)
@@ -392,6 +1130,16 @@ impl Sync {
                     "syncing_started_at".to_string(),
                     Utc::now().timestamp().to_string(),
                 );
+                for field in [
+                    "sync_eras_done",
+                    "sync_eras_total",
+                    "sync_validators_done",
+                    "sync_validators_total",
+                ]
+                .iter()
+                {
+                    data.insert(field.to_string(), "0".to_string());
+                }
             }
             Status::Finished => {
                 data.insert("syncing".to_string(), "false".to_string());
@@ -409,6 +1157,8 @@ impl Sync {
             .await
             .map_err(CacheError::RedisCMDError)?;
 
+        SYNC_IN_PROGRESS.store(matches!(status, Status::Started), Ordering::SeqCst);
+
         Ok(())
     }
 
@@ -433,7 +1183,7 @@ impl Sync {
     }
 
     /// Sync all validators currently available
-    async fn validators(&self) -> Result<(), SyncError> {
+    async fn validators(&self, eras_per_day: Option<f64>) -> Result<(), SyncError> {
         let mut conn = self
             .cache_pool
             .get()
@@ -442,19 +1192,51 @@ impl Sync {
         let api = self.api();
 
         info!("Starting validators sync");
-        let history_depth: u32 = api.storage().staking().history_depth(None).await?;
-        let active_era_index = match api.storage().staking().active_era(None).await? {
-            Some(active_era_info) => active_era_info.index,
-            None => return Err(SyncError::Other("Active era not available".into())),
+        let history_depth: u32 = self.history_depth().await?;
+        let active_era_index = self.active_era_index_or_await().await?;
+        let unapplied_slashes = self.unapplied_slashes(active_era_index).await?;
+        // NOTE: In active mode the history is only calculated for the active set, the
+        // other validators keep the inclusion rate and average reward points last known
+        let active_set: BTreeSet<AccountId32> = match CONFIG.sync_mode {
+            SyncMode::Active => with_retry!(api.storage().session().validators(None))?
+                .into_iter()
+                .collect(),
+            SyncMode::Full => BTreeSet::new(),
         };
+        // Oldest era synced, validators already active then might have been active before
+        let oldest_era_index = active_era_index.saturating_sub(history_depth);
+        let started_at = time::Instant::now();
+        // NOTE: The names index is rebuilt in a temporary key and renamed at the end
+        // so that searches never see a partial index
+        let names_index_tmp = format!("{}:tmp", Key::ValidatorNamesIndex);
+        let _: () = redis::cmd("DEL")
+            .arg(names_index_tmp.clone())
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        let mut names: u32 = 0;
+        self.clear_stake_board(BOARD_OWN_STAKE_VALIDATORS).await?;
+        let total = with_retry!(api.storage().staking().counter_for_validators(None))?;
+        self.record_sync_progress(&[
+            ("sync_validators_done", 0),
+            ("sync_validators_total", total),
+        ])
+        .await?;
         let mut validators = api.storage().staking().validators_iter(None).await?;
         let mut i: u32 = 0;
         while let Some((key, validator_prefs)) = validators.next().await? {
             let stash = get_account_id_from_storage_key(key);
             // Sync controller
-            if let Some(controller) = api.storage().staking().bonded(stash.clone(), None).await? {
+            if let Some(controller) =
+                with_retry!(api.storage().staking().bonded(stash.clone(), None))?
+            {
+                // NOTE: All writes for the stash are queued in a single pipeline
+                // and flushed once, saving a round trip per command
+                let mut pipe = redis::pipe();
                 let mut validator_data: BTreeMap<String, String> = BTreeMap::new();
                 validator_data.insert("active".to_string(), "false".to_string());
+                // Note: only validators in the active set can be oversubscribed
+                validator_data.insert("oversubscribed".to_string(), "false".to_string());
                 validator_data.insert(
                     "commission".to_string(),
                     validator_prefs.commission.deconstruct().to_string(),
@@ -462,81 +1244,215 @@ impl Sync {
                 validator_data.insert("blocked".to_string(), validator_prefs.blocked.to_string());
 
                 validator_data.insert("controller".to_string(), controller.to_string());
-                // Fetch own stake
-                let own_stake = self.get_controller_stake(&controller).await?;
+                // Fetch own stake and the eras with rewards claimed
+                let (own_stake, claimed_rewards) = self.get_controller_ledger(&controller).await?;
                 validator_data.insert("own_stake".to_string(), own_stake.to_string());
                 if own_stake != 0 {
-                    let _: () = redis::cmd("ZADD")
-                        .arg(Key::BoardAtEra(0, BOARD_OWN_STAKE_VALIDATORS.to_string()))
-                        .arg(own_stake.to_string()) // score
-                        .arg(stash.to_string()) // member
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
+                    pipe.cmd("ZADD")
+                        .arg(stake_board_tmp_key(BOARD_OWN_STAKE_VALIDATORS))
+                        .arg(0) // score
+                        .arg(stake_board_member(own_stake, &stash.to_string())) // member
+                        .ignore();
+
+                    // Note: the exact planck value is also kept in a companion hash
+                    // keyed by stash
+                    pipe.cmd("HSET")
+                        .arg(stake_board_tmp_key(&planck_board_name(
+                            BOARD_OWN_STAKE_VALIDATORS,
+                        )))
+                        .arg(stash.to_string())
+                        .arg(own_stake.to_string())
+                        .ignore();
                 }
                 // Sync payee - where the reward payment should be made
-                let payee = api.storage().staking().payee(stash.clone(), None).await?;
-                let reward_staked = if RewardDestination::<_>::Staked == payee {
-                    true
-                } else {
-                    false
+                let payee = match with_retry!(api.storage().staking().payee(stash.clone(), None))? {
+                    RewardDestination::Staked => "Staked".to_string(),
+                    RewardDestination::Stash => "Stash".to_string(),
+                    RewardDestination::Controller => "Controller".to_string(),
+                    RewardDestination::Account(account) => format!("Account({})", account),
+                    RewardDestination::None => "None".to_string(),
                 };
 
-                validator_data.insert("reward_staked".to_string(), reward_staked.to_string());
+                validator_data.insert("payee".to_string(), payee);
 
-                // Calculate inclusion rate
-                let inclusion_rate = self
-                    .calculate_inclusion_rate(
-                        &stash,
-                        active_era_index - history_depth,
-                        active_era_index,
-                    )
-                    .await?;
-                validator_data.insert("inclusion_rate".to_string(), inclusion_rate.to_string());
+                // Sync tenure - members are scored by era, so the first is the oldest
+                let first_active_era: Vec<(String, EraIndex)> = redis::cmd("ZRANGE")
+                    .arg(Key::ActiveErasByValidator(stash.clone()))
+                    .arg(0)
+                    .arg(0)
+                    .arg("WITHSCORES")
+                    .query_async(&mut conn as &mut Connection)
+                    .await
+                    .map_err(CacheError::RedisCMDError)?;
+                let (first_active_era_index, eras_validating, lower_bound) = tenure(
+                    first_active_era.first().map(|(_, era_index)| *era_index),
+                    active_era_index,
+                    oldest_era_index,
+                );
+                validator_data.insert(
+                    "first_active_era".to_string(),
+                    first_active_era_index.to_string(),
+                );
+                validator_data.insert("eras_validating".to_string(), eras_validating.to_string());
+                validator_data.insert(
+                    "first_active_era_lower_bound".to_string(),
+                    lower_bound.to_string(),
+                );
+
+                // Sync rewards claimed for the eras the validator was active
+                if let Some(claimed_rewards) = claimed_rewards {
+                    let active_eras: Vec<EraIndex> = redis::cmd("ZRANGE")
+                        .arg(Key::ActiveErasByValidator(stash.clone()))
+                        .arg(format!("{}", oldest_era_index))
+                        .arg(format!("({}", active_era_index))
+                        .arg("BYSCORE")
+                        .arg("WITHSCORES")
+                        .query_async::<_, Vec<(String, EraIndex)>>(&mut conn as &mut Connection)
+                        .await
+                        .map_err(CacheError::RedisCMDError)?
+                        .into_iter()
+                        .map(|(_, era_index)| era_index)
+                        .collect();
+                    for (era_index, claimed) in
+                        rewards_claimed_status(&active_eras, &claimed_rewards, active_era_index)
+                    {
+                        pipe.cmd("HSET")
+                            .arg(Key::ValidatorAtEra(era_index, stash.clone()))
+                            .arg("rewards_claimed")
+                            .arg(claimed.to_string())
+                            .ignore();
+                    }
+                }
+
+                if CONFIG.sync_mode == SyncMode::Full || active_set.contains(&stash) {
+                    // Calculate inclusion rate
+                    let era_index_min = active_era_index.saturating_sub(history_depth);
+                    let active_eras_count = self
+                        .calculate_active_eras_count(&stash, era_index_min, active_era_index)
+                        .await?;
+                    let inclusion_rate =
+                        inclusion_rate(active_eras_count, era_index_min, active_era_index);
+                    validator_data.insert("inclusion_rate".to_string(), inclusion_rate.to_string());
+                    validator_data.insert(
+                        "active_eras_count".to_string(),
+                        active_eras_count.to_string(),
+                    );
+                    validator_data.insert(
+                        "total_eras_window".to_string(),
+                        active_era_index.saturating_sub(era_index_min).to_string(),
+                    );
+
+                    // Calculate average reward points and how much they vary across eras
+                    let (avg_reward_points, reward_points_stddev) = self
+                        .calculate_reward_points_stats(
+                            &stash,
+                            active_era_index.saturating_sub(history_depth),
+                            active_era_index,
+                        )
+                        .await?;
+                    validator_data.insert(
+                        "avg_reward_points".to_string(),
+                        avg_reward_points.to_string(),
+                    );
+                    validator_data.insert(
+                        "reward_points_stddev".to_string(),
+                        reward_points_stddev.to_string(),
+                    );
+                }
+
+                // Estimate APR and APY, skipped if the eras per day are not known
+                if let Some(eras_per_day) = eras_per_day {
+                    let (est_apr, est_apy) = self
+                        .calculate_est_apr_apy(
+                            &stash,
+                            active_era_index.saturating_sub(EST_APR_ERAS),
+                            active_era_index,
+                            eras_per_day,
+                        )
+                        .await?;
+                    validator_data.insert("est_apr".to_string(), est_apr.to_string());
+                    validator_data.insert("est_apy".to_string(), est_apy.to_string());
+                }
 
-                // Calculate average reward points
-                let avg_reward_points = self
-                    .calculate_avg_reward_points(
+                // Sync slashes
+                let (spans, last_slash_era) = self.get_slashes(&stash).await?;
+                let slashes = count_slashes(spans, *unapplied_slashes.get(&stash).unwrap_or(&0));
+                validator_data.insert("slashes".to_string(), slashes.to_string());
+                validator_data.insert("last_slash_era".to_string(), last_slash_era.to_string());
+
+                // Calculate commission volatility
+                let commission_volatility = self
+                    .calculate_commission_volatility(
                         &stash,
-                        active_era_index - history_depth,
+                        active_era_index.saturating_sub(history_depth),
                         active_era_index,
                     )
                     .await?;
                 validator_data.insert(
-                    "avg_reward_points".to_string(),
-                    avg_reward_points.to_string(),
+                    "commission_volatility".to_string(),
+                    commission_volatility.to_string(),
                 );
 
                 // Fetch identity
                 let mut identity_data = self.get_identity(&stash, None).await?;
                 validator_data.append(&mut identity_data);
 
+                // Index name to be searched by any substring
+                if let Some(name) = validator_data.get("name").filter(|x| !x.is_empty()) {
+                    for member in name_index_members(name, &stash.to_string()) {
+                        pipe.cmd("ZADD")
+                            .arg(names_index_tmp.clone())
+                            .arg(0) // score
+                            .arg(member) // member
+                            .ignore();
+                    }
+                    names += 1;
+                }
+
                 // NOTE: Reset nominators counters
                 validator_data.insert("nominators".to_string(), "0".to_string());
                 validator_data.insert("nominators_stake".to_string(), "0".to_string());
 
                 // Cache information for the stash
-                let _: () = redis::cmd("HSET")
+                pipe.cmd("HSET")
                     .arg(Key::Validator(stash.clone()))
                     .arg(validator_data.clone())
-                    .query_async(&mut conn as &mut Connection)
-                    .await
-                    .map_err(CacheError::RedisCMDError)?;
+                    .ignore();
+
+                // The stash is known from now on
+                pipe.cmd("DEL")
+                    .arg(Key::ValidatorNotFound(stash.clone()))
+                    .ignore();
 
                 // Add stash to the sorted set board named: all
-                let _: () = redis::cmd("ZADD")
+                pipe.cmd("ZADD")
                     .arg(Key::BoardAtEra(
                         active_era_index,
                         BOARD_ALL_VALIDATORS.to_string(),
                     ))
                     .arg(0) // score
                     .arg(stash.to_string()) // member
-                    .query_async(&mut conn as &mut Connection)
-                    .await
-                    .map_err(CacheError::RedisCMDError)?;
+                    .ignore();
+
+                // Keep track of the stashes that don't accept nominations, so that they
+                // can be excluded from the boards of all and active validators
+                let key_blocked =
+                    Key::BoardAtEra(active_era_index, BOARD_BLOCKED_VALIDATORS.to_string());
+                if validator_prefs.blocked {
+                    pipe.cmd("ZADD")
+                        .arg(key_blocked)
+                        .arg(0) // score
+                        .arg(stash.to_string()) // member
+                        .ignore();
+                } else {
+                    pipe.cmd("ZREM")
+                        .arg(key_blocked)
+                        .arg(stash.to_string())
+                        .ignore();
+                }
 
                 // Cache statistical boards
-                let _: () = redis::cmd("ZADD")
+                pipe.cmd("ZADD")
                     .arg(Key::BoardAtEra(0, BOARD_JUDGEMENTS_VALIDATORS.to_string()))
                     .arg(
                         validator_data
@@ -547,11 +1463,9 @@ impl Sync {
                             .unwrap_or_default(),
                     ) // score
                     .arg(stash.to_string()) // member
-                    .query_async(&mut conn as &mut Connection)
-                    .await
-                    .map_err(CacheError::RedisCMDError)?;
+                    .ignore();
 
-                let _: () = redis::cmd("ZADD")
+                pipe.cmd("ZADD")
                     .arg(Key::BoardAtEra(
                         0,
                         BOARD_SUB_ACCOUNTS_VALIDATORS.to_string(),
@@ -565,14 +1479,54 @@ impl Sync {
                             .unwrap_or_default(),
                     ) // score
                     .arg(stash.to_string()) // member
+                    .ignore();
+
+                pipe.cmd("ZADD")
+                    .arg(Key::BoardAtEra(0, BOARD_SLASHED_VALIDATORS.to_string()))
+                    .arg(slashes) // score
+                    .arg(stash.to_string()) // member
+                    .ignore();
+
+                pipe.cmd("ZADD")
+                    .arg(Key::BoardAtEra(
+                        0,
+                        BOARD_COMMISSION_VOLATILITY_VALIDATORS.to_string(),
+                    ))
+                    .arg(commission_volatility) // score
+                    .arg(stash.to_string()) // member
+                    .ignore();
+
+                let _: () = pipe
                     .query_async(&mut conn as &mut Connection)
                     .await
                     .map_err(CacheError::RedisCMDError)?;
 
                 debug!("Successfully synced validator with stash {}", stash);
                 i += 1;
+                if i % SYNC_PROGRESS_INTERVAL == 0 {
+                    self.record_sync_progress(&[("sync_validators_done", i)])
+                        .await?;
+                }
             }
         }
+        self.record_sync_progress(&[("sync_validators_done", i)])
+            .await?;
+        self.replace_stake_board(BOARD_OWN_STAKE_VALIDATORS).await?;
+
+        if names > 0 {
+            let _: () = redis::cmd("RENAME")
+                .arg(names_index_tmp)
+                .arg(Key::ValidatorNamesIndex)
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+        } else {
+            let _: () = redis::cmd("DEL")
+                .arg(Key::ValidatorNamesIndex)
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+        }
 
         let _: () = redis::cmd("HSET")
             .arg(Key::Info)
@@ -581,9 +1535,18 @@ impl Sync {
             .await
             .map_err(CacheError::RedisCMDError)?;
 
+        let _: () = redis::cmd("HSET")
+            .arg(Key::Era(active_era_index))
+            .arg(&[("num_validators", i.to_string())])
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
         info!(
-            "Successfully synced {} validators in era {}",
-            i, active_era_index
+            "Successfully synced {} validators in era {} ({:?})",
+            i,
+            active_era_index,
+            started_at.elapsed()
         );
 
         Ok(())
@@ -599,84 +1562,157 @@ impl Sync {
         let api = self.api();
 
         info!("Starting nominators sync");
+        // NOTE: Nominations are aggregated in memory first and each validator is then
+        // written once with the absolute values, so consecutive syncs never accumulate
+        let mut totals: BTreeMap<AccountId32, NominationsTotal> = BTreeMap::new();
+        let active_era_index: EraIndex = redis::cmd("GET")
+            .arg(Key::ActiveEra)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        self.clear_stake_board(BOARD_TOTAL_STAKE_VALIDATORS).await?;
+        // NOTE: Every nominator synced is tagged with the run of the sync, which only grows,
+        // so that nominators no longer nominating can be told apart even when the sync
+        // runs again in the same era
+        let sync_run = Utc::now().timestamp_millis();
         let mut nominators = api.storage().staking().nominators_iter(None).await?;
         let mut i = 0;
+        let mut pipe = redis::pipe();
         while let Some((key, nominations)) = nominators.next().await? {
             let stash = get_account_id_from_storage_key(key);
-            if let Some(controller) = api.storage().staking().bonded(stash.clone(), None).await? {
+            if let Some(controller) =
+                with_retry!(api.storage().staking().bonded(stash.clone(), None))?
+            {
                 let nominator_stake = self.get_controller_stake(&controller).await?;
-                for validator_stash in nominations.targets.iter() {
-                    let exists: bool = redis::cmd("EXISTS")
-                        .arg(Key::Validator(validator_stash.clone()))
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
-
-                    if !exists {
-                        debug!(
-                            "Skipping validator with stash {} -> no longer available",
-                            validator_stash
-                        );
-                        continue;
-                    }
-                    let _: () = redis::cmd("HINCRBY")
-                        .arg(Key::Validator(validator_stash.clone()))
-                        .arg("nominators")
-                        .arg(1)
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
-
-                    // Since the range of values supported by HINCRBY is limited to 64 bit signed integers.
-                    // Store value as string and make calculation here
-                    let res: Option<String> = redis::cmd("HGET")
-                        .arg(Key::Validator(validator_stash.clone()))
-                        .arg("nominators_stake")
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
-                    let mut nominators_stake = match res {
-                        Some(value) => value.parse::<u128>().unwrap_or_default(),
-                        None => 0,
-                    };
-                    nominators_stake += nominator_stake;
-                    let _: () = redis::cmd("HSET")
-                        .arg(Key::Validator(validator_stash.clone()))
-                        .arg("nominators_stake")
-                        .arg(nominators_stake.to_string())
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
-
-                    // Calculate the validator total stake and add it to the board
-                    let res: Option<String> = redis::cmd("HGET")
-                        .arg(Key::Validator(validator_stash.clone()))
-                        .arg("own_stake")
-                        .query_async(&mut conn as &mut Connection)
-                        .await
-                        .map_err(CacheError::RedisCMDError)?;
-                    let mut total_stake = match res {
-                        Some(own_stake) => own_stake.parse::<u128>().unwrap_or_default(),
-                        None => 0,
-                    };
-                    total_stake += nominators_stake;
-                    if total_stake != 0 {
-                        let _: () = redis::cmd("ZADD")
-                            .arg(Key::BoardAtEra(0, BOARD_TOTAL_STAKE_VALIDATORS.to_string()))
-                            .arg(total_stake.to_string()) // score
-                            .arg(validator_stash.to_string()) // member
-                            .query_async(&mut conn as &mut Connection)
-                            .await
-                            .map_err(CacheError::RedisCMDError)?;
-                    }
-                }
+                add_nominations(&mut totals, &nominations.targets, nominator_stake);
+
+                // Cache the nominator targets
+                let targets: Vec<String> =
+                    nominations.targets.iter().map(|x| x.to_string()).collect();
+                pipe.cmd("HSET")
+                    .arg(Key::Nominator(stash.clone()))
+                    .arg(&[
+                        ("targets", targets.join(",")),
+                        ("bonded", nominator_stake.to_string()),
+                        ("era_index", active_era_index.to_string()),
+                        ("sync_run", sync_run.to_string()),
+                    ])
+                    .ignore();
             }
             i += 1;
             debug!("Successfully synced nominator with stash {}", stash);
+            if i % NOMINATORS_PIPELINE_CAPACITY == 0 {
+                let _: () = pipe
+                    .query_async(&mut conn as &mut Connection)
+                    .await
+                    .map_err(CacheError::RedisCMDError)?;
+                pipe = redis::pipe();
+            }
+        }
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        // Only validators currently available are updated
+        let validators: Vec<String> = redis::cmd("ZRANGE")
+            .arg(Key::BoardAtEra(
+                active_era_index,
+                BOARD_ALL_VALIDATORS.to_string(),
+            ))
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        for validator_stash in validators {
+            let validator_stash = match AccountId32::from_str(&validator_stash) {
+                Ok(stash) => stash,
+                Err(e) => {
+                    warn!("Skipping validator with stash {} -> {}", validator_stash, e);
+                    continue;
+                }
+            };
+            let total = totals.remove(&validator_stash).unwrap_or_default();
+
+            let res: Option<String> = redis::cmd("HGET")
+                .arg(Key::Validator(validator_stash.clone()))
+                .arg("own_stake")
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+            let own_stake = match res {
+                Some(own_stake) => own_stake.parse::<u128>().unwrap_or_default(),
+                None => 0,
+            };
+
+            // Since the range of values supported by HINCRBY is limited to 64 bit signed integers.
+            // Store stake as string
+            let ratio = self_stake_ratio(own_stake, total.stake);
+            let mut pipe = redis::pipe();
+            pipe.cmd("HSET")
+                .arg(Key::Validator(validator_stash.clone()))
+                .arg(&nominations_fields(&total, ratio))
+                .ignore();
+
+            pipe.cmd("ZADD")
+                .arg(Key::BoardAtEra(
+                    0,
+                    BOARD_SELF_STAKE_RATIO_VALIDATORS.to_string(),
+                ))
+                .arg(ratio) // score
+                .arg(validator_stash.to_string()) // member
+                .ignore();
+
+            // Calculate the validator total stake and add it to the board
+            let total_stake = own_stake + total.stake;
+            if total_stake != 0 {
+                pipe.cmd("ZADD")
+                    .arg(stake_board_tmp_key(BOARD_TOTAL_STAKE_VALIDATORS))
+                    .arg(0) // score
+                    .arg(stake_board_member(
+                        total_stake,
+                        &validator_stash.to_string(),
+                    )) // member
+                    .ignore();
+
+                pipe.cmd("HSET")
+                    .arg(stake_board_tmp_key(&planck_board_name(
+                        BOARD_TOTAL_STAKE_VALIDATORS,
+                    )))
+                    .arg(validator_stash.to_string())
+                    .arg(total_stake.to_string())
+                    .ignore();
+            }
+            let _: () = pipe
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
         }
+
+        for validator_stash in totals.keys() {
+            debug!(
+                "Skipping validator with stash {} -> no longer available",
+                validator_stash
+            );
+        }
+        self.replace_stake_board(BOARD_TOTAL_STAKE_VALIDATORS)
+            .await?;
         let _: () = redis::cmd("HSET")
             .arg(Key::Info)
-            .arg(&[("nominators", i.to_string())])
+            .arg(&[
+                ("nominators", i.to_string()),
+                ("nominators_era_index", active_era_index.to_string()),
+                ("nominators_sync_run", sync_run.to_string()),
+            ])
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        let _: () = redis::cmd("HSET")
+            .arg(Key::Era(active_era_index))
+            .arg(&[("num_nominators", i.to_string())])
             .query_async(&mut conn as &mut Connection)
             .await
             .map_err(CacheError::RedisCMDError)?;
@@ -684,6 +1720,161 @@ impl Sync {
         Ok(())
     }
 
+    /// Clear the temporary keys where a stake board and its planck hash are rebuilt
+    async fn clear_stake_board(&self, name: &str) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+        let _: () = redis::cmd("DEL")
+            .arg(stake_board_tmp_key(name))
+            .arg(stake_board_tmp_key(&planck_board_name(name)))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        Ok(())
+    }
+
+    /// Replace a stake board and its planck hash by the ones rebuilt in the temporary keys,
+    /// so that stakes of validators no longer available never linger
+    async fn replace_stake_board(&self, name: &str) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+        for board_name in &[name.to_string(), planck_board_name(name)] {
+            let key = Key::BoardAtEra(0, board_name.to_string());
+            let tmp = stake_board_tmp_key(board_name);
+            let exists: bool = redis::cmd("EXISTS")
+                .arg(tmp.clone())
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+            if exists {
+                let _: () = redis::cmd("RENAME")
+                    .arg(tmp)
+                    .arg(key)
+                    .query_async(&mut conn as &mut Connection)
+                    .await
+                    .map_err(CacheError::RedisCMDError)?;
+            } else {
+                let _: () = redis::cmd("DEL")
+                    .arg(key)
+                    .query_async(&mut conn as &mut Connection)
+                    .await
+                    .map_err(CacheError::RedisCMDError)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calculate the stake concentration of the active era once validators, active
+    /// validators and nominators are synced: the Nakamoto coefficient of the active set,
+    /// cached in the era, and the share of every validator in the total active stake
+    async fn stake_concentration(&self) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let active_era_index: EraIndex = redis::cmd("GET")
+            .arg(Key::ActiveEra)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        // Note: the exact planck values of the stake board are used, since sorted set
+        // scores are doubles
+        let stakes: BTreeMap<String, String> = redis::cmd("HGETALL")
+            .arg(Key::BoardAtEra(
+                0,
+                planck_board_name(BOARD_TOTAL_STAKE_VALIDATORS),
+            ))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        let stakes: BTreeMap<String, u128> = stakes
+            .into_iter()
+            .filter_map(|(stash, stake)| Some((stash, stake.parse::<u128>().ok()?)))
+            .collect();
+
+        let active: Vec<String> = redis::cmd("ZRANGE")
+            .arg(Key::BoardAtEra(
+                active_era_index,
+                BOARD_ACTIVE_VALIDATORS.to_string(),
+            ))
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        let active_stakes: Vec<u128> = active
+            .iter()
+            .filter_map(|stash| stakes.get(stash).copied())
+            .collect();
+        let total_active_stake: u128 = active_stakes.iter().sum();
+        let nakamoto_coefficient = nakamoto_coefficient(&active_stakes, NAKAMOTO_STAKE_SHARE);
+
+        let mut pipe = redis::pipe();
+        pipe.cmd("HSET")
+            .arg(Key::Era(active_era_index))
+            .arg(&[("nakamoto_coefficient", nakamoto_coefficient.to_string())])
+            .ignore();
+        for (stash, total_stake) in stakes.iter() {
+            let stash = match AccountId32::from_str(stash) {
+                Ok(stash) => stash,
+                Err(e) => {
+                    warn!("Skipping validator with stash {} -> {}", stash, e);
+                    continue;
+                }
+            };
+            pipe.cmd("HSET")
+                .arg(Key::Validator(stash))
+                .arg(&[(
+                    "stake_share",
+                    stake_share(*total_stake, total_active_stake).to_string(),
+                )])
+                .ignore();
+        }
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        info!(
+            "Successfully calculated stake concentration in era {} -> Nakamoto coefficient {}",
+            active_era_index, nakamoto_coefficient
+        );
+        Ok(())
+    }
+
+    /// Clear the limits shared by the boards of the active era, so that boards generated
+    /// from now on calculate them again from the data just synced
+    async fn clear_era_limits(&self) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let active_era_index: EraIndex = redis::cmd("GET")
+            .arg(Key::ActiveEra)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        let _: () = redis::cmd("DEL")
+            .arg(Key::LimitsAtEra(active_era_index))
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(())
+    }
+
     #[async_recursion]
     async fn get_identity(
         &self,
@@ -693,12 +1884,7 @@ impl Sync {
         let api = self.api();
         let mut identity_data: BTreeMap<String, String> = BTreeMap::new();
 
-        match api
-            .storage()
-            .identity()
-            .identity_of(stash.clone(), None)
-            .await?
-        {
+        match with_retry!(api.storage().identity().identity_of(stash.clone(), None))? {
             Some(identity) => {
                 debug!("identity {:?}", identity);
                 let parent = parse_identity_data(identity.info.display);
@@ -708,6 +1894,17 @@ impl Sync {
                     None => parent,
                 };
                 identity_data.insert("name".to_string(), name);
+                // Contacts
+                identity_data.insert(
+                    "email".to_string(),
+                    parse_identity_data(identity.info.email),
+                );
+                identity_data.insert(
+                    "twitter".to_string(),
+                    parse_identity_data(identity.info.twitter),
+                );
+                identity_data.insert("web".to_string(), parse_identity_data(identity.info.web));
+                identity_data.insert("riot".to_string(), parse_identity_data(identity.info.riot));
                 // Judgements: [(0, Judgement::Reasonable)]
                 let judgements = identity
                     .judgements
@@ -720,19 +1917,12 @@ impl Sync {
                     });
                 identity_data.insert("judgements".to_string(), judgements.to_string());
                 // Identity Sub-Accounts
-                let (_, subs) = api
-                    .storage()
-                    .identity()
-                    .subs_of(stash.clone(), None)
-                    .await?;
+                let (_, subs) = with_retry!(api.storage().identity().subs_of(stash.clone(), None))?;
                 identity_data.insert("sub_accounts".to_string(), subs.0.len().to_string());
             }
             None => {
-                if let Some((parent_account, data)) = api
-                    .storage()
-                    .identity()
-                    .super_of(stash.clone(), None)
-                    .await?
+                if let Some((parent_account, data)) =
+                    with_retry!(api.storage().identity().super_of(stash.clone(), None))?
                 {
                     let sub_account_name = parse_identity_data(data);
                     return self
@@ -740,6 +1930,10 @@ impl Sync {
                         .await;
                 } else {
                     identity_data.insert("name".to_string(), "".to_string());
+                    identity_data.insert("email".to_string(), "".to_string());
+                    identity_data.insert("twitter".to_string(), "".to_string());
+                    identity_data.insert("web".to_string(), "".to_string());
+                    identity_data.insert("riot".to_string(), "".to_string());
                     identity_data.insert("judgements".to_string(), "0".to_string());
                     identity_data.insert("sub_accounts".to_string(), "0".to_string());
                 }
@@ -748,35 +1942,70 @@ impl Sync {
         Ok(identity_data)
     }
 
+    /// Get the slashing span index and the last era with a nonzero slash.
+    /// Note: a slashing spans record is only created once the stash gets slashed
+    async fn get_slashes(&self, stash: &AccountId32) -> Result<(Option<u32>, EraIndex), SyncError> {
+        let api = self.api();
+        let slashes =
+            match with_retry!(api.storage().staking().slashing_spans(stash.clone(), None))? {
+                Some(spans) => (Some(spans.span_index), spans.last_nonzero_slash),
+                None => (None, 0),
+            };
+        Ok(slashes)
+    }
+
+    /// Count the unapplied slashes by validator, deferred to be applied in the next eras
+    async fn unapplied_slashes(
+        &self,
+        active_era_index: EraIndex,
+    ) -> Result<BTreeMap<AccountId32, u32>, SyncError> {
+        let api = self.api();
+        let slash_defer_duration = self.slash_defer_duration();
+        let mut unapplied: BTreeMap<AccountId32, u32> = BTreeMap::new();
+        for era_index in active_era_index..=active_era_index + slash_defer_duration {
+            let slashes = with_retry!(api.storage().staking().unapplied_slashes(era_index, None))?;
+            for slash in slashes.iter() {
+                *unapplied.entry(slash.validator.clone()).or_insert(0) += 1;
+            }
+        }
+        Ok(unapplied)
+    }
+
     async fn get_controller_stake(&self, controller: &AccountId32) -> Result<u128, SyncError> {
+        let (amount, _) = self.get_controller_ledger(controller).await?;
+        Ok(amount)
+    }
+
+    /// Active stake and eras for which the rewards have already been claimed, read from
+    /// the controller ledger at once. No claimed rewards are returned if the ledger is not
+    /// available, so that the status is left unknown
+    async fn get_controller_ledger(
+        &self,
+        controller: &AccountId32,
+    ) -> Result<(u128, Option<Vec<EraIndex>>), SyncError> {
         let api = self.api();
-        let amount = if let Some(ledger) = api
-            .storage()
-            .staking()
-            .ledger(controller.clone(), None)
-            .await?
-        {
-            ledger.active
-        } else {
-            0
+        let ledger = match with_retry!(api.storage().staking().ledger(controller.clone(), None))? {
+            Some(ledger) => (ledger.active, Some(ledger.claimed_rewards)),
+            None => (0, None),
         };
-        Ok(amount)
+        Ok(ledger)
     }
 
-    /// Calculate inclusion rate for the last depth history eras
-    async fn calculate_inclusion_rate(
+    /// Count the eras the validator was active for the last depth history eras,
+    /// between era_index_min (inclusive) and era_index_max (exclusive)
+    async fn calculate_active_eras_count(
         &self,
         stash: &AccountId32,
         era_index_min: EraIndex,
         era_index_max: EraIndex,
-    ) -> Result<f32, SyncError> {
+    ) -> Result<u32, SyncError> {
         let mut conn = self
             .cache_pool
             .get()
             .await
             .map_err(CacheError::RedisPoolError)?;
 
-        let count: f32 = redis::cmd("ZCOUNT")
+        let count: u32 = redis::cmd("ZCOUNT")
             .arg(Key::ActiveErasByValidator(stash.clone()))
             .arg(format!("{}", era_index_min))
             .arg(format!("({}", era_index_max))
@@ -784,18 +2013,16 @@ impl Sync {
             .await
             .map_err(CacheError::RedisCMDError)?;
 
-        let inclusion = count / (era_index_max as f32 - era_index_min as f32);
-
-        Ok(inclusion)
+        Ok(count)
     }
 
-    /// Calculate average reward points for all eras available
-    async fn calculate_avg_reward_points(
+    /// Calculate average and standard deviation of the reward points for all eras available
+    async fn calculate_reward_points_stats(
         &self,
         stash: &AccountId32,
         era_index_min: EraIndex,
         era_index_max: EraIndex,
-    ) -> Result<f64, SyncError> {
+    ) -> Result<(f64, f64), SyncError> {
         let mut conn = self
             .cache_pool
             .get()
@@ -815,21 +2042,129 @@ impl Sync {
 
         // To easily calculate the mean we first convert the members Vector to a points Vector
         // [era1:points1, era2:points2, ..] -> [points1, points2, ..]
-        let v: Vec<u32> = t
+        let (eras, v) = split_era_points(&t);
+
+        let avg = match CONFIG.avg_reward_points_weighting {
+            Weighting::None => mean(&v),
+            weighting => weighted_mean(
+                &to_f64(&v),
+                &era_weights(&eras, era_index_min, era_index_max, weighting),
+            ),
+        };
+
+        Ok((avg, reward_points_stddev(&v)))
+    }
+
+    /// Standard deviation of the commission of the validator in the eras it was active,
+    /// between era_index_min (inclusive) and era_index_max (exclusive)
+    async fn calculate_commission_volatility(
+        &self,
+        stash: &AccountId32,
+        era_index_min: EraIndex,
+        era_index_max: EraIndex,
+    ) -> Result<f64, SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let eras: Vec<EraIndex> = redis::cmd("ZRANGE")
+            .arg(Key::ActiveErasByValidator(stash.clone()))
+            .arg(format!("{}", era_index_min))
+            .arg(format!("({}", era_index_max))
+            .arg("BYSCORE")
+            .arg("WITHSCORES")
+            .query_async::<_, Vec<(String, EraIndex)>>(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?
             .into_iter()
-            .map(|x| {
-                let i = x.find(':').unwrap();
-                let points: u32 = String::from(&x[i + 1..x.len()]).parse().unwrap();
-                points
-            })
+            .map(|(_, era_index)| era_index)
             .collect();
 
-        let avg = mean(&v);
+        let mut pipe = redis::pipe();
+        for era_index in eras.iter() {
+            pipe.cmd("HGET")
+                .arg(Key::ValidatorAtEra(*era_index, stash.clone()))
+                .arg("commission");
+        }
+        let commissions: Vec<Option<u32>> = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        let commissions: Vec<u32> = commissions.into_iter().flatten().collect();
+
+        Ok(standard_deviation(&to_f64(&commissions)))
+    }
+
+    /// Estimate the annual percentage rate and yield for the nominators of a validator.
+    /// The return of each era is the validator share of the era reward, after commission,
+    /// over the validator total stake. The average return of the eras in which the validator
+    /// was active, between era_index_min (inclusive) and era_index_max (exclusive), is then
+    /// annualized. Validators with no active eras in the window have 0.0 for both.
+    async fn calculate_est_apr_apy(
+        &self,
+        stash: &AccountId32,
+        era_index_min: EraIndex,
+        era_index_max: EraIndex,
+        eras_per_day: f64,
+    ) -> Result<(f64, f64), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let mut returns: Vec<f64> = Vec::new();
+        for era_index in era_index_min..era_index_max {
+            let validator_data: BTreeMap<String, String> = redis::cmd("HGETALL")
+                .arg(Key::ValidatorAtEra(era_index, stash.clone()))
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+            // Validator was not active in this era
+            if validator_data.len() == 0 {
+                continue;
+            }
+            let era_data: BTreeMap<String, String> = redis::cmd("HGETALL")
+                .arg(Key::Era(era_index))
+                .query_async(&mut conn as &mut Connection)
+                .await
+                .map_err(CacheError::RedisCMDError)?;
+
+            let parse = |data: &BTreeMap<String, String>, field: &str| -> f64 {
+                data.get(field)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or_default()
+            };
+            let total_reward = parse(&era_data, "total_reward");
+            let total_reward_points = parse(&era_data, "total_reward_points");
+            let reward_points = parse(&validator_data, "reward_points");
+            let total_stake = parse(&validator_data, "total_stake");
+            let commission = parse(&validator_data, "commission") / PERBILL_PLANCK;
+            if total_reward_points == 0.0 || total_stake == 0.0 {
+                continue;
+            }
+            let validator_reward = total_reward * reward_points / total_reward_points;
+            let nominators_reward = validator_reward * (1.0 - commission);
+            returns.push(nominators_reward / total_stake);
+        }
 
-        Ok(avg)
+        if returns.len() == 0 {
+            return Ok((0.0, 0.0));
+        }
+        let avg_return = mean_f64(&returns);
+        let eras_per_year = eras_per_day * 365.0;
+        let apr = avg_return * eras_per_year;
+        let apy = (1.0 + avg_return).powf(eras_per_year) - 1.0;
+        Ok((apr, apy))
     }
 
-    /// Sync active validators for specific era
+    /// Sync active validators for specific era.
+    /// Note: the active set of an era is only snapshot while the era is active, eras
+    /// synced after they ended are never written, since validators elected without
+    /// reward points in the era can no longer be told apart from the rest
     async fn active_validators(&self) -> Result<(), SyncError> {
         let mut conn = self
             .cache_pool
@@ -838,32 +2173,78 @@ impl Sync {
             .map_err(CacheError::RedisPoolError)?;
         let api = self.api();
 
-        let active_era_index = match api.storage().staking().active_era(None).await? {
-            Some(active_era_info) => active_era_info.index,
-            None => return Err(SyncError::Other("Active era not available".into())),
-        };
-        let validators = api.storage().session().validators(None).await?;
+        let active_era_index = self.active_era_index_or_await().await?;
+        let max_nominator_rewarded = self.max_nominator_rewarded();
+        let validators = with_retry!(api.storage().session().validators(None))?;
+
+        // Validators with more nominators than the chain limit don't reward all of them.
+        // Note: the nominators are already synced, so the stakers are not read again
+        let mut pipe = redis::pipe();
         for stash in validators.iter() {
+            pipe.cmd("HGET")
+                .arg(Key::Validator(stash.clone()))
+                .arg("nominators");
+        }
+        let nominators: Vec<Option<u32>> = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        let mut pipe = redis::pipe();
+        for (stash, nominators) in validators.iter().zip(nominators.into_iter()) {
+            let oversubscribed = nominators.unwrap_or_default() > max_nominator_rewarded;
+
             // Cache information for the stash
-            let _: () = redis::cmd("HSET")
+            pipe.cmd("HSET")
                 .arg(Key::Validator(stash.clone()))
-                .arg(&[("active", "true")])
-                .query_async(&mut conn as &mut Connection)
-                .await
-                .map_err(CacheError::RedisCMDError)?;
+                .arg(&[
+                    ("active", "true".to_string()),
+                    ("oversubscribed", oversubscribed.to_string()),
+                ])
+                .ignore();
 
             // Add stash to the sorted set board named: active
-            let _: () = redis::cmd("ZADD")
+            pipe.cmd("ZADD")
                 .arg(Key::BoardAtEra(
                     active_era_index,
                     BOARD_ACTIVE_VALIDATORS.to_string(),
                 ))
                 .arg(0) // score
                 .arg(stash.to_string()) // member
-                .query_async(&mut conn as &mut Connection)
-                .await
-                .map_err(CacheError::RedisCMDError)?;
+                .ignore();
+        }
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        // Cache the boards of all and active validators without the blocked ones
+        let key_blocked = Key::BoardAtEra(active_era_index, BOARD_BLOCKED_VALIDATORS.to_string());
+        let mut pipe = redis::pipe();
+        for (board, unblocked_board) in [
+            (BOARD_ALL_VALIDATORS, BOARD_ALL_UNBLOCKED_VALIDATORS),
+            (BOARD_ACTIVE_VALIDATORS, BOARD_ACTIVE_UNBLOCKED_VALIDATORS),
+        ]
+        .iter()
+        {
+            pipe.cmd("ZDIFFSTORE")
+                .arg(Key::BoardAtEra(
+                    active_era_index,
+                    unblocked_board.to_string(),
+                ))
+                .arg(2)
+                .arg(Key::BoardAtEra(active_era_index, board.to_string()))
+                .arg(key_blocked.clone())
+                .ignore();
         }
+        pipe.cmd("HSET")
+            .arg(Key::Era(active_era_index))
+            .arg(&[("num_active", validators.len().to_string())])
+            .ignore();
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
 
         info!(
             "Successfully synced {} active validators in era {}",
@@ -875,19 +2256,70 @@ impl Sync {
     }
 
     /// Sync all era information for all history depth
-    async fn eras_history_depth(&self, active_era_index: EraIndex) -> Result<(), SyncError> {
-        let api = self.api();
-
-        let history_depth: u32 = api.storage().staking().history_depth(None).await?;
-        let start_index = active_era_index - history_depth;
-        for era_index in start_index..active_era_index {
-            self.eras_history(era_index, None).await?;
+    async fn eras_history_depth(
+        &self,
+        active_era_index: EraIndex,
+        force: Option<bool>,
+    ) -> Result<(), SyncError> {
+        let history_depth: u32 = self.history_depth().await?;
+        let start_index = active_era_index.saturating_sub(history_depth);
+        self.record_sync_progress(&[("sync_eras_done", 0), ("sync_eras_total", history_depth)])
+            .await?;
+        // NOTE: Each era writes to its own keys, so eras can be synced concurrently
+        let mut eras = stream::iter(start_index..active_era_index)
+            .map(|era_index| async move { (era_index, self.eras_history(era_index, force).await) })
+            .buffer_unordered(CONFIG.sync_era_concurrency.max(1));
+        let mut n = 0;
+        while let Some((era_index, result)) = eras.next().await {
+            result?;
+            n += 1;
+            info!("Era {} history ready ({}/{})", era_index, n, history_depth);
+            self.record_sync_progress(&[("sync_eras_done", n)]).await?;
+        }
+        if history_depth > 0 {
+            self.last_era_synced(active_era_index - 1).await?;
         }
         info!("Successfully synced {} eras history", history_depth);
 
         Ok(())
     }
 
+    /// Record the progress of the sync, fields are given as the number of items done or total
+    async fn record_sync_progress(&self, fields: &[(&str, u32)]) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let _: () = redis::cmd("HSET")
+            .arg(Key::Info)
+            .arg(fields)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(())
+    }
+
+    /// Keep track of the last era with history fully synced
+    async fn last_era_synced(&self, era_index: EraIndex) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+
+        let _: () = redis::cmd("HSET")
+            .arg(Key::Info)
+            .arg(&[("last_era_synced", era_index.to_string())])
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        Ok(())
+    }
+
     /// Sync all era information for a given era.
     ///
     /// <ErasValidatorReward<T>>;       --> collected
@@ -946,11 +2378,10 @@ impl Sync {
             .map_err(CacheError::RedisPoolError)?;
         let api = self.api();
 
-        let result = api
+        let result = with_retry!(api
             .storage()
             .staking()
-            .eras_validator_reward(era_index, None)
-            .await?;
+            .eras_validator_reward(era_index, None))?;
         let reward = match result {
             Some(v) => v,
             None => 0,
@@ -976,11 +2407,7 @@ impl Sync {
             .map_err(CacheError::RedisPoolError)?;
         let api = self.api();
 
-        let total_stake = api
-            .storage()
-            .staking()
-            .eras_total_stake(era_index, None)
-            .await?;
+        let total_stake = with_retry!(api.storage().staking().eras_total_stake(era_index, None))?;
         let _: () = redis::cmd("HSET")
             .arg(Key::Era(era_index))
             .arg(&[("total_stake", total_stake.to_string())])
@@ -1002,11 +2429,8 @@ impl Sync {
             .map_err(CacheError::RedisPoolError)?;
         let api = self.api();
 
-        let era_reward_points = api
-            .storage()
-            .staking()
-            .eras_reward_points(era_index, None)
-            .await?;
+        let era_reward_points =
+            with_retry!(api.storage().staking().eras_reward_points(era_index, None))?;
         let mut reward_points: Vec<RewardPoint> =
             Vec::with_capacity(era_reward_points.individual.len());
         for (stash, points) in era_reward_points.individual.iter() {
@@ -1065,6 +2489,18 @@ impl Sync {
         era_data.insert("max_reward_points".to_string(), max.to_string());
         let avg = mean(&reward_points);
         era_data.insert("avg_reward_points".to_string(), avg.to_string());
+        let reward_points_f64 = to_f64(&reward_points);
+        let stddev = standard_deviation(&reward_points_f64);
+        era_data.insert("stddev_reward_points".to_string(), stddev.to_string());
+        let (ci95_lower, ci95_upper) = confidence_interval_95(&reward_points_f64);
+        era_data.insert(
+            "ci95_lower_reward_points".to_string(),
+            ci95_lower.to_string(),
+        );
+        era_data.insert(
+            "ci95_upper_reward_points".to_string(),
+            ci95_upper.to_string(),
+        );
         let median = median(&mut reward_points);
         era_data.insert("median_reward_points".to_string(), median.to_string());
 
@@ -1117,6 +2553,95 @@ impl Sync {
         Ok(())
     }
 
+    /// Sync the reward points earned by every validator in the session that just ended.
+    /// Era points are cumulative, so the points of the session are the difference from
+    /// the snapshot taken at the end of the previous session
+    async fn session_points(&self, new_session_index: SessionIndex) -> Result<(), SyncError> {
+        let mut conn = self
+            .cache_pool
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+        let api = self.api();
+
+        let session_index = new_session_index.saturating_sub(1);
+
+        // If the new session starts the active era, the session that ended belongs to
+        // the previous era
+        let active_era_index = self.active_era_index_or_await().await?;
+        let start_session_index = with_retry!(api
+            .storage()
+            .staking()
+            .eras_start_session_index(active_era_index, None))?;
+        let era_index = if start_session_index == Some(new_session_index) {
+            active_era_index.saturating_sub(1)
+        } else {
+            active_era_index
+        };
+
+        let era_reward_points =
+            with_retry!(api.storage().staking().eras_reward_points(era_index, None))?;
+
+        let snapshot: BTreeMap<String, String> = redis::cmd("HGETALL")
+            .arg(Key::SessionPointsSnapshot)
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        let snapshot_era_index = snapshot
+            .get("era_index")
+            .and_then(|v| v.parse::<EraIndex>().ok());
+
+        let mut new_snapshot: BTreeMap<String, String> = BTreeMap::new();
+        new_snapshot.insert("era_index".to_string(), era_index.to_string());
+        let mut pipe = redis::pipe();
+        for (stash, points) in era_reward_points.individual.iter() {
+            new_snapshot.insert(stash.to_string(), points.to_string());
+            let snapshot_points = snapshot
+                .get(&stash.to_string())
+                .and_then(|v| v.parse::<u32>().ok());
+            let delta =
+                match session_points_delta(era_index, *points, snapshot_era_index, snapshot_points)
+                {
+                    Some(delta) => delta,
+                    None => continue,
+                };
+            let member = format!("{}:{}:{}", session_index, era_index, delta);
+            pipe.cmd("ZADD")
+                .arg(Key::SessionsByValidator(stash.clone()))
+                .arg(session_index) // score
+                .arg(member) // member
+                .ignore();
+            pipe.cmd("ZREMRANGEBYRANK")
+                .arg(Key::SessionsByValidator(stash.clone()))
+                .arg(0)
+                .arg(-(SESSION_POINTS_CAPACITY + 1))
+                .ignore();
+        }
+        pipe.cmd("DEL").arg(Key::SessionPointsSnapshot).ignore();
+        pipe.cmd("HSET")
+            .arg(Key::SessionPointsSnapshot)
+            .arg(new_snapshot)
+            .ignore();
+        let _: () = pipe
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+
+        if snapshot_era_index.is_none() {
+            info!(
+                "Session points tracked from session {} in era {}",
+                new_session_index, era_index
+            );
+        } else {
+            debug!(
+                "Successfully synced session {} points in era {}",
+                session_index, era_index
+            );
+        }
+
+        Ok(())
+    }
+
     /// Sync <ErasValidatorPrefs<T>>;
     async fn set_eras_validator_prefs<'a>(
         &self,
@@ -1126,12 +2651,11 @@ impl Sync {
     ) -> Result<(), SyncError> {
         let api = self.api();
 
-        let stash_cloned = stash.clone();
-        let validator_prefs = api
-            .storage()
-            .staking()
-            .eras_validator_prefs(era_index, stash_cloned, None)
-            .await?;
+        let validator_prefs = with_retry!(api.storage().staking().eras_validator_prefs(
+            era_index,
+            stash.clone(),
+            None
+        ))?;
         data.insert(
             "commission".to_string(),
             validator_prefs.commission.deconstruct().to_string(),
@@ -1154,11 +2678,11 @@ impl Sync {
     ) -> Result<(), SyncError> {
         let api = self.api();
 
-        let exposure = api
-            .storage()
-            .staking()
-            .eras_stakers(era_index, stash.clone(), None)
-            .await?;
+        let exposure =
+            with_retry!(api
+                .storage()
+                .staking()
+                .eras_stakers(era_index, stash.clone(), None))?;
         let mut others_stake: u128 = 0;
         for individual_exposure in exposure.others.iter() {
             others_stake += individual_exposure.value;
@@ -1185,11 +2709,11 @@ impl Sync {
     ) -> Result<(), SyncError> {
         let api = self.api();
 
-        let exposure = api
-            .storage()
-            .staking()
-            .eras_stakers_clipped(era_index, stash.clone(), None)
-            .await?;
+        let exposure = with_retry!(api.storage().staking().eras_stakers_clipped(
+            era_index,
+            stash.clone(),
+            None
+        ))?;
         let mut others_stake: u128 = 0;
         for individual_exposure in exposure.others.iter() {
             others_stake += individual_exposure.value;
@@ -1211,37 +2735,118 @@ impl Sync {
 
 pub fn spawn_and_restart_era_payout_subscription_on_error() {
     task::spawn(async {
+        let mut sync: Sync = Sync::new().await;
+        let mut attempt: u32 = 0;
         loop {
-            let sync: Sync = Sync::new().await;
-            if let Err(e) = sync.subscribe_era_payout_events().await {
-                error!("{}", e);
-                thread::sleep(time::Duration::from_millis(500));
+            if let Err(e) = sync.subscribe_era_payout_events(&mut attempt).await {
+                attempt = attempt.saturating_add(1);
+                let seed = Utc::now().timestamp_subsec_millis() as u64;
+                let delay = subscription_delay(attempt, seed);
+                error!("{} (restart subscription in {:?})", e, delay);
+                sync.record_error("era_payout_subscription", &e).await;
+                task::sleep(delay).await;
+                // Only reconnect to the node if the error was not transient
+                if !e.is_transient() {
+                    set_node_connected(false);
+                    sync = Sync::new().await;
+                }
             };
         }
     });
 }
 
-#[allow(dead_code)]
 pub fn spawn_and_restart_new_session_subscription_on_error() {
     task::spawn(async {
+        let mut sync: Sync = Sync::new().await;
+        let mut attempt: u32 = 0;
         loop {
-            let sync: Sync = Sync::new().await;
-            if let Err(e) = sync.subscribe_new_session_events().await {
-                error!("{}", e);
-                thread::sleep(time::Duration::from_millis(500));
+            if let Err(e) = sync.subscribe_new_session_events(&mut attempt).await {
+                attempt = attempt.saturating_add(1);
+                let seed = Utc::now().timestamp_subsec_millis() as u64;
+                let delay = subscription_delay(attempt, seed);
+                error!("{} (restart subscription in {:?})", e, delay);
+                sync.record_error("new_session_subscription", &e).await;
+                task::sleep(delay).await;
+                // Only reconnect to the node if the error was not transient
+                if !e.is_transient() {
+                    set_node_connected(false);
+                    sync = Sync::new().await;
+                }
             };
         }
     });
 }
 
-pub fn spawn_and_restart_history_on_error() {
+/// Check the active era every SYNC_FALLBACK_INTERVAL_SECONDS, independent of the
+/// era payout subscription
+pub fn spawn_and_restart_sync_fallback_on_error() {
     task::spawn(async {
+        let mut sync: Sync = Sync::new().await;
+        let interval = time::Duration::from_secs(CONFIG.sync_fallback_interval_seconds);
+        loop {
+            task::sleep(interval).await;
+            if let Err(e) = sync.check_active_era().await {
+                error!("{} (check again in {:?})", e, interval);
+                sync.record_error("sync_fallback", &e).await;
+                // Only reconnect to the node if the error was not transient
+                if !e.is_transient() {
+                    set_node_connected(false);
+                    sync = Sync::new().await;
+                }
+            }
+        }
+    });
+}
+
+/// Clear the syncing status and the sync lock if this process is shutting down in the middle
+/// of a sync, otherwise boards would not be generated until the next era is synced
+pub async fn clear_interrupted_sync() {
+    if !SYNC_IN_PROGRESS.load(Ordering::SeqCst) {
+        return;
+    }
+    let res = async {
+        let mut conn = create_or_await_pool(CONFIG.clone())
+            .get()
+            .await
+            .map_err(CacheError::RedisPoolError)?;
+        let mut data: BTreeMap<String, String> = BTreeMap::new();
+        data.insert("syncing".to_string(), "false".to_string());
+        data.insert(
+            "syncing_interrupted_at".to_string(),
+            Utc::now().timestamp().to_string(),
+        );
+        let _: () = redis::pipe()
+            .cmd("HSET")
+            .arg(Key::Info)
+            .arg(data)
+            .ignore()
+            .cmd("DEL")
+            .arg(Key::SyncLock)
+            .ignore()
+            .query_async(&mut conn as &mut Connection)
+            .await
+            .map_err(CacheError::RedisCMDError)?;
+        Ok::<(), SyncError>(())
+    }
+    .await;
+    match res {
+        Ok(()) => info!("Interrupted sync status cleared"),
+        Err(e) => error!("Interrupted sync status not cleared -> {}", e),
+    }
+}
+
+pub fn spawn_and_restart_history_on_error(force: Option<bool>) {
+    task::spawn(async move {
         loop {
             let sync: Sync = Sync::new().await;
-            match sync.history().await {
+            match sync.history(force).await {
                 Ok(()) => break,
                 Err(e) => {
                     error!("{}", e);
+                    sync.record_error("history", &e).await;
+                    if !e.is_transient() {
+                        set_node_connected(false);
+                    }
                     thread::sleep(time::Duration::from_millis(1000));
                 }
             }
@@ -1249,6 +2854,107 @@ pub fn spawn_and_restart_history_on_error() {
     });
 }
 
+/// Decay factor per era of the exponential weighting, e.g. the previous era is weighted
+/// 0.9 of the last one
+const EXPONENTIAL_DECAY: f64 = 0.9;
+
+/// Weight of each era in the window [era_index_min, era_index_max), higher for the
+/// most recent eras
+fn era_weights(
+    eras: &[EraIndex],
+    era_index_min: EraIndex,
+    era_index_max: EraIndex,
+    weighting: Weighting,
+) -> Vec<f64> {
+    eras.iter()
+        .map(|era_index| match weighting {
+            Weighting::None => 1.0,
+            Weighting::Linear => (era_index.saturating_sub(era_index_min) + 1) as f64,
+            Weighting::Exponential => {
+                let age = era_index_max.saturating_sub(*era_index).max(1) - 1;
+                EXPONENTIAL_DECAY.powi(age as i32)
+            }
+        })
+        .collect()
+}
+
+/// Number of nominators and total stake nominating a validator
+#[derive(Debug, Default, Clone, PartialEq)]
+struct NominationsTotal {
+    nominators: u32,
+    stake: u128,
+}
+
+fn add_nominations(
+    totals: &mut BTreeMap<AccountId32, NominationsTotal>,
+    targets: &[AccountId32],
+    nominator_stake: u128,
+) {
+    for target in targets.iter() {
+        let total = totals.entry(target.clone()).or_default();
+        total.nominators += 1;
+        total.stake += nominator_stake;
+    }
+}
+
+/// Fields of the validator written by the nominators sync. The fields are set rather than
+/// incremented, so that syncing the same nominations again never double counts them
+fn nominations_fields(total: &NominationsTotal, self_stake_ratio: f64) -> [(&str, String); 3] {
+    [
+        ("nominators", total.nominators.to_string()),
+        ("nominators_stake", total.stake.to_string()),
+        ("self_stake_ratio", self_stake_ratio.to_string()),
+    ]
+}
+
+/// Fraction of eras active in the window [era_index_min, era_index_max), the same
+/// bounds used to count the active eras, clamped to [0, 1]
+fn inclusion_rate(count: u32, era_index_min: EraIndex, era_index_max: EraIndex) -> f32 {
+    let eras = era_index_max.saturating_sub(era_index_min);
+    if eras == 0 {
+        return 0.0;
+    }
+    (count as f32 / eras as f32).min(1.0).max(0.0)
+}
+
+/// Number of validators synced between updates of the sync progress
+const SYNC_PROGRESS_INTERVAL: u32 = 50;
+
+/// Number of nominators written to the cache in one pipeline
+const NOMINATORS_PIPELINE_CAPACITY: u32 = 100;
+
+/// Share of the sync progress given to the eras history, when it is part of the sync
+const SYNC_PROGRESS_ERAS_SHARE: f32 = 0.5;
+
+/// Progress (0-1) of the sync from the eras and validators done so far, given as
+/// (done, total). Syncs without eras history (e.g. every era payout) only count the
+/// validators. A sync in progress is kept below 1 while the nominators are synced
+pub fn sync_progress(syncing: bool, eras: (u32, u32), validators: (u32, u32)) -> f32 {
+    if !syncing {
+        return 1.0;
+    }
+    let fraction = |(done, total): (u32, u32)| -> f32 {
+        if total == 0 {
+            return 0.0;
+        }
+        (done as f32 / total as f32).min(1.0)
+    };
+    let progress = if eras.1 > 0 {
+        SYNC_PROGRESS_ERAS_SHARE * fraction(eras)
+            + (1.0 - SYNC_PROGRESS_ERAS_SHARE) * fraction(validators)
+    } else {
+        fraction(validators)
+    };
+    progress.min(0.99)
+}
+
+fn capped_history_depth(chain_history_depth: u32, config_history_depth: Option<u32>) -> u32 {
+    match config_history_depth {
+        Some(depth) => depth.min(chain_history_depth),
+        None => chain_history_depth,
+    }
+}
+
 fn parse_identity_data(data: Data) -> String {
     match data {
         Data::Raw0(bytes) => parse_display_name(bytes.to_vec()),
@@ -1288,6 +2994,358 @@ fn parse_identity_data(data: Data) -> String {
     }
 }
 
+// Identity bytes are set freely on-chain, invalid utf-8 sequences are replaced
+// rather than halting the sync
 fn parse_display_name(bytes: Vec<u8>) -> String {
-    format!("{}", String::from_utf8(bytes).expect("Identity not utf-8"))
+    match String::from_utf8(bytes) {
+        Ok(name) => name,
+        Err(e) => {
+            let name = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            warn!("Identity data {} is not valid utf-8 -> {}", name, e);
+            name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_serializes_era_synced_event() {
+        assert_eq!(
+            serde_json::to_string(&EraSyncedEvent::new(42)).unwrap(),
+            r#"{"event":"era_synced","era_index":42}"#
+        );
+    }
+
+    #[test]
+    fn it_weights_recent_eras_higher() {
+        let eras = vec![7, 8, 9];
+        assert_eq!(
+            era_weights(&eras, 6, 10, Weighting::None),
+            vec![1.0, 1.0, 1.0]
+        );
+        assert_eq!(
+            era_weights(&eras, 6, 10, Weighting::Linear),
+            vec![2.0, 3.0, 4.0]
+        );
+        assert_eq!(
+            era_weights(&eras, 6, 10, Weighting::Exponential),
+            vec![0.81, 0.9, 1.0]
+        );
+    }
+
+    #[test]
+    fn it_indexes_names_by_suffix() {
+        assert_eq!(
+            name_index_members("TuRbo", "ABC"),
+            vec![
+                "turbo\u{0}ABC",
+                "urbo\u{0}ABC",
+                "rbo\u{0}ABC",
+                "bo\u{0}ABC",
+                "o\u{0}ABC"
+            ]
+        );
+        assert_eq!(
+            name_index_members("Zé", "ABC"),
+            vec!["zé\u{0}ABC", "é\u{0}ABC"]
+        );
+        assert_eq!(parse_name_index_member("rbo\u{0}ABC"), Some("ABC"));
+        assert_eq!(parse_name_index_member("rbo"), None);
+    }
+
+    #[test]
+    fn it_ranges_names_by_prefix() {
+        let (min, max) = name_index_range("RB");
+        assert_eq!(min, b"[rb".to_vec());
+        assert_eq!(max, b"[rb\xff".to_vec());
+        let member = name_index_members("turbo", "ABC")[2].clone();
+        assert!(member.as_bytes() >= &min[1..] && member.as_bytes() <= &max[1..]);
+    }
+
+    #[test]
+    fn it_caps_subscription_delay() {
+        assert_eq!(subscription_delay(1, 0), time::Duration::from_millis(500));
+        assert_eq!(subscription_delay(4, 0), time::Duration::from_millis(4000));
+        assert_eq!(
+            subscription_delay(10, 0),
+            time::Duration::from_millis(SUBSCRIPTION_MAX_DELAY_MS)
+        );
+        assert_eq!(
+            subscription_delay(u32::MAX, 999),
+            time::Duration::from_millis(SUBSCRIPTION_MAX_DELAY_MS)
+        );
+    }
+
+    #[test]
+    fn it_backs_off_exponentially_with_jitter() {
+        assert_eq!(backoff_delay(1, 0), time::Duration::from_millis(500));
+        assert_eq!(backoff_delay(2, 0), time::Duration::from_millis(1000));
+        assert_eq!(backoff_delay(3, 0), time::Duration::from_millis(2000));
+        assert_eq!(backoff_delay(3, 1234), time::Duration::from_millis(2234));
+        assert!(backoff_delay(u32::MAX, 999) < time::Duration::from_millis(500 * (1 << 16) + 500));
+    }
+
+    #[test]
+    fn it_aggregates_nominations_without_double_counting() {
+        let v1 = AccountId32::new([1; 32]);
+        let v2 = AccountId32::new([2; 32]);
+        let nominations = vec![(vec![v1.clone(), v2.clone()], 100), (vec![v1.clone()], 50)];
+
+        let aggregate = || {
+            let mut totals: BTreeMap<AccountId32, NominationsTotal> = BTreeMap::new();
+            for (targets, stake) in nominations.iter() {
+                add_nominations(&mut totals, targets, *stake);
+            }
+            totals
+        };
+        // Every sync aggregates the nominations and writes them to the validator
+        let sync = |cache: &mut BTreeMap<String, String>| {
+            let totals = aggregate();
+            let total = totals.get(&v1).cloned().unwrap_or_default();
+            let ratio = self_stake_ratio(50, total.stake);
+            for (field, value) in nominations_fields(&total, ratio).iter() {
+                cache.insert(field.to_string(), value.clone());
+            }
+            totals
+        };
+
+        // Two back to back syncs end up with the same totals
+        let mut cache: BTreeMap<String, String> = BTreeMap::new();
+        let first = sync(&mut cache);
+        let first_cache = cache.clone();
+        let totals = sync(&mut cache);
+        assert_eq!(first, totals);
+        assert_eq!(first_cache, cache);
+        assert_eq!(cache.get("nominators"), Some(&"2".to_string()));
+        assert_eq!(cache.get("nominators_stake"), Some(&"150".to_string()));
+        assert_eq!(
+            totals.get(&v1),
+            Some(&NominationsTotal {
+                nominators: 2,
+                stake: 150
+            })
+        );
+        assert_eq!(
+            totals.get(&v2),
+            Some(&NominationsTotal {
+                nominators: 1,
+                stake: 100
+            })
+        );
+    }
+
+    #[test]
+    fn it_calculates_inclusion_rate() {
+        // Synthetic active eras set covering the full window, counted with the same
+        // bounds as ZCOUNT min (max
+        let (min, max) = (100, 184);
+        let active_eras: Vec<EraIndex> = (90..200).collect();
+        let count = active_eras
+            .iter()
+            .filter(|&&era| era >= min && era < max)
+            .count() as u32;
+        assert_eq!(inclusion_rate(count, min, max), 1.0);
+        assert_eq!(inclusion_rate(42, min, max), 0.5);
+        assert_eq!(inclusion_rate(0, min, max), 0.0);
+        assert_eq!(inclusion_rate(100, min, max), 1.0);
+        assert_eq!(inclusion_rate(1, max, max), 0.0);
+    }
+
+    #[test]
+    fn it_derives_rewards_claimed_status() {
+        let active_eras: Vec<EraIndex> = vec![100, 101, 103, 104];
+        let claimed_rewards: Vec<EraIndex> = vec![99, 100, 103];
+        assert_eq!(
+            rewards_claimed_status(&active_eras, &claimed_rewards, 104),
+            vec![(100, true), (101, false), (103, true)]
+        );
+        // Ledgers with no claimed rewards, e.g. a validator never paid out
+        assert_eq!(
+            rewards_claimed_status(&active_eras, &[], 105),
+            vec![(100, false), (101, false), (103, false), (104, false)]
+        );
+    }
+
+    #[test]
+    fn it_calculates_reward_points_stddev() {
+        assert_eq!(reward_points_stddev(&vec![]), 0.0);
+        assert_eq!(reward_points_stddev(&vec![1000]), 0.0);
+        assert_eq!(reward_points_stddev(&vec![500, 500, 500]), 0.0);
+        assert_eq!(reward_points_stddev(&vec![0, 1000]), 500.0);
+    }
+
+    #[test]
+    fn it_calculates_sync_progress() {
+        assert_eq!(sync_progress(false, (0, 0), (0, 0)), 1.0);
+        assert_eq!(sync_progress(true, (0, 0), (0, 0)), 0.0);
+        // Full sync, eras history first and then validators
+        assert_eq!(sync_progress(true, (42, 84), (0, 1000)), 0.25);
+        assert_eq!(sync_progress(true, (84, 84), (500, 1000)), 0.75);
+        // Sync at every era payout, without eras history
+        assert_eq!(sync_progress(true, (0, 0), (620, 1000)), 0.62);
+        // Kept below 1 until the sync is finished
+        assert_eq!(sync_progress(true, (84, 84), (1000, 1000)), 0.99);
+        assert_eq!(sync_progress(true, (0, 0), (1010, 1000)), 0.99);
+    }
+
+    #[test]
+    fn it_caps_history_depth() {
+        assert_eq!(capped_history_depth(84, None), 84);
+        assert_eq!(capped_history_depth(84, Some(10)), 10);
+        assert_eq!(capped_history_depth(84, Some(100)), 84);
+    }
+
+    #[test]
+    fn it_parses_session_points() {
+        assert_eq!(
+            parse_session_points("12042:2005:440"),
+            Some((12042, 2005, 440))
+        );
+        assert_eq!(parse_session_points("12042:2005"), None);
+        assert_eq!(parse_session_points("12042:2005:440:1"), None);
+        assert_eq!(parse_session_points("a:2005:440"), None);
+    }
+
+    #[test]
+    fn it_calculates_session_points_delta() {
+        // same era, points since the snapshot
+        assert_eq!(
+            session_points_delta(10, 340, Some(10), Some(100)),
+            Some(240)
+        );
+        // validator not in the snapshot yet
+        assert_eq!(session_points_delta(10, 340, Some(10), None), Some(340));
+        // first session of the era, points start from zero
+        assert_eq!(session_points_delta(11, 60, Some(10), Some(340)), Some(60));
+        // previous session not tracked
+        assert_eq!(session_points_delta(11, 60, None, None), None);
+        assert_eq!(session_points_delta(11, 60, Some(8), Some(340)), None);
+    }
+
+    #[test]
+    fn it_calculates_tenure() {
+        // Active since an era within the synced history
+        assert_eq!(tenure(Some(2450), 2470, 2386), (2450, 20, false));
+        // Already active in the oldest era synced
+        assert_eq!(tenure(Some(2386), 2470, 2386), (2386, 84, true));
+        // Never active
+        assert_eq!(tenure(None, 2470, 2386), (2470, 0, false));
+    }
+
+    #[test]
+    fn it_parses_era_points() {
+        assert_eq!(parse_era_points("2470:1820"), Some((2470, 1820)));
+        assert_eq!(parse_era_points("2470:"), None);
+        assert_eq!(parse_era_points("2470"), None);
+        assert_eq!(parse_era_points("a:1820"), None);
+        assert_eq!(parse_era_points("2470:1820:1"), None);
+    }
+
+    #[test]
+    fn it_skips_malformed_era_points() {
+        let members: Vec<String> = vec!["10:100", "garbage", "11:", "12:300", ":5", "13:x"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let (eras, points) = split_era_points(&members);
+        assert_eq!(eras, vec![10, 12]);
+        assert_eq!(points, vec![100, 300]);
+        assert_eq!(mean(&points), 200.0);
+    }
+
+    #[test]
+    fn it_checks_if_active_era_is_behind() {
+        assert!(!is_active_era_behind(Some(2500), 2500));
+        assert!(is_active_era_behind(Some(2499), 2500));
+        assert!(is_active_era_behind(None, 2500));
+    }
+
+    #[test]
+    fn it_lists_missed_eras() {
+        assert_eq!(missed_eras(Some(2499), 2500, 84), vec![2499]);
+        // Every era paid since the cached active era is synced
+        assert_eq!(
+            missed_eras(Some(2496), 2500, 84),
+            vec![2496, 2497, 2498, 2499]
+        );
+        assert_eq!(missed_eras(Some(2400), 2500, 3), vec![2497, 2498, 2499]);
+        assert_eq!(missed_eras(None, 2500, 84), vec![2499]);
+        assert_eq!(missed_eras(Some(2501), 2500, 84), vec![2499]);
+        assert!(missed_eras(None, 0, 84).is_empty());
+    }
+
+    #[test]
+    fn it_calculates_stake_share() {
+        assert_eq!(stake_share(0, 0), 0.0);
+        assert_eq!(stake_share(100, 0), 0.0);
+        assert_eq!(stake_share(25, 100), 0.25);
+        assert_eq!(stake_share(0, 100), 0.0);
+    }
+
+    #[test]
+    fn it_calculates_self_stake_ratio() {
+        assert_eq!(self_stake_ratio(0, 0), 0.0);
+        assert_eq!(self_stake_ratio(25, 75), 0.25);
+        assert_eq!(self_stake_ratio(100, 0), 1.0);
+        assert_eq!(self_stake_ratio(0, 100), 0.0);
+    }
+
+    #[test]
+    fn it_counts_slashes_once() {
+        assert_eq!(count_slashes(None, 0), 0);
+        // A deferred slash not recorded in the spans yet
+        assert_eq!(count_slashes(None, 1), 1);
+        // The same slash recorded in the spans and still unapplied
+        assert_eq!(count_slashes(Some(1), 1), 1);
+        assert_eq!(count_slashes(Some(0), 1), 1);
+        assert_eq!(count_slashes(Some(3), 0), 3);
+    }
+
+    #[test]
+    fn it_calculates_eras_per_day() {
+        // Polkadot: 6 sessions of 2400 blocks of 6 seconds
+        assert_eq!(calculate_eras_per_day(6, 2400, 6000), Some(1.0));
+        // Kusama: 6 sessions of 600 blocks of 6 seconds
+        assert_eq!(calculate_eras_per_day(6, 600, 6000), Some(4.0));
+        assert_eq!(calculate_eras_per_day(6, 0, 6000), None);
+    }
+
+    #[test]
+    fn it_keeps_nearly_equal_stakes_ranked_apart() {
+        // Both stakes collapse to the same double, so their sorted set scores would tie
+        let a: u128 = (1 << 100) + 1;
+        let b: u128 = (1 << 100) + (1 << 30);
+        assert_eq!(a as f64, b as f64);
+        // Members with the same score are ranked lexicographically by Redis
+        let mut members = vec![
+            stake_board_member(b, "x"),
+            stake_board_member(u128::MAX, "w"),
+            stake_board_member(a, "y"),
+            stake_board_member(1, "z"),
+        ];
+        members.sort();
+        let ranked: Vec<(u128, &str)> = members
+            .iter()
+            .filter_map(|m| parse_stake_board_member(m))
+            .collect();
+        assert_eq!(ranked, vec![(1, "z"), (a, "y"), (b, "x"), (u128::MAX, "w")]);
+        assert_eq!(parse_stake_board_member("stash"), None);
+    }
+
+    #[test]
+    fn it_parses_identity_data() {
+        assert_eq!(parse_identity_data(Data::Raw4(*b"flux")), "flux");
+    }
+
+    #[test]
+    fn it_parses_identity_data_with_invalid_utf8() {
+        let name = parse_identity_data(Data::Raw4([0x66, 0xff, 0xfe, 0x78]));
+        assert_eq!(name, "f\u{FFFD}\u{FFFD}x");
+        // A valid name may contain the replacement character itself
+        let name = parse_identity_data(Data::Raw4([0x66, 0xef, 0xbf, 0xbd]));
+        assert_eq!(name, "f\u{FFFD}");
+    }
 }